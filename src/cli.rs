@@ -1,5 +1,16 @@
 use clap::{App, Arg};
 
+fn play_command() -> App<'static> {
+    let play = App::new("play").about("Starts the interactive game (the default)");
+    #[cfg(feature = "tui")]
+    let play = play.arg(
+        Arg::with_name("tui")
+            .long("tui")
+            .help("Uses the full-screen terminal interface"),
+    );
+    play
+}
+
 /// Builds and returns the CLI parser.
 pub fn build_cli() -> App<'static> {
     App::new("Text Combat Game")
@@ -10,6 +21,239 @@ pub fn build_cli() -> App<'static> {
             Arg::with_name("debug")
                 .short('d')
                 .long("debug")
+                .global(true)
                 .help("Enables debug mode"),
         )
+        .arg(
+            Arg::with_name("save")
+                .long("save")
+                .takes_value(true)
+                .value_name("FILE")
+                .global(true)
+                .help("Save file to use [default: savegame.json, or savegame.msgpack for binary saves]"),
+        )
+        .arg(
+            Arg::with_name("assets")
+                .long("assets")
+                .takes_value(true)
+                .value_name("DIR")
+                .global(true)
+                .help("Directory holding the content files, like entities.json and world.json [default: assets]"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("FILE")
+                .global(true)
+                .help("Settings file to read [default: config.json]"),
+        )
+        .arg(
+            Arg::with_name("packs")
+                .long("packs")
+                .takes_value(true)
+                .value_name("DIR")
+                .global(true)
+                .help("Directory holding content packs [default: packs]"),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .takes_value(true)
+                .value_name("FILE")
+                .global(true)
+                .help("Records each game session to FILE for replay"),
+        )
+        .arg(
+            Arg::with_name("script")
+                .long("script")
+                .takes_value(true)
+                .value_name("FILE")
+                .conflicts_with("batch")
+                .global(true)
+                .help("Runs the commands in FILE without prompting, then exits"),
+        )
+        .arg(
+            Arg::with_name("batch")
+                .long("batch")
+                .global(true)
+                .help("Runs commands piped on stdin without prompting, then exits"),
+        )
+        .subcommand(play_command())
+        .subcommand(
+            App::new("new-character")
+                .about("Creates a character and saves it")
+                .arg(
+                    Arg::with_name("name")
+                        .required(true)
+                        .help("Name of the new character"),
+                )
+                .arg(
+                    Arg::with_name("class")
+                        .long("class")
+                        .takes_value(true)
+                        .help("Class by number, id or name (default: the first)"),
+                )
+                .arg(
+                    Arg::with_name("race")
+                        .long("race")
+                        .takes_value(true)
+                        .help("Race by number, id or name (default: the first)"),
+                )
+                .arg(
+                    Arg::with_name("points")
+                        .long("points")
+                        .takes_value(true)
+                        .help("Buy stats instead of taking the class's, e.g. hp=4,attack=6"),
+                ),
+        )
+        .subcommand(App::new("list-characters").about("Lists the saved characters"))
+        .subcommand(App::new("graveyard").about("Lists the characters lost to permadeath"))
+        .subcommand(
+            App::new("show")
+                .about("Shows a character's stats, skills and gear")
+                .arg(
+                    Arg::with_name("character")
+                        .required(true)
+                        .help("Character number, id or name"),
+                ),
+        )
+        .subcommand(
+            App::new("simulate")
+                .about("Fights random enemies with a character without saving")
+                .arg(
+                    Arg::with_name("character")
+                        .required(true)
+                        .help("Character number, id or name"),
+                )
+                .arg(
+                    Arg::with_name("fights")
+                        .short('n')
+                        .long("fights")
+                        .takes_value(true)
+                        .default_value("1")
+                        .help("Number of fights to run"),
+                ),
+        )
+        .subcommand(
+            App::new("export")
+                .about("Writes the save file as readable JSON")
+                .arg(
+                    Arg::with_name("file")
+                        .default_value("savegame.export.json")
+                        .help("File to write"),
+                ),
+        )
+        .subcommand(
+            App::new("replay")
+                .about("Re-runs a recorded session and checks it ends the same way")
+                .arg(
+                    Arg::with_name("file")
+                        .required(true)
+                        .help("Recording made with --record"),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .help("Waits for enter before each command"),
+                ),
+        )
+        .subcommand(
+            App::new("validate").about("Checks the asset files and reports every problem found"),
+        )
+        .subcommand(
+            App::new("convert")
+                .about("Converts a content file between JSON, TOML and RON")
+                .arg(
+                    Arg::with_name("input")
+                        .required(true)
+                        .help("File to convert, format detected by extension"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .required(true)
+                        .help("File to write, format detected by extension"),
+                ),
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ErrorKind;
+
+    fn parse(args: &[&str]) -> clap::Result<clap::ArgMatches> {
+        build_cli().try_get_matches_from(std::iter::once("game").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn global_options_work_after_the_subcommand() {
+        let matches =
+            parse(&["list-characters", "--save", "a.json", "--assets", "content"]).unwrap();
+        assert_eq!(matches.value_of("save"), Some("a.json"));
+        assert_eq!(matches.value_of("assets"), Some("content"));
+        assert_eq!(matches.subcommand_name(), Some("list-characters"));
+    }
+
+    #[test]
+    fn headless_subcommands_take_their_arguments() {
+        let matches = parse(&[
+            "new-character",
+            "Mira",
+            "--class",
+            "mage",
+            "--points",
+            "hp=4,attack=6",
+        ])
+        .unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.value_of("name"), Some("Mira"));
+        assert_eq!(sub.value_of("class"), Some("mage"));
+        assert_eq!(sub.value_of("race"), None);
+        assert_eq!(sub.value_of("points"), Some("hp=4,attack=6"));
+
+        let matches = parse(&["simulate", "Mira", "-n", "20"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.value_of("fights"), Some("20"));
+        let matches = parse(&["simulate", "Mira"]).unwrap();
+        assert_eq!(
+            matches.subcommand().unwrap().1.value_of("fights"),
+            Some("1")
+        );
+
+        let matches = parse(&["export"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.value_of("file"), Some("savegame.export.json"));
+
+        let matches = parse(&["replay", "run.json", "--step"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.value_of("file"), Some("run.json"));
+        assert!(sub.is_present("step"));
+
+        let matches = parse(&["convert", "skills.json", "skills.toml"]).unwrap();
+        let (_, sub) = matches.subcommand().unwrap();
+        assert_eq!(sub.value_of("output"), Some("skills.toml"));
+    }
+
+    #[test]
+    fn missing_and_conflicting_arguments_are_rejected() {
+        for args in [
+            &["show"][..],
+            &["new-character"],
+            &["convert", "skills.json"],
+        ] {
+            assert_eq!(
+                parse(args).unwrap_err().kind(),
+                ErrorKind::MissingRequiredArgument,
+                "{:?}",
+                args
+            );
+        }
+        assert_eq!(
+            parse(&["--script", "run.txt", "--batch"])
+                .unwrap_err()
+                .kind(),
+            ErrorKind::ArgumentConflict
+        );
+    }
 }
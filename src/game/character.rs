@@ -0,0 +1,735 @@
+// src/game/character.rs
+use crate::game::combat;
+use crate::game::effect;
+use crate::game::entity::{level_hp, Entity};
+use crate::game::error::{GameError, GameResult};
+use crate::game::item::Item;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::stats::Stats;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ClassesWrapper {
+    #[serde(default)]
+    pub(crate) classes: Vec<Archetype>,
+    #[serde(default)]
+    pub(crate) races: Vec<Archetype>,
+    #[serde(default)]
+    pub(crate) point_buy: PointBuy,
+}
+
+/// A class or a race from `classes.json`. Characters are built from one of
+/// each, and get the stats, growth, skills, items and restrictions of both.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archetype {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Base stats for a class, bonuses or penalties on top for a race.
+    #[serde(default = "Stats::new")]
+    pub stats: Stats,
+    /// Stats gained on every level up.
+    #[serde(default = "Stats::new")]
+    pub growth: Stats,
+    /// Skill ids new characters know.
+    #[serde(default)]
+    pub skills: Vec<u32>,
+    /// Item ids new characters carry. Weapons and armour are equipped if
+    /// they can be.
+    #[serde(default)]
+    pub items: Vec<u32>,
+    /// Item tags that can't be equipped, e.g. `heavy`.
+    #[serde(default)]
+    pub cannot_equip: Vec<String>,
+}
+
+/// How characters buy their own stats, at creation instead of taking their
+/// class's, and later with the attribute points they earn by levelling.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PointBuy {
+    /// Points to spend on a new character.
+    pub budget: u32,
+    /// What each stat of a new character starts at before buying anything.
+    pub min: Stats,
+    /// The most each stat of a new character may be bought up to.
+    pub max: Stats,
+    /// How much one point raises each stat.
+    pub per_point: Stats,
+    /// Attribute points earned on every level up.
+    pub points_per_level: u32,
+}
+
+impl Default for PointBuy {
+    fn default() -> Self {
+        Self {
+            budget: 16,
+            min: Stats {
+                hp: 60,
+                attack: 6,
+                defense: 2,
+                agility: 2,
+            },
+            max: Stats {
+                hp: 160,
+                attack: 16,
+                defense: 10,
+                agility: 10,
+            },
+            per_point: Stats {
+                hp: 10,
+                attack: 1,
+                defense: 1,
+                agility: 1,
+            },
+            points_per_level: 3,
+        }
+    }
+}
+
+const STAT_NAMES: [&str; 4] = ["hp", "attack", "defense", "agility"];
+
+fn unknown_stat(name: &str) -> GameError {
+    GameError::InvalidInput(format!(
+        "unknown stat '{}'; use hp, attack, defense or agility",
+        name
+    ))
+}
+
+impl PointBuy {
+    /// Read an allocation like `hp=4,attack=6` into the points spent on
+    /// each stat. Stats left out get none.
+    pub fn parse(text: &str) -> GameResult<Stats> {
+        let mut points = Stats::new();
+        for part in text
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let (name, value) = part.split_once('=').ok_or_else(|| {
+                GameError::InvalidInput(format!(
+                    "'{}' is not an allocation; write it as stat=points, e.g. attack=3",
+                    part
+                ))
+            })?;
+            let value: u32 = value.trim().parse().map_err(|_| {
+                GameError::InvalidInput(format!(
+                    "'{}' is not a number of points for {}",
+                    value.trim(),
+                    name.trim()
+                ))
+            })?;
+            let stat = points
+                .get_mut(name.trim())
+                .ok_or_else(|| unknown_stat(name.trim()))?;
+            *stat += value as i32;
+        }
+        Ok(points)
+    }
+
+    /// The stats `points` buy, checked against the budget and the limits of
+    /// every stat.
+    pub fn buy(&self, points: &Stats) -> GameResult<Stats> {
+        let mut stats = self.min;
+        let mut spent = 0;
+        for name in STAT_NAMES {
+            let bought = points.get(name).unwrap_or(0);
+            let (per_point, max) = (
+                self.per_point.get(name).unwrap_or(0),
+                self.max.get(name).unwrap_or(0),
+            );
+            let stat = stats.get_mut(name).ok_or_else(|| unknown_stat(name))?;
+            *stat += bought * per_point;
+            if *stat > max {
+                return Err(GameError::InvalidInput(format!(
+                    "{} points on {} make it {}; the most it can start at is {}",
+                    bought, name, *stat, max
+                )));
+            }
+            spent += bought as u32;
+        }
+        if spent > self.budget {
+            return Err(GameError::InvalidInput(format!(
+                "that allocation costs {} points; the budget is {}",
+                spent, self.budget
+            )));
+        }
+        Ok(stats)
+    }
+
+    /// What can be bought, for prompting.
+    pub fn describe(&self) -> String {
+        let mut text = format!("Spend up to {} points:", self.budget);
+        for name in STAT_NAMES {
+            text.push_str(&format!(
+                "\n    {}: {} to {}, {:+} per point",
+                name,
+                self.min.get(name).unwrap_or(0),
+                self.max.get(name).unwrap_or(0),
+                self.per_point.get(name).unwrap_or(0)
+            ));
+        }
+        text.push_str("\nUnspent points are kept to train with later.");
+        text
+    }
+}
+
+/// XP needed in total to reach `level`: 100 for level 2, 300 for level 3,
+/// 600 for level 4 and so on.
+pub fn experience_for(level: u32) -> u32 {
+    50 * level * level.saturating_sub(1)
+}
+
+fn class(state: &GameState, id: u32) -> GameResult<&Archetype> {
+    state
+        .classes
+        .iter()
+        .find(|class| class.id == id)
+        .ok_or_else(|| GameError::Content(format!("no class with id {}", id)))
+}
+
+fn race(state: &GameState, id: u32) -> GameResult<&Archetype> {
+    state
+        .races
+        .iter()
+        .find(|race| race.id == id)
+        .ok_or_else(|| GameError::Content(format!("no race with id {}", id)))
+}
+
+/// The race and class `entity` was created with, if it was.
+fn archetypes<'a>(state: &'a GameState, entity: &Entity) -> Option<[&'a Archetype; 2]> {
+    Some([
+        race(state, entity.race?).ok()?,
+        class(state, entity.class?).ok()?,
+    ])
+}
+
+/// Base stats of a `race` `class` character.
+fn base_stats(race: &Archetype, class: &Archetype) -> Stats {
+    let mut stats = class.stats;
+    stats.apply_modifier(race.stats);
+    stats
+}
+
+/// What a `race` `class` character starts out as, for picking one, with
+/// stats bought with `points` instead of the class's. The stats include the
+/// starting equipment.
+pub fn preview(
+    state: &GameState,
+    race: &Archetype,
+    class: &Archetype,
+    points: Option<&Stats>,
+) -> GameResult<String> {
+    let character = build(state, 0, "", race.id, class.id, points)?;
+    let stats = character.stats;
+    let mut growth = class.growth;
+    growth.apply_modifier(race.growth);
+    let mut text = format!(
+        "{} {}: {} HP, {} attack, {} defense, {} agility\n    Per level: {:+} HP, {:+} attack, {:+} defense, {:+} agility",
+        race.name,
+        class.name,
+        stats.hp,
+        stats.attack,
+        stats.defense,
+        stats.agility,
+        growth.hp,
+        growth.attack,
+        growth.defense,
+        growth.agility
+    );
+    let skills: Vec<String> = class
+        .skills
+        .iter()
+        .chain(&race.skills)
+        .filter_map(|id| state.get_skill_by_id(*id))
+        .map(|skill| skill.name)
+        .collect();
+    text.push_str(&format!("\n    Skills: {}", skills.join(", ")));
+    let items: Vec<String> = class
+        .items
+        .iter()
+        .chain(&race.items)
+        .filter_map(|id| state.get_item_by_id(*id))
+        .map(|item| item.name)
+        .collect();
+    if !items.is_empty() {
+        text.push_str(&format!("\n    Items: {}", items.join(", ")));
+    }
+    let restrictions: Vec<&str> = class
+        .cannot_equip
+        .iter()
+        .chain(&race.cannot_equip)
+        .map(|tag| tag.as_str())
+        .collect();
+    if !restrictions.is_empty() {
+        text.push_str(&format!("\n    Cannot equip: {}", restrictions.join(", ")));
+    }
+    let foe = typical_foe(state, &character);
+    text.push_str(&format!("\n    {}", combat_numbers(&character, &foe)));
+    if character.attribute_points > 0 {
+        text.push_str(&format!(
+            "\n    Unspent attribute points: {}",
+            character.attribute_points
+        ));
+    }
+    Ok(text)
+}
+
+/// A new character of `race` and `class`, with its starting kit. Its stats
+/// are bought with `points` if given, see `PointBuy`, and the class's
+/// otherwise; the race's bonuses apply either way.
+pub fn build(
+    state: &GameState,
+    id: u32,
+    name: &str,
+    race: u32,
+    class: u32,
+    points: Option<&Stats>,
+) -> GameResult<Entity> {
+    let [race, class] = [self::race(state, race)?, self::class(state, class)?];
+    let mut player = Entity::new(id, name);
+    player.race = Some(race.id);
+    player.class = Some(class.id);
+    player.stats = match points {
+        Some(points) => {
+            let mut stats = state.point_buy.buy(points)?;
+            stats.apply_modifier(race.stats);
+            let spent = STAT_NAMES
+                .iter()
+                .map(|name| points.get(name).unwrap_or(0) as u32)
+                .sum::<u32>();
+            player.attribute_points = state.point_buy.budget - spent;
+            stats
+        }
+        None => base_stats(race, class),
+    };
+    player.base = Some(player.stats);
+    for id in class.skills.iter().chain(&race.skills) {
+        let skill = state
+            .get_skill_by_id(*id)
+            .ok_or_else(|| GameError::Content(format!("no skill with id {}", id)))?;
+        if !player.skills.iter().any(|known| known.id == skill.id) {
+            player.skills.push(skill);
+        }
+    }
+    for id in class.items.iter().chain(&race.items) {
+        let item = state
+            .get_item_by_id(*id)
+            .ok_or_else(|| GameError::Content(format!("no item with id {}", id)))?;
+        player.add_item_to_inventory(item.clone());
+        if !item.is_consumable() && check_equip(state, &player, &item).is_ok() {
+            // Anything for a slot that is already taken stays in the bag
+            player.equip_item(item).ok();
+        }
+    }
+    Ok(player)
+}
+
+/// Fail if the race or class of `entity` forbids equipping `item`.
+pub fn check_equip(state: &GameState, entity: &Entity, item: &Item) -> GameResult<()> {
+    let Some(archetypes) = archetypes(state, entity) else {
+        return Ok(());
+    };
+    for archetype in archetypes {
+        if let Some(tag) = item
+            .tags
+            .iter()
+            .find(|tag| archetype.cannot_equip.contains(tag))
+        {
+            return Err(GameError::InvalidState(format!(
+                "{} cannot equip {} items like {}.",
+                archetype.name, tag, item.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Stats `entity` gains on every level up. Characters made before classes
+/// existed grow like NPCs do, see `Entity::scale_to_level`.
+fn growth(state: &GameState, entity: &Entity) -> Stats {
+    if let Some([race, class]) = archetypes(state, entity) {
+        let mut growth = class.growth;
+        growth.apply_modifier(race.growth);
+        return growth;
+    }
+    let base = state.get_default_player().map_or(0, |template| {
+        template.stats.hp
+            - template
+                .equipment
+                .iter()
+                .map(|item| item.stat_modifier.hp)
+                .sum::<i32>()
+    });
+    Stats {
+        hp: level_hp(base, entity.level + 1) - level_hp(base, entity.level),
+        attack: 1,
+        defense: 1,
+        agility: 1,
+    }
+}
+
+fn equipment_modifier(entity: &Entity) -> Stats {
+    let mut modifier = Stats::new();
+    for item in &entity.equipment {
+        modifier.apply_modifier(item.stat_modifier);
+    }
+    modifier
+}
+
+/// HP of a character with a class or trained stats when fully healed: its
+/// base HP plus what it gained per level and from its equipment.
+pub fn full_hp(state: &GameState, entity: &Entity) -> Option<i32> {
+    let equipment_hp = equipment_modifier(entity).hp;
+    if let Some(base) = entity.base {
+        return Some(base.hp + equipment_hp);
+    }
+    let [race, class] = archetypes(state, entity)?;
+    Some(
+        base_stats(race, class).hp
+            + (class.growth.hp + race.growth.hp) * entity.level.saturating_sub(1) as i32
+            + equipment_hp,
+    )
+}
+
+/// The stats of `entity` at full health without its equipment, worked out
+/// for characters that don't keep track of them yet.
+fn base_of(state: &GameState, entity: &Entity) -> Stats {
+    if let Some(base) = entity.base {
+        return base;
+    }
+    let equipment = equipment_modifier(entity);
+    Stats {
+        hp: state.full_hp(entity) - equipment.hp,
+        attack: entity.stats.attack - equipment.attack,
+        defense: entity.stats.defense - equipment.defense,
+        agility: entity.stats.agility - equipment.agility,
+    }
+}
+
+/// Who to work out the chance to hit against: the current enemy, or else
+/// the player template as a stand-in for an average foe.
+fn typical_foe(state: &GameState, entity: &Entity) -> Entity {
+    state
+        .get_enemy()
+        .cloned()
+        .or_else(|_| state.get_default_player())
+        .unwrap_or_else(|_| entity.clone())
+}
+
+/// Damage range of every skill of `entity` and its chance to hit `foe`.
+pub fn combat_numbers(entity: &Entity, foe: &Entity) -> String {
+    let damage: Vec<String> = entity
+        .skills
+        .iter()
+        .filter_map(|skill| {
+            let (low, high) = effect::damage_range(skill, entity, foe)?;
+            Some(format!("{} {}-{}", skill.name, low, high))
+        })
+        .collect();
+    format!(
+        "Damage: {}; {}% to hit {}",
+        damage.join(", "),
+        combat::hit_chance(entity, foe),
+        // Only NPCs have a faction; anything else stands in for one
+        if foe.faction.is_some() {
+            foe.name.clone()
+        } else {
+            format!("a foe with {} agility", foe.stats.agility)
+        }
+    )
+}
+
+/// Spend `points` of the current character's attribute points on `stat`,
+/// or with no stat, show what there is to spend. Either way, show the
+/// combat numbers the character ends up with.
+pub fn train(state: &mut GameState, stat: Option<&str>, points: Option<&str>) -> GameResult<()> {
+    let player = state.get_player()?;
+    let per_point = state.point_buy.per_point;
+    let foe = typical_foe(state, player);
+    let Some(stat) = stat else {
+        let mut text = format!("Unspent attribute points: {}", player.attribute_points);
+        for name in STAT_NAMES {
+            text.push_str(&format!(
+                "\n    {}: {:+} per point",
+                name,
+                per_point.get(name).unwrap_or(0)
+            ));
+        }
+        text.push_str(&format!("\n{}", combat_numbers(player, &foe)));
+        say!("{}\nSpend them with 'train <stat> [points]'.", text);
+        return Ok(());
+    };
+    let name = stat.to_ascii_lowercase();
+    let gain = per_point.get(&name).ok_or_else(|| unknown_stat(stat))?;
+    let points: u32 = match points {
+        Some(points) => points
+            .trim()
+            .parse()
+            .ok()
+            .filter(|points| *points > 0)
+            .ok_or_else(|| {
+                GameError::InvalidInput(format!("'{}' is not a number of points", points))
+            })?,
+        None => 1,
+    };
+    if points > player.attribute_points {
+        return Err(GameError::InvalidState(format!(
+            "{} has {} attribute points to spend, not {}.",
+            player.name, player.attribute_points, points
+        )));
+    }
+    let mut base = base_of(state, player);
+    let before = player.clone();
+    let player = state.get_player_mut()?;
+    let increase = gain * points as i32;
+    for stats in [&mut base, &mut player.stats] {
+        *stats.get_mut(&name).ok_or_else(|| unknown_stat(stat))? += increase;
+    }
+    player.base = Some(base);
+    player.attribute_points -= points;
+    say!(
+        "{} trains {}: {} -> {}, attribute points left: {}\n    Before: {}\n    After:  {}",
+        player.name,
+        name,
+        before.stats.get(&name).unwrap_or(0),
+        player.stats.get(&name).unwrap_or(0),
+        player.attribute_points,
+        combat_numbers(&before, &foe),
+        combat_numbers(player, &foe)
+    );
+    Ok(())
+}
+
+/// Give the current character `amount` XP, levelling it up as often as that
+/// allows.
+pub fn gain_experience(state: &mut GameState, amount: u32) -> GameResult<()> {
+    state.get_player_mut()?.experience += amount;
+    loop {
+        let player = state.get_player()?;
+        if player.experience < experience_for(player.level + 1) {
+            return Ok(());
+        }
+        let growth = growth(state, player);
+        let points = state.point_buy.points_per_level;
+        let skill_points = state.skill_points_per_level;
+        let player = state.get_player_mut()?;
+        player.level += 1;
+        player.stats.apply_modifier(growth);
+        if let Some(base) = &mut player.base {
+            base.apply_modifier(growth);
+        }
+        player.attribute_points += points;
+        player.skill_points += skill_points;
+        say!(
+            "{} reaches level {}! {:+} HP, {:+} attack, {:+} defense, {:+} agility, {} attribute points to train with{}",
+            player.name,
+            player.level,
+            growth.hp,
+            growth.attack,
+            growth.defense,
+            growth.agility,
+            points,
+            if skill_points > 0 {
+                format!(", {} skill points to learn with", skill_points)
+            } else {
+                String::new()
+            }
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+    use crate::game::item::ItemType;
+
+    fn stats(hp: i32, attack: i32, defense: i32, agility: i32) -> Stats {
+        Stats {
+            hp,
+            attack,
+            defense,
+            agility,
+        }
+    }
+
+    fn item(id: u32, name: &str, item_type: ItemType, tags: &[&str]) -> Item {
+        let mut item = Item::_new(id, name, "", item_type, stats(0, 1, 1, 0));
+        item.tags = tags.iter().map(|tag| tag.to_string()).collect();
+        item
+    }
+
+    fn archetype(id: u32, name: &str, stats: Stats, growth: Stats) -> Archetype {
+        Archetype {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            stats,
+            growth,
+            skills: Vec::new(),
+            items: Vec::new(),
+            cannot_equip: Vec::new(),
+        }
+    }
+
+    /// The arena with a mage class that can't wear heavy armour, a dwarf
+    /// race that can't use bows, and their starting kit.
+    fn classes() -> GameState {
+        let mut state = fixtures::arena();
+        state.items = vec![
+            item(1, "Staff", ItemType::Weapon, &[]),
+            item(2, "Plate", ItemType::Armour, &["heavy"]),
+            item(3, "Longbow", ItemType::Weapon, &["bow"]),
+            item(4, "Robe", ItemType::Armour, &[]),
+        ];
+        let mut mage = archetype(1, "Mage", stats(50, 8, 2, 4), stats(6, 2, 0, 1));
+        mage.skills = vec![2];
+        mage.items = vec![1, 2];
+        mage.cannot_equip = vec!["heavy".to_string()];
+        let mut dwarf = archetype(1, "Dwarf", stats(10, 0, 2, -1), stats(2, 0, 1, 0));
+        dwarf.skills = vec![1];
+        dwarf.items = vec![4];
+        dwarf.cannot_equip = vec!["bow".to_string()];
+        state.classes = vec![mage];
+        state.races = vec![dwarf];
+        state
+    }
+
+    #[test]
+    fn characters_start_with_their_class_and_race_kit() {
+        let state = classes();
+        let mage = build(&state, 7, "Mira", 1, 1, None).unwrap();
+        assert_eq!(mage.stats, stats(60, 10, 6, 3));
+        let skills: Vec<u32> = mage.skills.iter().map(|skill| skill.id).collect();
+        assert_eq!(skills, [2, 1]);
+        let equipped: Vec<&str> = mage.equipment.iter().map(|i| i.name.as_str()).collect();
+        let carried: Vec<&str> = mage.inventory.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(equipped, ["Staff", "Robe"]);
+        assert_eq!(carried, ["Plate"]);
+        assert_eq!(full_hp(&state, &mage), Some(60));
+        assert!(matches!(
+            build(&state, 7, "Mira", 1, 9, None),
+            Err(GameError::Content(_))
+        ));
+    }
+
+    #[test]
+    fn classes_and_races_forbid_equipment_by_tag() {
+        let state = classes();
+        let mage = build(&state, 7, "Mira", 1, 1, None).unwrap();
+        for (id, allowed) in [(1, true), (2, false), (3, false), (4, true)] {
+            let item = state.get_item_by_id(id).unwrap();
+            assert_eq!(
+                check_equip(&state, &mage, &item).is_ok(),
+                allowed,
+                "{}",
+                item.name
+            );
+        }
+        let plate = state.get_item_by_id(2).unwrap();
+        match check_equip(&state, &mage, &plate) {
+            Err(GameError::InvalidState(message)) => {
+                assert_eq!(message, "Mage cannot equip heavy items like Plate.")
+            }
+            other => panic!("expected a restriction, got {:?}", other),
+        }
+        let template = state.get_default_player().unwrap();
+        assert!(check_equip(&state, &template, &plate).is_ok());
+    }
+
+    #[test]
+    fn levels_grow_by_class_and_race() {
+        let mut state = classes();
+        let mage = build(&state, 7, "Mira", 1, 1, None).unwrap();
+        state.create_player(mage);
+        state.set_player(1).unwrap();
+        gain_experience(&mut state, experience_for(2)).unwrap();
+        let mage = state.get_player().unwrap();
+        assert_eq!(mage.level, 2);
+        assert_eq!(mage.stats, stats(68, 12, 7, 4));
+        assert_eq!(full_hp(&state, mage), Some(68));
+    }
+
+    #[test]
+    fn allocations_parse_into_points() {
+        assert_eq!(
+            PointBuy::parse("hp=4, attack = 6,").unwrap(),
+            stats(4, 6, 0, 0)
+        );
+        for bad in ["hp", "hp=lots", "luck=2", "hp=-1"] {
+            assert!(
+                matches!(PointBuy::parse(bad), Err(GameError::InvalidInput(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn point_buy_keeps_to_the_budget_and_limits() {
+        let rules = PointBuy::default();
+        assert_eq!(rules.buy(&stats(4, 6, 3, 3)).unwrap(), stats(100, 12, 5, 5));
+        let over_budget = rules.buy(&stats(4, 6, 4, 3)).unwrap_err();
+        assert!(over_budget
+            .to_string()
+            .contains("costs 17 points; the budget is 16"));
+        let over_max = rules.buy(&stats(0, 11, 0, 0)).unwrap_err();
+        assert!(over_max
+            .to_string()
+            .contains("the most it can start at is 16"));
+        assert_eq!(rules.buy(&Stats::new()).unwrap(), rules.min);
+    }
+
+    #[test]
+    fn unspent_points_are_kept_for_training() {
+        let state = classes();
+        let mage = build(&state, 7, "Mira", 1, 1, Some(&stats(2, 4, 0, 0))).unwrap();
+        assert_eq!(mage.attribute_points, 10);
+        assert_eq!(mage.base, Some(stats(90, 10, 4, 1)));
+    }
+
+    #[test]
+    fn training_spends_attribute_points() {
+        let mut state = classes();
+        let mage = build(&state, 7, "Mira", 1, 1, Some(&Stats::new())).unwrap();
+        state.create_player(mage);
+        state.set_player(1).unwrap();
+
+        train(&mut state, Some("HP"), Some("3")).unwrap();
+        train(&mut state, Some("attack"), None).unwrap();
+        let mage = state.get_player().unwrap();
+        assert_eq!(mage.attribute_points, 12);
+        assert_eq!(mage.base, Some(stats(100, 7, 4, 1)));
+        assert_eq!(full_hp(&state, mage), Some(100));
+
+        for (stat, points) in [("attack", "13"), ("attack", "0"), ("luck", "1")] {
+            assert!(train(&mut state, Some(stat), Some(points)).is_err());
+        }
+        assert_eq!(state.get_player().unwrap().attribute_points, 12);
+    }
+
+    #[test]
+    fn training_shows_the_combat_numbers() {
+        let mut state = classes();
+        state.get_player_mut().unwrap().attribute_points = 1;
+        let (result, lines) = crate::game::log::capture(|| train(&mut state, Some("attack"), None));
+        result.unwrap();
+        assert_eq!(
+            lines[0],
+            "Mira trains attack: 4 -> 5, attribute points left: 0"
+        );
+        assert!(
+            lines[1].starts_with("    Before: Damage: Strike"),
+            "{:?}",
+            lines
+        );
+        assert!(
+            lines[2].starts_with("    After:  Damage: Strike"),
+            "{:?}",
+            lines
+        );
+    }
+}
@@ -1,23 +1,236 @@
 // src/game/combat.rs
-use crate::game::{entity::Entity, skills::Skill};
-
-pub fn combat_round(attacker: &mut Entity, defender: &mut Entity) {
-    // Example combat resolution:
-    let skill = attacker.get_skill(0);
-    let damage = attacker.damage_roll(&skill);
-    defender.stats.hp -= damage as i32;
-    println!(
-        "{} attacks {} for {} damage!",
-        attacker.name, defender.name, damage
-    );
-}
-
-// You can expand this module with more complex mechanics like turn order, critical hits, etc.
-pub fn attack_entity(attacker: &mut Entity, defender: &mut Entity, skill: &Skill) {
-    let damage = attacker.damage_roll(skill);
-    defender.stats.hp -= damage as i32;
-    println!(
-        "{} attacks {} for {} damage!",
-        attacker.name, defender.name, damage
-    );
+use crate::game::{
+    cooldown,
+    effect::{self, Summon},
+    entity::Entity,
+    error::{GameError, GameResult},
+    log::{self, say},
+    persistence::GameState,
+    skills::Skill,
+};
+
+/// Give up on a simulated fight after this many rounds.
+const MAX_SIMULATED_ROUNDS: u32 = 1000;
+/// Chance to hit, in percent, against a foe exactly as agile.
+const BASE_HIT_CHANCE: i32 = 80;
+/// Chance to hit gained or lost per point of agility over or under the foe's.
+const HIT_CHANCE_PER_AGILITY: i32 = 3;
+/// Most creatures on either side of a fight that summons can bring it to.
+const SUMMON_LIMIT: usize = 6;
+
+/// How a simulated fight ended.
+pub struct FightOutcome {
+    pub player_won: bool,
+    pub rounds: u32,
+}
+
+/// Percent chance for `attacker` to hit `defender`: better the more agile
+/// the attacker is than the defender, but never certain either way.
+pub fn hit_chance(attacker: &Entity, defender: &Entity) -> u32 {
+    let difference = attacker.stats.agility - defender.stats.agility;
+    (BASE_HIT_CHANCE + HIT_CHANCE_PER_AGILITY * difference).clamp(5, 95) as u32
+}
+
+/// An enemy's or an ally's turn: it uses its strongest skill that is
+/// ready. Returns who it summons, see `place`.
+pub fn combat_round(attacker: &mut Entity, defender: &mut Entity) -> GameResult<Vec<Summon>> {
+    // Entities without skills can't fight at all
+    attacker.get_skill(0)?;
+    let skill = cooldown::pick_skill(attacker);
+    Ok(take_turn(attacker, defender, skill.as_ref()))
+}
+
+/// The player's turn, using `skill`. Fails without taking the turn if the
+/// skill isn't ready, see `cooldown::blocker`; while channelling, the turn
+/// goes to that instead. Returns who the player summons, see `place`.
+pub fn attack_entity(
+    attacker: &mut Entity,
+    defender: &mut Entity,
+    skill: &Skill,
+) -> GameResult<Vec<Summon>> {
+    if cooldown::channelling(attacker).is_none() {
+        if let Some(reason) = cooldown::blocker(attacker, skill) {
+            return Err(GameError::InvalidState(format!(
+                "{} isn't ready: {}.",
+                skill.name, reason
+            )));
+        }
+    }
+    Ok(take_turn(attacker, defender, Some(skill)))
+}
+
+/// Take `attacker`'s turn: statuses and knockbacks may cost it the turn, a
+/// warm-up that is done takes effect, then `skill` is used, unless
+/// channelling took the turn or nothing is ready.
+fn take_turn(attacker: &mut Entity, defender: &mut Entity, skill: Option<&Skill>) -> Vec<Summon> {
+    let acts = effect::start_turn(attacker);
+    let mut summons = Vec::new();
+    if let Some(ready) = cooldown::start_turn(attacker, acts) {
+        summons.extend(effect::execute(&ready, attacker, defender));
+        if ready.channelled || defender.stats.hp <= 0 {
+            return summons;
+        }
+    } else if !acts {
+        return summons;
+    } else if let Some(channelled) = cooldown::channelling(attacker) {
+        say!("{} keeps channelling {}...", attacker.name, channelled.name);
+        return summons;
+    }
+    let Some(skill) = skill else {
+        say!("{} has nothing ready and waits.", attacker.name);
+        return summons;
+    };
+    cooldown::spend(attacker, skill);
+    if skill.warmup > 0 {
+        say!(
+            "{} begins {}; it takes effect in {}.",
+            attacker.name,
+            skill.name,
+            cooldown::turns(skill.warmup)
+        );
+        return summons;
+    }
+    summons.extend(effect::execute(skill, attacker, defender));
+    summons
+}
+
+/// Bring what a skill summoned into the fight: on the player's side, or
+/// on the enemies'.
+pub fn place(state: &mut GameState, summons: Vec<Summon>, for_player: bool) -> GameResult<()> {
+    for summon in summons {
+        let mut creature = state.npc(summon.npc)?;
+        creature.scale_to_level(summon.level);
+        creature.combat.max_hp = creature.stats.hp;
+        for _ in 0..summon.count {
+            let side = if for_player {
+                &mut state.allies
+            } else {
+                &mut state.enemies
+            };
+            if side.len() >= SUMMON_LIMIT {
+                say!("There is no room for another {}.", creature.name);
+                break;
+            }
+            side.push(creature.clone());
+            if for_player {
+                say!("{} joins the fight on your side!", creature.name);
+            } else {
+                say!("{} joins the fight!", creature.name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every ally of the player takes a turn against the current enemy, while
+/// it stands.
+pub fn allies_turn(state: &mut GameState) -> GameResult<()> {
+    let mut allies = std::mem::take(&mut state.allies);
+    let mut summons = Vec::new();
+    for ally in &mut allies {
+        match state.enemies.get_mut(state.enemy_index) {
+            Some(enemy) if enemy.stats.hp > 0 => summons.extend(combat_round(ally, enemy)?),
+            _ => break,
+        }
+    }
+    state.allies = allies;
+    place(state, summons, true)
+}
+
+/// The player's encounter is over: the timers and statuses of both sides
+/// go, so an enemy fled from keeps no buffs, and the player's allies leave.
+pub fn end_encounter(state: &mut GameState) {
+    if let Ok(player) = state.get_player_mut() {
+        cooldown::end_encounter(player);
+    }
+    for enemy in &mut state.enemies {
+        cooldown::end_encounter(enemy);
+    }
+    state.allies.clear();
+}
+
+/// Fight silently until one side drops, each side using its strongest
+/// ready skill, with cooldowns, charges and warm-ups as in the game.
+/// The player strikes first, as with `attack` in the game. Summons are
+/// left out.
+pub fn simulate_fight(mut player: Entity, mut enemy: Entity) -> GameResult<FightOutcome> {
+    player.get_skill(0)?;
+    enemy.get_skill(0)?;
+    cooldown::end_encounter(&mut player);
+    cooldown::end_encounter(&mut enemy);
+    player.combat.max_hp = player.stats.hp;
+    enemy.combat.max_hp = enemy.stats.hp;
+    let (outcome, _) = log::capture(|| {
+        for rounds in 1..=MAX_SIMULATED_ROUNDS {
+            let skill = cooldown::pick_skill(&player);
+            take_turn(&mut player, &mut enemy, skill.as_ref());
+            if player.stats.hp > 0 && enemy.stats.hp > 0 {
+                let skill = cooldown::pick_skill(&enemy);
+                take_turn(&mut enemy, &mut player, skill.as_ref());
+            }
+            if enemy.stats.hp <= 0 || player.stats.hp <= 0 {
+                return Some(FightOutcome {
+                    player_won: player.stats.hp > 0,
+                    rounds,
+                });
+            }
+        }
+        None
+    });
+    outcome.ok_or_else(|| {
+        GameError::InvalidState(format!(
+            "{} and {} were still standing after {} rounds",
+            player.name, enemy.name, MAX_SIMULATED_ROUNDS
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::effect::{Effect, Status, Target};
+    use crate::game::fixtures;
+    use crate::game::rng;
+    use crate::game::stats::Stats;
+
+    #[test]
+    fn ending_an_encounter_takes_statuses_off_enemies_too() {
+        let mut state = fixtures::arena();
+        let mut war_cry = fixtures::skill(3, "War Cry", 0);
+        war_cry.effects = vec![Effect::ApplyStatus {
+            status: Status {
+                name: "emboldened".to_string(),
+                turns: 5,
+                modifier: Stats {
+                    hp: 0,
+                    attack: 4,
+                    defense: 0,
+                    agility: 0,
+                },
+                per_turn: 0,
+                stun: false,
+            },
+            target: Target::User,
+            chance: 100,
+        }];
+        let (player, enemy) = state.get_combatants().unwrap();
+        effect::execute(&war_cry, enemy, player);
+        assert_eq!(state.get_enemy().unwrap().stats.attack, 6);
+
+        end_encounter(&mut state);
+        let enemy = state.get_enemy().unwrap();
+        assert_eq!(enemy.stats.attack, 2);
+        assert!(enemy.combat.is_idle());
+    }
+
+    #[test]
+    fn simulated_fights_keep_to_cooldowns() {
+        rng::seed(1);
+        let mut meteor = fixtures::skill(3, "Meteor", 10);
+        meteor.cooldown = 1000;
+        let mira = fixtures::fighter(1, "Mira", 60, 4, vec![meteor]);
+        let goblin = fixtures::arena().npc(100).unwrap();
+        let outcome = simulate_fight(mira, goblin).unwrap();
+        assert!(!outcome.player_won);
+    }
 }
@@ -0,0 +1,1017 @@
+// src/game/command.rs
+use crate::game::character::Archetype;
+use crate::game::entity::Entity;
+use crate::game::error::{GameError, GameResult};
+use crate::game::item::Item;
+use crate::game::quest::Quest;
+use crate::game::skills::Skill;
+
+/// What kind of value a command argument refers to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArgKind {
+    /// A skill of the current character.
+    Skill,
+    /// An item in the current character's inventory.
+    Item,
+    /// An item the current character has equipped.
+    Equipment,
+    /// One of the current enemies.
+    Enemy,
+    /// One of the saved characters.
+    Character,
+    /// A command name.
+    Command,
+    /// A new name.
+    Name,
+    /// A file path.
+    File,
+    /// Any NPC definition, not only the current enemies.
+    Npc,
+    /// Any item definition, not only what the character carries.
+    AnyItem,
+    /// A stat name.
+    Stat,
+    /// A whole number.
+    Number,
+    /// `on` or `off`.
+    Switch,
+    /// An exit of the current room.
+    Direction,
+    /// An item lying on the floor of the current room.
+    Floor,
+    /// Any quest.
+    Quest,
+    /// Someone in the current room to talk to.
+    Person,
+    /// An item in the open shop.
+    Stock,
+    /// A character class.
+    Class,
+    /// A character race.
+    Race,
+    /// A skill in one of the current character's skill trees.
+    TreeSkill,
+    /// Points bought per stat, e.g. `hp=4,attack=6`.
+    Points,
+}
+
+impl ArgKind {
+    /// Whether the argument picks something from a list, see `resolve_index`.
+    pub fn is_reference(&self) -> bool {
+        !matches!(
+            self,
+            ArgKind::Command
+                | ArgKind::File
+                | ArgKind::Name
+                | ArgKind::Stat
+                | ArgKind::Number
+                | ArgKind::Switch
+                | ArgKind::Direction
+                | ArgKind::Points
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    pub kind: ArgKind,
+    pub required: bool,
+}
+
+/// A command the player can type, with everything needed to parse it and
+/// explain it.
+#[derive(Debug)]
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    pub args: &'static [ArgSpec],
+    pub help: &'static str,
+    /// Only available with `--debug`.
+    pub debug: bool,
+}
+
+const fn required(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec {
+        name,
+        kind,
+        required: false,
+    }
+}
+
+/// Commands of the main menu (`>.` prompt).
+pub const MENU_COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "start",
+        aliases: &["1"],
+        args: &[],
+        help: "Start game.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "cc",
+        aliases: &["2", "create"],
+        args: &[
+            optional("name", ArgKind::Name),
+            optional("class", ArgKind::Class),
+            optional("race", ArgKind::Race),
+            optional("points", ArgKind::Points),
+        ],
+        help: "Create a new character, previewing its stats before you commit. Give points to buy its stats instead of taking the class's.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "lc",
+        aliases: &["3", "load"],
+        args: &[optional("character", ArgKind::Character)],
+        help: "Load an existing character.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "sc",
+        aliases: &["4", "characters"],
+        args: &[],
+        help: "Show all characters.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &["5", "?"],
+        args: &[optional("command", ArgKind::Command)],
+        help: "Display the list of commands, or help for one command.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "exit",
+        aliases: &["6", "quit"],
+        args: &[],
+        help: "Exit the game.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "export",
+        aliases: &["7"],
+        args: &[optional("file", ArgKind::File)],
+        help: "Export the game state as JSON.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "graveyard",
+        aliases: &["8"],
+        args: &[],
+        help: "Show the characters lost to permadeath.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "reload",
+        aliases: &[],
+        args: &[],
+        help: "Re-read the asset files and packs.",
+        debug: true,
+    },
+];
+
+/// Commands available while playing (`>>.` prompt).
+pub const GAME_COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "attack",
+        aliases: &["a"],
+        args: &[
+            optional("skill", ArgKind::Skill),
+            optional("enemy", ArgKind::Enemy),
+        ],
+        help: "Attack the enemy with a skill. Naming an enemy switches target first.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "run",
+        aliases: &["r"],
+        args: &[],
+        help: "Run away from the current enemy; it gets one last hit in.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "status",
+        aliases: &["s"],
+        args: &[],
+        help: "Show your stats and the enemy's.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "show_enemies",
+        aliases: &["enemies"],
+        args: &[],
+        help: "List all enemies.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "show_inventory",
+        aliases: &["inventory", "i"],
+        args: &[],
+        help: "List your inventory.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "equip",
+        aliases: &["e"],
+        args: &[optional("item", ArgKind::Item)],
+        help: "Equip an item from your inventory.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "unequip",
+        aliases: &["u"],
+        args: &[optional("item", ArgKind::Equipment)],
+        help: "Move an equipped item back to your inventory.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "use_item",
+        aliases: &["use"],
+        args: &[optional("item", ArgKind::Item)],
+        help: "Use a consumable from your inventory.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "look",
+        aliases: &["l"],
+        args: &[],
+        help: "Describe the room you are in.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "go",
+        aliases: &["g", "move"],
+        args: &[required("direction", ArgKind::Direction)],
+        help: "Leave the room through an exit; the current enemy gets one last hit in.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "map",
+        aliases: &["m"],
+        args: &[],
+        help: "Show the rooms you have explored in this zone.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "take",
+        aliases: &["t", "pickup"],
+        args: &[optional("item", ArgKind::Floor)],
+        help: "Pick up an item from the floor.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "descend",
+        aliases: &["down"],
+        args: &[optional("seed", ArgKind::Number)],
+        help: "Enter a dungeon, or take the stairs to its next floor. A seed gives a known layout.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "leave",
+        aliases: &[],
+        args: &[],
+        help: "Climb out of the dungeon; unless its boss is dead, the run is lost.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "talk",
+        aliases: &["speak"],
+        args: &[required("person", ArgKind::Person)],
+        help: "Talk to someone in the room.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "choose",
+        aliases: &["c"],
+        args: &[required("choice", ArgKind::Number)],
+        help: "Answer in the conversation with one of the numbered choices.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "buy",
+        aliases: &[],
+        args: &[required("item", ArgKind::Stock)],
+        help: "Buy an item from the shop of the person you talked to.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "reputation",
+        aliases: &["rep", "factions"],
+        args: &[],
+        help: "Show where you stand with every faction.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "skills",
+        aliases: &["sk"],
+        args: &[],
+        help: "List your skills and what the skill trees let you learn.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "learn",
+        aliases: &[],
+        args: &[required("skill", ArgKind::TreeSkill)],
+        help: "Spend skill points on a skill from a skill tree, or on its next rank.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "train",
+        aliases: &["allocate"],
+        args: &[
+            optional("stat", ArgKind::Stat),
+            optional("points", ArgKind::Number),
+        ],
+        help: "Spend attribute points from levelling on a stat (one point by default), or list what you can spend.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "quests",
+        aliases: &["journal", "j"],
+        args: &[],
+        help: "List your quests.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "quest",
+        aliases: &[],
+        args: &[required("quest", ArgKind::Quest)],
+        help: "Show a quest's objectives, your progress and its rewards.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "accept",
+        aliases: &[],
+        args: &[required("quest", ArgKind::Quest)],
+        help: "Take on a quest nobody hands out, once it is unlocked.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "pickup_sword",
+        aliases: &[],
+        args: &[],
+        help: "Pick up a sword.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "undo",
+        aliases: &[],
+        args: &[optional("turns", ArgKind::Number)],
+        help: "Take back your last turns (casual difficulty only).",
+        debug: false,
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &["h", "?"],
+        args: &[optional("command", ArgKind::Command)],
+        help: "Display the list of commands, or help for one command.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "exit",
+        aliases: &["quit"],
+        args: &[],
+        help: "Leave the game and return to the menu.",
+        debug: false,
+    },
+    CommandSpec {
+        name: "reload",
+        aliases: &[],
+        args: &[],
+        help: "Re-read the asset files and packs.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "spawn",
+        aliases: &[],
+        args: &[required("npc", ArgKind::Npc)],
+        help: "Spawn an NPC and make it the current enemy.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "give",
+        aliases: &[],
+        args: &[
+            required("item", ArgKind::AnyItem),
+            optional("count", ArgKind::Number),
+        ],
+        help: "Put items in your inventory.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "set",
+        aliases: &[],
+        args: &[
+            required("stat", ArgKind::Stat),
+            required("value", ArgKind::Number),
+        ],
+        help: "Set one of your stats.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "heal",
+        aliases: &[],
+        args: &[optional("amount", ArgKind::Number)],
+        help: "Restore HP, up to full if no amount is given.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "kill",
+        aliases: &[],
+        args: &[],
+        help: "Defeat the current enemy.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "seed",
+        aliases: &[],
+        args: &[required("seed", ArgKind::Number)],
+        help: "Seed the random number generator.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "dump",
+        aliases: &[],
+        args: &[optional("file", ArgKind::File)],
+        help: "Print the game state as JSON, or write it to a file.",
+        debug: true,
+    },
+    CommandSpec {
+        name: "verbose",
+        aliases: &[],
+        args: &[optional("switch", ArgKind::Switch)],
+        help: "Show the math behind every damage roll.",
+        debug: true,
+    },
+];
+
+/// A command line resolved against a command table.
+#[derive(Debug)]
+pub struct ParsedCommand {
+    pub spec: &'static CommandSpec,
+    pub args: Vec<String>,
+}
+
+impl ParsedCommand {
+    pub fn name(&self) -> &'static str {
+        self.spec.name
+    }
+}
+
+impl CommandSpec {
+    pub fn matches(&self, word: &str) -> bool {
+        self.name.eq_ignore_ascii_case(word)
+            || self
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(word))
+    }
+
+    /// One-line usage, e.g. `attack [skill] [enemy]`.
+    pub fn usage(&self) -> String {
+        let mut usage = self.name.to_string();
+        for arg in self.args {
+            if arg.required {
+                usage.push_str(&format!(" <{}>", arg.name));
+            } else {
+                usage.push_str(&format!(" [{}]", arg.name));
+            }
+        }
+        usage
+    }
+
+    /// Argument `index` of a call to this command, one of its required
+    /// ones. `parse` makes sure they are there, but commands also come
+    /// straight from replays and the TUI.
+    pub fn arg<'a>(&self, args: &'a [String], index: usize) -> GameResult<&'a str> {
+        args.get(index).map(String::as_str).ok_or_else(|| {
+            GameError::InvalidInput(format!(
+                "missing {}. Usage: {}",
+                self.args.get(index).map_or("argument", |arg| arg.name),
+                self.usage()
+            ))
+        })
+    }
+
+    /// Full help for `help <command>`.
+    pub fn help_text(&self) -> String {
+        let mut text = format!("{}\n    {}", self.usage(), self.help);
+        if !self.aliases.is_empty() {
+            text.push_str(&format!("\n    Aliases: {}", self.aliases.join(", ")));
+        }
+        for arg in self.args {
+            let description = match arg.kind {
+                ArgKind::Skill => "one of your skills",
+                ArgKind::Item => "an item in your inventory",
+                ArgKind::Equipment => "an equipped item",
+                ArgKind::Enemy => "one of the enemies",
+                ArgKind::Character => "one of your characters",
+                ArgKind::Command => "command name",
+                ArgKind::Name => "name to give it",
+                ArgKind::File => "file path",
+                ArgKind::Npc => "any NPC",
+                ArgKind::AnyItem => "any item",
+                ArgKind::Stat => "hp, attack, defense or agility",
+                ArgKind::Number => "a whole number",
+                ArgKind::Switch => "on or off",
+                ArgKind::Direction => "an exit of the room, e.g. north, or the start of one",
+                ArgKind::Floor => "an item on the floor",
+                ArgKind::Quest => "a quest",
+                ArgKind::Person => "someone in the room",
+                ArgKind::Stock => "an item for sale",
+                ArgKind::Class => "a class, e.g. warrior",
+                ArgKind::Race => "a race, e.g. human",
+                ArgKind::TreeSkill => "a skill from your skill trees",
+                ArgKind::Points => "points per stat, e.g. hp=4,attack=6,defense=3,agility=3",
+            };
+            text.push_str(&format!("\n    {}: {}", arg.name, description));
+        }
+        if self.args.iter().any(|arg| arg.kind.is_reference()) {
+            text.push_str("\n    Refer to things by list number, id or the start of their name.");
+        }
+        text
+    }
+}
+
+/// Split a command line into words. Single or double quotes group words, so
+/// `equip "Iron Helmet"` gives `["equip", "Iron Helmet"]`.
+pub fn tokenize(input: &str) -> GameResult<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in input.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if let Some(q) = quote {
+        return Err(GameError::InvalidInput(format!("missing closing {}", q)));
+    }
+    if in_word {
+        words.push(current);
+    }
+    Ok(words)
+}
+
+/// Edit distance between two words, used for "did you mean" suggestions.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn available(
+    commands: &'static [CommandSpec],
+    debug_mode: bool,
+) -> impl Iterator<Item = &'static CommandSpec> {
+    commands
+        .iter()
+        .filter(move |spec| debug_mode || !spec.debug)
+}
+
+/// Look up a command by name or alias.
+pub fn find(
+    commands: &'static [CommandSpec],
+    word: &str,
+    debug_mode: bool,
+) -> Option<&'static CommandSpec> {
+    available(commands, debug_mode).find(|spec| spec.matches(word))
+}
+
+/// The closest command name to `word`, if any is close enough to be a typo.
+pub fn suggest(
+    commands: &'static [CommandSpec],
+    word: &str,
+    debug_mode: bool,
+) -> Option<&'static str> {
+    let word = word.to_lowercase();
+    available(commands, debug_mode)
+        .flat_map(|spec| {
+            std::iter::once(spec.name)
+                .chain(spec.aliases.iter().copied())
+                .map(move |n| (spec.name, n))
+        })
+        .filter(|(_, candidate)| candidate.len() > 1)
+        .map(|(name, candidate)| (levenshtein(&word, candidate), name))
+        .filter(|(distance, _)| *distance <= 2 && *distance < word.len())
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, name)| name)
+}
+
+fn unknown_command(commands: &'static [CommandSpec], word: &str, debug_mode: bool) -> GameError {
+    match suggest(commands, word, debug_mode) {
+        Some(name) => GameError::InvalidInput(format!(
+            "unknown command '{}'. Did you mean '{}'?",
+            word, name
+        )),
+        None => GameError::InvalidInput(format!(
+            "unknown command '{}'. Type 'help' for a list of commands.",
+            word
+        )),
+    }
+}
+
+/// Parse a command line against a command table.
+/// Returns `Ok(None)` for an empty line.
+pub fn parse(
+    input: &str,
+    commands: &'static [CommandSpec],
+    debug_mode: bool,
+) -> GameResult<Option<ParsedCommand>> {
+    let mut words = tokenize(input)?;
+    if words.is_empty() {
+        return Ok(None);
+    }
+    let word = words.remove(0);
+    let spec = find(commands, &word, debug_mode)
+        .ok_or_else(|| unknown_command(commands, &word, debug_mode))?;
+
+    if words.len() > spec.args.len() {
+        return Err(GameError::InvalidInput(format!(
+            "too many arguments. Usage: {}",
+            spec.usage()
+        )));
+    }
+    if let Some(missing) = spec.args.iter().skip(words.len()).find(|arg| arg.required) {
+        return Err(GameError::InvalidInput(format!(
+            "missing {}. Usage: {}",
+            missing.name,
+            spec.usage()
+        )));
+    }
+    Ok(Some(ParsedCommand { spec, args: words }))
+}
+
+/// Text for `help` and `help <command>`.
+pub fn help(
+    commands: &'static [CommandSpec],
+    topic: Option<&str>,
+    debug_mode: bool,
+) -> GameResult<String> {
+    if let Some(topic) = topic {
+        return find(commands, topic, debug_mode)
+            .map(|spec| spec.help_text())
+            .ok_or_else(|| unknown_command(commands, topic, debug_mode));
+    }
+    let mut text = String::from("Commands:");
+    for spec in available(commands, debug_mode) {
+        text.push_str(&format!("\n    {}: {}", spec.usage(), spec.help));
+    }
+    text.push_str("\nType 'help <command>' for details.");
+    Ok(text)
+}
+
+/// Something the player can pick from a list by number, id or name.
+pub trait Selectable {
+    fn id(&self) -> u32;
+    fn name(&self) -> &str;
+}
+
+impl Selectable for Entity {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Selectable for Archetype {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Selectable for Item {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Selectable for Quest {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Selectable for Skill {
+    fn id(&self) -> u32 {
+        self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Find an entry of `list` from a player-typed reference.
+///
+/// A number is taken as a 1-based position when it is in range and as an id
+/// otherwise. Anything else is a case-insensitive name, or the start of one;
+/// an exact name wins over prefixes. A prefix that matches entries with
+/// different names is rejected with the list of candidates.
+pub fn resolve_index<T: Selectable>(list: &[T], reference: &str, what: &str) -> GameResult<usize> {
+    resolve(list, reference, what, true)
+}
+
+/// Like `resolve_index`, but a number is only ever taken as an id, for lists
+/// that are shown with ids rather than numbered.
+pub fn resolve_id<T: Selectable>(list: &[T], reference: &str, what: &str) -> GameResult<usize> {
+    resolve(list, reference, what, false)
+}
+
+fn resolve<T: Selectable>(
+    list: &[T],
+    reference: &str,
+    what: &str,
+    by_position: bool,
+) -> GameResult<usize> {
+    let reference = reference.trim();
+    if reference.is_empty() {
+        return Err(GameError::InvalidInput(format!("no {} given", what)));
+    }
+
+    if let Ok(number) = reference.parse::<u32>() {
+        if by_position && number >= 1 && (number as usize) <= list.len() {
+            return Ok(number as usize - 1);
+        }
+        return list
+            .iter()
+            .position(|entry| entry.id() == number)
+            .ok_or_else(|| {
+                if by_position {
+                    GameError::NotFound(format!("no {} with number or id {}", what, number))
+                } else {
+                    GameError::NotFound(format!("no {} with id {}", what, number))
+                }
+            });
+    }
+
+    let lower = reference.to_lowercase();
+    if let Some(index) = list
+        .iter()
+        .position(|entry| entry.name().to_lowercase() == lower)
+    {
+        return Ok(index);
+    }
+    let matches: Vec<usize> = (0..list.len())
+        .filter(|i| list[*i].name().to_lowercase().starts_with(&lower))
+        .collect();
+    match matches.as_slice() {
+        [] => Err(GameError::NotFound(format!(
+            "no {} named '{}'",
+            what, reference
+        ))),
+        // Several copies of the same thing, e.g. two potions: any will do
+        [first, rest @ ..]
+            if rest
+                .iter()
+                .all(|i| list[*i].name().eq_ignore_ascii_case(list[*first].name())) =>
+        {
+            Ok(*first)
+        }
+        _ => {
+            let candidates: Vec<String> = matches
+                .iter()
+                .map(|i| {
+                    if by_position {
+                        format!("{}. {}", i + 1, list[*i].name())
+                    } else {
+                        format!("[id {}] {}", list[*i].id(), list[*i].name())
+                    }
+                })
+                .collect();
+            Err(GameError::InvalidInput(format!(
+                "'{}' matches several {}s: {}. Use the {} or more of the name.",
+                reference,
+                what,
+                candidates.join(", "),
+                if by_position { "number" } else { "id" }
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(input: &str) -> Vec<String> {
+        tokenize(input).unwrap()
+    }
+
+    fn parse_error(input: &str) -> String {
+        match parse(input, GAME_COMMANDS, false) {
+            Err(GameError::InvalidInput(message)) => message,
+            other => panic!("expected an input error for '{}', got {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn tokenize_splits_on_any_whitespace() {
+        assert_eq!(words("  attack\t2   Goblin "), ["attack", "2", "Goblin"]);
+        assert!(words("   ").is_empty());
+    }
+
+    #[test]
+    fn tokenize_groups_quoted_words() {
+        assert_eq!(words(r#"equip "Iron Helmet""#), ["equip", "Iron Helmet"]);
+        assert_eq!(words("equip 'Iron Helmet'"), ["equip", "Iron Helmet"]);
+        // The other kind of quote is just a character inside quotes
+        assert_eq!(words(r#"say "it's fine""#), ["say", "it's fine"]);
+        // Quotes join onto the word around them
+        assert_eq!(words(r#"a"b c"d e"#), ["ab cd", "e"]);
+        assert_eq!(words(r#"name """#), ["name", ""]);
+    }
+
+    #[test]
+    fn tokenize_rejects_unterminated_quotes() {
+        for (input, quote) in [(r#"equip "Iron Helmet"#, '"'), ("equip 'Iron", '\'')] {
+            match tokenize(input) {
+                Err(GameError::InvalidInput(message)) => {
+                    assert_eq!(message, format!("missing closing {}", quote))
+                }
+                other => panic!("expected an error for {:?}, got {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_resolves_names_and_aliases() {
+        assert!(parse("", GAME_COMMANDS, false).unwrap().is_none());
+        let parsed = parse("A 2 'Cave Troll'", GAME_COMMANDS, false)
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.name(), "attack");
+        assert_eq!(parsed.args, ["2", "Cave Troll"]);
+        assert_eq!(
+            parse("move north", GAME_COMMANDS, false)
+                .unwrap()
+                .unwrap()
+                .name(),
+            "go"
+        );
+    }
+
+    #[test]
+    fn parse_checks_arity() {
+        assert_eq!(
+            parse_error("go"),
+            "missing direction. Usage: go <direction>"
+        );
+        assert_eq!(
+            parse_error("attack 1 2 3"),
+            "too many arguments. Usage: attack [skill] [enemy]"
+        );
+        assert_eq!(
+            parse_error("status now"),
+            "too many arguments. Usage: status"
+        );
+    }
+
+    #[test]
+    fn parse_hides_debug_commands_outside_debug_mode() {
+        assert!(parse("spawn Goblin", GAME_COMMANDS, true).is_ok());
+        assert!(parse_error("spawn Goblin").starts_with("unknown command 'spawn'"));
+    }
+
+    #[test]
+    fn unknown_commands_suggest_the_closest_name() {
+        assert_eq!(
+            parse_error("atack"),
+            "unknown command 'atack'. Did you mean 'attack'?"
+        );
+        // Aliases count, but the suggestion is the command's name
+        assert_eq!(
+            parse_error("inventroy"),
+            "unknown command 'inventroy'. Did you mean 'show_inventory'?"
+        );
+        assert_eq!(
+            parse_error("xyzzy"),
+            "unknown command 'xyzzy'. Type 'help' for a list of commands."
+        );
+    }
+
+    #[test]
+    fn suggest_ignores_words_too_short_to_be_typos() {
+        // One letter away from "r", but that is a whole different word
+        assert_eq!(suggest(GAME_COMMANDS, "x", false), None);
+        assert_eq!(suggest(GAME_COMMANDS, "lok", false), Some("look"));
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+    }
+
+    struct Entry(u32, &'static str);
+
+    impl Selectable for Entry {
+        fn id(&self) -> u32 {
+            self.0
+        }
+        fn name(&self) -> &str {
+            self.1
+        }
+    }
+
+    const BAG: &[Entry] = &[
+        Entry(401, "Health Potion"),
+        Entry(402, "Health Potion"),
+        Entry(2, "Iron Helmet"),
+        Entry(103, "Iron Sword"),
+        Entry(404, "Potion"),
+    ];
+
+    #[test]
+    fn resolve_index_takes_numbers_as_positions_before_ids() {
+        assert_eq!(resolve_index(BAG, "2", "item").unwrap(), 1);
+        // Out of range as a position, so it is an id
+        assert_eq!(resolve_index(BAG, "103", "item").unwrap(), 3);
+        assert!(matches!(
+            resolve_index(BAG, "99", "item"),
+            Err(GameError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve_index(BAG, "0", "item"),
+            Err(GameError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_index_matches_names_and_prefixes() {
+        assert_eq!(resolve_index(BAG, " iron sword ", "item").unwrap(), 3);
+        assert_eq!(resolve_index(BAG, "Iron S", "item").unwrap(), 3);
+        // An exact name wins over the longer names it starts
+        assert_eq!(resolve_index(BAG, "potion", "item").unwrap(), 4);
+        assert!(matches!(
+            resolve_index(BAG, "Mana", "item"),
+            Err(GameError::NotFound(_))
+        ));
+        assert!(matches!(
+            resolve_index(BAG, "  ", "item"),
+            Err(GameError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn resolve_index_picks_the_first_of_duplicate_names() {
+        assert_eq!(resolve_index(BAG, "Health Potion", "item").unwrap(), 0);
+        assert_eq!(resolve_index(BAG, "heal", "item").unwrap(), 0);
+    }
+
+    #[test]
+    fn resolve_index_rejects_ambiguous_prefixes() {
+        match resolve_index(BAG, "iron", "item") {
+            Err(GameError::InvalidInput(message)) => assert_eq!(
+                message,
+                "'iron' matches several items: 3. Iron Helmet, 4. Iron Sword. \
+                 Use the number or more of the name."
+            ),
+            other => panic!("expected an ambiguity error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// src/game/config.rs
+use crate::game::content::read_content;
+use crate::game::error::GameResult;
+use crate::game::format;
+use crate::game::persistence::SaveFormat;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How forgiving the game is.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    /// `undo` can take back recent turns.
+    Casual,
+    #[default]
+    Normal,
+    /// No undo, ever.
+    Ironman,
+}
+
+/// What happens when a character's HP drops to zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeathPolicy {
+    /// The character is moved to the graveyard for good.
+    Permadeath,
+    /// The character wakes up at camp, losing half its gold and a quarter
+    /// of its experience.
+    #[default]
+    Revive,
+    /// Like `Revive`, but only a tenth of the gold is lost.
+    Lenient,
+}
+
+impl DeathPolicy {
+    /// Percent of gold and of experience lost when revived.
+    pub fn penalty(&self) -> (u32, u32) {
+        match self {
+            DeathPolicy::Permadeath => (100, 100),
+            DeathPolicy::Revive => (50, 25),
+            DeathPolicy::Lenient => (10, 0),
+        }
+    }
+}
+
+/// Player settings, read from an optional config file.
+/// Any field left out of the file keeps its default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Encoding used for autosaves. Loading detects the encoding by itself.
+    pub save_format: SaveFormat,
+    pub difficulty: Difficulty,
+    /// How many turns `undo` can take back on casual difficulty.
+    pub undo_depth: usize,
+    pub death_policy: DeathPolicy,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            save_format: SaveFormat::Json,
+            difficulty: Difficulty::Normal,
+            undo_depth: 10,
+            death_policy: DeathPolicy::Revive,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config at `path` (or its TOML/RON variant).
+    /// A missing file gives the default config.
+    pub fn load(path: &str) -> GameResult<Self> {
+        let path = format::resolve_variant(path);
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        read_content(&path, "config")
+    }
+}
@@ -0,0 +1,207 @@
+// src/game/console.rs
+use crate::game::error::{GameError, GameResult};
+use std::io::{self, BufRead, Write};
+
+/// Where commands and prompt answers come from.
+///
+/// Interactive consoles read from the terminal and may ask follow-up
+/// questions. Script consoles read commands from a file or pipe, echo them,
+/// never prompt (a missing argument is an error) and stop at the first
+/// failing command. Replay consoles answer prompts from a recording.
+pub struct Console {
+    reader: Box<dyn BufRead>,
+    interactive: bool,
+    echo_answers: bool,
+    source: String,
+    line_number: usize,
+    failed: bool,
+    /// Prompt answers since the last `take_answers`, for recordings.
+    answers: Vec<String>,
+}
+
+impl Console {
+    pub fn stdin() -> Self {
+        Self {
+            reader: Box::new(io::BufReader::new(io::stdin())),
+            interactive: true,
+            echo_answers: false,
+            source: "stdin".to_string(),
+            line_number: 0,
+            failed: false,
+            answers: Vec::new(),
+        }
+    }
+
+    /// A non-interactive console reading commands from `reader`.
+    /// `source` names the script in error messages.
+    pub fn script(reader: Box<dyn BufRead>, source: &str) -> Self {
+        Self {
+            reader,
+            interactive: false,
+            echo_answers: true,
+            source: source.to_string(),
+            line_number: 0,
+            failed: false,
+            answers: Vec::new(),
+        }
+    }
+
+    /// A console that answers prompts with recorded `answers`, in order.
+    pub fn replay(answers: &[String]) -> Self {
+        let mut input = answers.join("\n");
+        input.push('\n');
+        Self {
+            reader: Box::new(io::Cursor::new(input)),
+            interactive: true,
+            echo_answers: true,
+            source: "replay".to_string(),
+            line_number: 0,
+            failed: false,
+            answers: Vec::new(),
+        }
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// True once any command failed.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Scripts stop at the first failing command.
+    pub fn should_stop(&self) -> bool {
+        self.failed && !self.interactive
+    }
+
+    fn read_line(&mut self) -> GameResult<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line_number += 1;
+        Ok(Some(line.trim().to_string()))
+    }
+
+    /// Show `prompt` and read the next command. Scripts skip blank lines and
+    /// `#` comments. Returns `None` at the end of the input.
+    pub fn read_command(&mut self, prompt: &str) -> GameResult<Option<String>> {
+        loop {
+            print!("{}", prompt);
+            io::stdout().flush()?;
+            let line = match self.read_line()? {
+                Some(line) => line,
+                None => {
+                    println!();
+                    return Ok(None);
+                }
+            };
+            if self.interactive {
+                return Ok(Some(line));
+            }
+            if line.is_empty() || line.starts_with('#') {
+                println!();
+                continue;
+            }
+            println!("{}", line);
+            return Ok(Some(line));
+        }
+    }
+
+    /// Fail with a "missing argument" error unless follow-up prompts are
+    /// allowed. Call this before printing anything the prompt needs.
+    pub fn require_interactive(&self, what: &str) -> GameResult<()> {
+        if self.interactive {
+            Ok(())
+        } else {
+            Err(GameError::InvalidInput(format!(
+                "missing {}; prompts are disabled in scripts",
+                what
+            )))
+        }
+    }
+
+    /// Ask a follow-up question.
+    pub fn ask(&mut self, message: &str) -> GameResult<String> {
+        print!("{}", message);
+        io::stdout().flush()?;
+        let answer = self.read_line()?.ok_or_else(|| {
+            GameError::Io(io::Error::new(io::ErrorKind::UnexpectedEof, "end of input"))
+        })?;
+        if self.echo_answers {
+            println!("{}", answer);
+        }
+        self.answers.push(answer.clone());
+        Ok(answer)
+    }
+
+    /// The prompt answers given since the last call.
+    pub fn take_answers(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.answers)
+    }
+
+    /// Print an error from a command and remember that something failed.
+    pub fn report_error(&mut self, e: &GameError) {
+        self.failed = true;
+        if self.interactive {
+            println!("Error: {}", e);
+        } else {
+            eprintln!("{}:{}: Error: {}", self.source, self.line_number, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(text: &str) -> Console {
+        Console::script(Box::new(io::Cursor::new(text.to_string())), "test.txt")
+    }
+
+    fn commands(console: &mut Console) -> Vec<String> {
+        std::iter::from_fn(|| console.read_command("> ").unwrap()).collect()
+    }
+
+    #[test]
+    fn scripts_skip_blank_lines_and_comments() {
+        let mut console = script("# set up\nnew-character Mira\n\n  attack 1  \n");
+        assert!(!console.is_interactive());
+        assert_eq!(commands(&mut console), ["new-character Mira", "attack 1"]);
+        assert_eq!(console.line_number, 4);
+    }
+
+    #[test]
+    fn scripts_never_prompt_and_stop_on_errors() {
+        let mut console = script("attack\n");
+        console.read_command("> ").unwrap();
+        let error = console.require_interactive("skill").unwrap_err();
+        assert!(
+            matches!(&error, GameError::InvalidInput(message) if message.starts_with("missing skill"))
+        );
+        assert!(!console.should_stop());
+        console.report_error(&error);
+        assert!(console.failed());
+        assert!(console.should_stop());
+    }
+
+    #[test]
+    fn interactive_consoles_keep_going_after_errors() {
+        let mut console = Console::replay(&[]);
+        console.require_interactive("skill").unwrap();
+        console.report_error(&GameError::InvalidInput("oops".into()));
+        assert!(console.failed());
+        assert!(!console.should_stop());
+    }
+
+    #[test]
+    fn replays_answer_prompts_in_order() {
+        let mut console = Console::replay(&["Fireball".to_string(), "2".to_string()]);
+        assert_eq!(console.ask("Skill: ").unwrap(), "Fireball");
+        assert_eq!(console.ask("Target: ").unwrap(), "2");
+        assert_eq!(console.take_answers(), ["Fireball", "2"]);
+        assert!(console.take_answers().is_empty());
+        assert!(matches!(console.ask("More: "), Err(GameError::Io(_))));
+    }
+}
@@ -0,0 +1,651 @@
+// src/game/content.rs
+use crate::game::character::{Archetype, ClassesWrapper, PointBuy};
+use crate::game::dialogue::{Dialogue, DialoguesWrapper};
+use crate::game::dungeon::{DungeonTheme, DungeonsWrapper};
+use crate::game::error::{GameError, GameResult};
+use crate::game::faction::{Faction, FactionsWrapper};
+use crate::game::format::{self, Format};
+use crate::game::log::say;
+use crate::game::persistence::{EntitiesWrapper, ItemsWrapper, RawEntity, SkillsWrapper};
+use crate::game::quest::{Quest, QuestsWrapper};
+use crate::game::skill_tree::{SkillTree, SkillTreesWrapper};
+use crate::game::validation::Issue;
+use crate::game::world::World;
+use crate::game::{item::Item, skills::Skill};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Every pack directory needs a manifest with this name.
+pub const MANIFEST_FILE: &str = "pack.json";
+pub const PACK_ENTITIES_FILE: &str = "entities.json";
+pub const PACK_ITEMS_FILE: &str = "items.json";
+pub const PACK_SKILLS_FILE: &str = "skills.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub name: String,
+    pub version: String,
+    /// Names of packs that must be loaded before this one.
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    /// Packs with a lower load order are applied first.
+    #[serde(default)]
+    pub load_order: i32,
+}
+
+/// A content pack found on disk.
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub manifest: PackManifest,
+    pub dir: PathBuf,
+}
+
+/// Definitions read from a single pack. Files a pack doesn't ship are empty.
+pub struct PackContent {
+    pub skills: Vec<Skill>,
+    pub items: Vec<Item>,
+    pub(crate) entities: Vec<RawEntity>,
+}
+
+/// An entity definition along with the file and position it was read from.
+pub(crate) struct ContentEntity {
+    pub(crate) file: String,
+    pub(crate) index: usize,
+    pub(crate) raw: RawEntity,
+}
+
+/// The merged set of skills, items and entities the game is built from, and
+/// the world map to play them in.
+pub struct Content {
+    pub skills: Vec<Skill>,
+    pub items: Vec<Item>,
+    pub(crate) entities: Vec<ContentEntity>,
+    pub world: World,
+    pub dungeons: Vec<DungeonTheme>,
+    pub quests: Vec<Quest>,
+    pub dialogues: Vec<Dialogue>,
+    pub factions: Vec<Faction>,
+    pub classes: Vec<Archetype>,
+    pub races: Vec<Archetype>,
+    pub point_buy: PointBuy,
+    pub skill_trees: Vec<SkillTree>,
+    pub skill_points_per_level: u32,
+    /// Which pack last defined a given (kind, id), used to report conflicts.
+    origins: HashMap<(&'static str, u32), String>,
+}
+
+/// Read a content file in the format given by its extension.
+pub(crate) fn read_content<T: DeserializeOwned>(path: &str, what: &str) -> GameResult<T> {
+    let data = fs::read_to_string(path)?;
+    Format::from_path(path).parse(&data).map_err(|e| {
+        GameError::Content(format!(
+            "Deserialization error loading {} from {}: {}",
+            what, path, e
+        ))
+    })
+}
+
+/// Like `read_content`, in any supported format, but a missing file is
+/// `None` rather than an error.
+fn read_optional_content<T: DeserializeOwned>(path: &str, what: &str) -> GameResult<Option<T>> {
+    let path = format::resolve_variant(path);
+    if !Path::new(&path).exists() {
+        return Ok(None);
+    }
+    read_content(&path, what).map(Some)
+}
+
+impl Pack {
+    /// Path of one of the pack's content files, in whichever supported
+    /// format it was written. Falls back to the JSON path if there is none.
+    pub fn file_path(&self, file: &str) -> String {
+        format::resolve_variant(&self.dir.join(file).to_string_lossy())
+    }
+
+    /// Read the pack's content files. Missing files are treated as empty.
+    pub fn load(&self) -> Result<PackContent, Issue> {
+        Ok(PackContent {
+            skills: self
+                .read_optional::<SkillsWrapper>(PACK_SKILLS_FILE, "skills")?
+                .map_or_else(Vec::new, |w| w.skills),
+            items: self
+                .read_optional::<ItemsWrapper>(PACK_ITEMS_FILE, "items")?
+                .map_or_else(Vec::new, |w| w.items),
+            entities: self
+                .read_optional::<EntitiesWrapper>(PACK_ENTITIES_FILE, "entities")?
+                .map_or_else(Vec::new, |w| w.entities),
+        })
+    }
+
+    fn read_optional<T: DeserializeOwned>(
+        &self,
+        file: &str,
+        what: &str,
+    ) -> Result<Option<T>, Issue> {
+        let path = self.file_path(file);
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+        read_content(&path, what).map(Some).map_err(|e| {
+            Issue::error(
+                &path,
+                "$",
+                format!("pack '{}' skipped: {}", self.manifest.name, e),
+            )
+        })
+    }
+}
+
+/// Find every pack in `packs_dir` and return the loadable ones in load order.
+///
+/// Packs are sorted by `load_order`, then name, and each pack is moved after
+/// its dependencies. Packs with missing or cyclic dependencies are reported
+/// and left out. A missing `packs_dir` simply means there are no packs.
+pub fn discover_packs(packs_dir: &str) -> (Vec<Pack>, Vec<Issue>) {
+    let mut issues = Vec::new();
+    let entries = match fs::read_dir(packs_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return (Vec::new(), issues),
+        Err(e) => {
+            issues.push(Issue::error(
+                packs_dir,
+                "$",
+                format!("cannot read pack directory: {}", e),
+            ));
+            return (Vec::new(), issues);
+        }
+    };
+
+    let mut dirs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    dirs.sort();
+
+    let mut pending: Vec<Pack> = Vec::new();
+    for dir in dirs {
+        let manifest_path = format::resolve_variant(&dir.join(MANIFEST_FILE).to_string_lossy());
+        if !Path::new(&manifest_path).exists() {
+            issues.push(Issue::warning(
+                &manifest_path,
+                "$",
+                "no pack manifest; directory skipped",
+            ));
+            continue;
+        }
+        let manifest: PackManifest = match read_content(&manifest_path, "pack manifest") {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                issues.push(Issue::error(&manifest_path, "$", e.to_string()));
+                continue;
+            }
+        };
+        if pending
+            .iter()
+            .any(|pack| pack.manifest.name == manifest.name)
+        {
+            issues.push(Issue::error(
+                &manifest_path,
+                "name",
+                format!("duplicate pack name '{}'; pack skipped", manifest.name),
+            ));
+            continue;
+        }
+        pending.push(Pack { manifest, dir });
+    }
+    pending.sort_by(|a, b| {
+        (a.manifest.load_order, &a.manifest.name).cmp(&(b.manifest.load_order, &b.manifest.name))
+    });
+
+    let names: Vec<String> = pending
+        .iter()
+        .map(|pack| pack.manifest.name.clone())
+        .collect();
+    let mut ordered: Vec<Pack> = Vec::new();
+    while let Some(i) = pending.iter().position(|pack| {
+        pack.manifest
+            .dependencies
+            .iter()
+            .all(|dep| ordered.iter().any(|loaded| &loaded.manifest.name == dep))
+    }) {
+        ordered.push(pending.remove(i));
+    }
+
+    for pack in pending {
+        let manifest_path = pack.file_path(MANIFEST_FILE);
+        for (i, dep) in pack.manifest.dependencies.iter().enumerate() {
+            if ordered.iter().any(|loaded| &loaded.manifest.name == dep) {
+                continue;
+            }
+            let reason = if names.contains(dep) {
+                format!(
+                    "dependency '{}' could not be loaded (cyclic or failed)",
+                    dep
+                )
+            } else {
+                format!("missing dependency '{}'", dep)
+            };
+            issues.push(Issue::error(
+                &manifest_path,
+                format!("dependencies[{}]", i),
+                format!("pack '{}' skipped: {}", pack.manifest.name, reason),
+            ));
+        }
+    }
+
+    (ordered, issues)
+}
+
+/// A content definition that packs can add or override by id.
+trait Definition {
+    const KIND: &'static str;
+    const LIST: &'static str;
+    fn id(&self) -> u32;
+}
+
+impl Definition for Skill {
+    const KIND: &'static str = "skill";
+    const LIST: &'static str = "skills";
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Definition for Item {
+    const KIND: &'static str = "item";
+    const LIST: &'static str = "items";
+    fn id(&self) -> u32 {
+        self.id
+    }
+}
+
+impl Definition for ContentEntity {
+    const KIND: &'static str = "entity";
+    const LIST: &'static str = "entities";
+    fn id(&self) -> u32 {
+        self.raw.id
+    }
+}
+
+/// Insert or replace each definition by id, reporting overrides of ids that
+/// another pack already defined.
+fn merge_list<T: Definition>(
+    list: &mut Vec<T>,
+    incoming: Vec<T>,
+    pack: &Pack,
+    file: &str,
+    origins: &mut HashMap<(&'static str, u32), String>,
+    issues: &mut Vec<Issue>,
+) -> (usize, usize) {
+    let (mut added, mut overridden) = (0, 0);
+    for (i, value) in incoming.into_iter().enumerate() {
+        let id = value.id();
+        if let Some(other) = origins.get(&(T::KIND, id)) {
+            issues.push(Issue::warning(
+                file,
+                format!("{}[{}].id", T::LIST, i),
+                format!(
+                    "{} {} from pack '{}' conflicts with pack '{}'; '{}' wins",
+                    T::KIND,
+                    id,
+                    pack.manifest.name,
+                    other,
+                    pack.manifest.name
+                ),
+            ));
+        }
+        origins.insert((T::KIND, id), pack.manifest.name.clone());
+
+        match list.iter().position(|existing| existing.id() == id) {
+            Some(pos) => {
+                list[pos] = value;
+                overridden += 1;
+            }
+            None => {
+                list.push(value);
+                added += 1;
+            }
+        }
+    }
+    (added, overridden)
+}
+
+impl Content {
+    pub(crate) fn new(
+        entities_file: &str,
+        entities: Vec<RawEntity>,
+        items: Vec<Item>,
+        skills: Vec<Skill>,
+    ) -> Self {
+        Self {
+            skills,
+            items,
+            entities: entities
+                .into_iter()
+                .enumerate()
+                .map(|(index, raw)| ContentEntity {
+                    file: entities_file.to_string(),
+                    index,
+                    raw,
+                })
+                .collect(),
+            world: World::default(),
+            dungeons: Vec::new(),
+            quests: Vec::new(),
+            dialogues: Vec::new(),
+            factions: Vec::new(),
+            classes: Vec::new(),
+            races: Vec::new(),
+            point_buy: PointBuy::default(),
+            skill_trees: Vec::new(),
+            skill_points_per_level: 0,
+            origins: HashMap::new(),
+        }
+    }
+
+    /// Load the base content files.
+    pub fn from_files(
+        entities_path: &str,
+        items_path: &str,
+        skills_path: &str,
+    ) -> GameResult<Self> {
+        let skills: SkillsWrapper = read_content(skills_path, "skills")?;
+        let items: ItemsWrapper = read_content(items_path, "items")?;
+        let entities: EntitiesWrapper = read_content(entities_path, "entities")?;
+        Ok(Self::new(
+            entities_path,
+            entities.entities,
+            items.items,
+            skills.skills,
+        ))
+    }
+
+    /// Load the world map. Without a world file the game stays an arena.
+    pub fn load_world(&mut self, world_path: &str) -> GameResult<()> {
+        if let Some(world) = read_optional_content(world_path, "world")? {
+            self.world = world;
+        }
+        Ok(())
+    }
+
+    /// Load the dungeon themes, if there is a file for them.
+    pub fn load_dungeons(&mut self, dungeons_path: &str) -> GameResult<()> {
+        if let Some(wrapper) = read_optional_content::<DungeonsWrapper>(dungeons_path, "dungeons")?
+        {
+            self.dungeons = wrapper.themes;
+        }
+        Ok(())
+    }
+
+    /// Load the quests, if there is a file for them.
+    pub fn load_quests(&mut self, quests_path: &str) -> GameResult<()> {
+        if let Some(wrapper) = read_optional_content::<QuestsWrapper>(quests_path, "quests")? {
+            self.quests = wrapper.quests;
+        }
+        Ok(())
+    }
+
+    /// Load the NPC dialogues, if there is a file for them.
+    pub fn load_dialogue(&mut self, dialogue_path: &str) -> GameResult<()> {
+        if let Some(wrapper) = read_optional_content::<DialoguesWrapper>(dialogue_path, "dialogue")?
+        {
+            self.dialogues = wrapper.dialogues;
+        }
+        Ok(())
+    }
+
+    /// Load the factions, if there is a file for them.
+    pub fn load_factions(&mut self, factions_path: &str) -> GameResult<()> {
+        if let Some(wrapper) = read_optional_content::<FactionsWrapper>(factions_path, "factions")?
+        {
+            self.factions = wrapper.factions;
+        }
+        Ok(())
+    }
+
+    /// Load the skill trees, if there is a file for them.
+    pub fn load_skill_trees(&mut self, skill_trees_path: &str) -> GameResult<()> {
+        if let Some(wrapper) =
+            read_optional_content::<SkillTreesWrapper>(skill_trees_path, "skill trees")?
+        {
+            self.skill_trees = wrapper.trees;
+            self.skill_points_per_level = wrapper.points_per_level;
+        }
+        Ok(())
+    }
+
+    /// Load the classes and races, if there is a file for them.
+    pub fn load_classes(&mut self, classes_path: &str) -> GameResult<()> {
+        if let Some(wrapper) = read_optional_content::<ClassesWrapper>(classes_path, "classes")? {
+            self.classes = wrapper.classes;
+            self.races = wrapper.races;
+            self.point_buy = wrapper.point_buy;
+        }
+        Ok(())
+    }
+
+    /// Merge one pack on top of the current content.
+    /// Returns the number of added and overridden definitions.
+    pub fn merge(
+        &mut self,
+        pack: &Pack,
+        content: PackContent,
+        issues: &mut Vec<Issue>,
+    ) -> (usize, usize) {
+        let skills = merge_list(
+            &mut self.skills,
+            content.skills,
+            pack,
+            &pack.file_path(PACK_SKILLS_FILE),
+            &mut self.origins,
+            issues,
+        );
+        let items = merge_list(
+            &mut self.items,
+            content.items,
+            pack,
+            &pack.file_path(PACK_ITEMS_FILE),
+            &mut self.origins,
+            issues,
+        );
+        let entities_file = pack.file_path(PACK_ENTITIES_FILE);
+        let entities = content
+            .entities
+            .into_iter()
+            .enumerate()
+            .map(|(index, raw)| ContentEntity {
+                file: entities_file.clone(),
+                index,
+                raw,
+            })
+            .collect();
+        let entities = merge_list(
+            &mut self.entities,
+            entities,
+            pack,
+            &entities_file,
+            &mut self.origins,
+            issues,
+        );
+        (
+            skills.0 + items.0 + entities.0,
+            skills.1 + items.1 + entities.1,
+        )
+    }
+
+    /// Discover, load and merge every pack in `packs_dir`.
+    pub fn apply_packs(&mut self, packs_dir: &str) -> Vec<Issue> {
+        let (packs, mut issues) = discover_packs(packs_dir);
+        for pack in &packs {
+            match pack.load() {
+                Ok(content) => {
+                    let (added, overridden) = self.merge(pack, content, &mut issues);
+                    say!(
+                        "Loaded pack {} {} ({} added, {} overridden).",
+                        pack.manifest.name,
+                        pack.manifest.version,
+                        added,
+                        overridden
+                    );
+                }
+                Err(issue) => issues.push(issue),
+            }
+        }
+        issues
+    }
+}
+
+/// Convert a content file (entities, items, skills or a pack manifest) to the
+/// format given by the extension of `output`.
+pub fn convert_file(input: &str, output: &str) -> GameResult<()> {
+    let out_format = Format::from_extension(output).ok_or_else(|| {
+        GameError::InvalidInput(format!(
+            "Unknown output format for {}; use .json, .toml or .ron",
+            output
+        ))
+    })?;
+    let data = fs::read_to_string(input)?;
+    let in_format = Format::from_path(input);
+
+    let converted = if let Ok(wrapper) = in_format.parse::<EntitiesWrapper>(&data) {
+        out_format.serialize(&wrapper)
+    } else if let Ok(wrapper) = in_format.parse::<ItemsWrapper>(&data) {
+        out_format.serialize(&wrapper)
+    } else if let Ok(wrapper) = in_format.parse::<SkillsWrapper>(&data) {
+        out_format.serialize(&wrapper)
+    } else if let Ok(manifest) = in_format.parse::<PackManifest>(&data) {
+        out_format.serialize(&manifest)
+    } else {
+        return Err(GameError::Content(format!(
+            "{} is not a valid entities, items, skills or pack manifest file",
+            input
+        )));
+    }
+    .map_err(|e| GameError::Content(format!("Serialization error: {}", e)))?;
+
+    fs::write(output, converted)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::{self, skill};
+    use crate::game::validation::Severity;
+    use serde_json::{json, Value};
+
+    /// Write a pack directory `name` holding `manifest` and `skills`.
+    fn write_pack(packs: &Path, name: &str, manifest: Value, skills: &[Skill]) {
+        let dir = packs.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(MANIFEST_FILE), manifest.to_string()).unwrap();
+        if !skills.is_empty() {
+            let skills = serde_json::to_string(&SkillsWrapper {
+                skills: skills.to_vec(),
+            })
+            .unwrap();
+            fs::write(dir.join(PACK_SKILLS_FILE), skills).unwrap();
+        }
+    }
+
+    fn names(packs: &[Pack]) -> Vec<&str> {
+        packs
+            .iter()
+            .map(|pack| pack.manifest.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn packs_load_by_order_and_after_their_dependencies() {
+        let packs = fixtures::temp_dir("packs-order");
+        write_pack(
+            &packs,
+            "a",
+            json!({ "name": "late", "version": "1", "load_order": 5 }),
+            &[],
+        );
+        write_pack(
+            &packs,
+            "b",
+            json!({ "name": "needs-late", "version": "1", "dependencies": ["late"] }),
+            &[],
+        );
+        write_pack(&packs, "c", json!({ "name": "early", "version": "1" }), &[]);
+        fs::create_dir_all(packs.join("d")).unwrap();
+        write_pack(
+            &packs,
+            "e",
+            json!({ "name": "orphan", "version": "1", "dependencies": ["gone"] }),
+            &[],
+        );
+
+        let (loaded, issues) = discover_packs(&packs.to_string_lossy());
+        fs::remove_dir_all(&packs).unwrap();
+        assert_eq!(names(&loaded), ["early", "late", "needs-late"]);
+        let found: Vec<(Severity, &str)> = issues
+            .iter()
+            .map(|issue| (issue.severity, issue.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Severity::Warning, "no pack manifest; directory skipped"),
+                (
+                    Severity::Error,
+                    "pack 'orphan' skipped: missing dependency 'gone'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn packs_override_ids_and_report_conflicts() {
+        let packs = fixtures::temp_dir("packs-merge");
+        write_pack(
+            &packs,
+            "frost",
+            json!({ "name": "frost", "version": "1" }),
+            &[skill(1, "Frost Strike", 6), skill(2, "Ice Wall", 2)],
+        );
+        write_pack(
+            &packs,
+            "fire",
+            json!({ "name": "fire", "version": "1", "load_order": 1 }),
+            &[skill(2, "Fire Wall", 3)],
+        );
+        let mut content = Content::new(
+            "entities.json",
+            Vec::new(),
+            Vec::new(),
+            vec![skill(1, "Strike", 4)],
+        );
+
+        let issues = content.apply_packs(&packs.to_string_lossy());
+        fs::remove_dir_all(&packs).unwrap();
+        let names: Vec<&str> = content.skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Frost Strike", "Fire Wall"]);
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].path, "skills[0].id");
+        assert_eq!(
+            issues[0].message,
+            "skill 2 from pack 'fire' conflicts with pack 'frost'; 'fire' wins"
+        );
+    }
+
+    #[test]
+    fn a_broken_manifest_is_reported() {
+        let packs = fixtures::temp_dir("packs-broken");
+        fs::create_dir_all(packs.join("bad")).unwrap();
+        fs::write(packs.join("bad").join(MANIFEST_FILE), "{ \"name\": 3 }").unwrap();
+        let (loaded, issues) = discover_packs(&packs.to_string_lossy());
+        fs::remove_dir_all(&packs).unwrap();
+        assert!(loaded.is_empty());
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Error);
+    }
+}
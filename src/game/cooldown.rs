@@ -0,0 +1,369 @@
+// src/game/cooldown.rs
+use crate::game::effect::{self, ActiveStatus};
+use crate::game::entity::Entity;
+use crate::game::skills::Skill;
+use serde::{Deserialize, Serialize};
+
+/// Where an entity stands in the current encounter: how many turns it has
+/// taken, which skills are cooling down or out of charges, what it is
+/// warming up, and the statuses and knockbacks on it. Cleared when the
+/// encounter ends.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CombatState {
+    /// Turns taken so far; every other number here is one of these.
+    pub turn: u32,
+    #[serde(default)]
+    pub timers: Vec<SkillTimer>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending: Option<Pending>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<ActiveStatus>,
+    /// Turns still lost to being knocked back.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub knocked_back: u32,
+    /// HP heals in the encounter go up to; 0 if not known.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub max_hp: i32,
+}
+
+fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkillTimer {
+    pub skill: u32,
+    /// First turn the skill can be used again.
+    pub ready_on: u32,
+    /// Turns on which spent charges come back, one each.
+    #[serde(default)]
+    pub recharges: Vec<u32>,
+}
+
+/// A skill that was used and takes effect on a later turn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pending {
+    pub skill: Skill,
+    pub resolve_on: u32,
+}
+
+impl CombatState {
+    pub fn is_idle(&self) -> bool {
+        *self == CombatState::default()
+    }
+
+    fn timer(&self, skill: u32) -> Option<&SkillTimer> {
+        self.timers.iter().find(|timer| timer.skill == skill)
+    }
+
+    fn timer_mut(&mut self, skill: u32) -> &mut SkillTimer {
+        if let Some(index) = self.timers.iter().position(|timer| timer.skill == skill) {
+            return &mut self.timers[index];
+        }
+        self.timers.push(SkillTimer {
+            skill,
+            ready_on: 0,
+            recharges: Vec::new(),
+        });
+        self.timers.last_mut().unwrap()
+    }
+
+    /// Charges of `skill` left on turn `turn`.
+    fn charges_on(&self, skill: &Skill, turn: u32) -> u32 {
+        let spent = self.timer(skill.id).map_or(0, |timer| {
+            timer
+                .recharges
+                .iter()
+                .filter(|back_on| **back_on > turn)
+                .count() as u32
+        });
+        skill.charges.saturating_sub(spent)
+    }
+}
+
+/// The skill `entity` is channelling, which takes up its next turn.
+pub fn channelling(entity: &Entity) -> Option<&Skill> {
+    entity
+        .combat
+        .pending
+        .as_ref()
+        .map(|pending| &pending.skill)
+        .filter(|skill| skill.channelled)
+}
+
+/// `count` turns, spelled out.
+pub fn turns(count: u32) -> String {
+    match count {
+        1 => "1 turn".into(),
+        _ => format!("{} turns", count),
+    }
+}
+
+/// When the next spent charge of `skill` comes back, counted from turn
+/// `turn`; `None` if none is on its way.
+fn next_recharge(state: &CombatState, skill: &Skill, turn: u32) -> Option<u32> {
+    if skill.recharge == 0 {
+        return None;
+    }
+    state.timer(skill.id).and_then(|timer| {
+        timer
+            .recharges
+            .iter()
+            .filter(|back_on| **back_on > turn)
+            .min()
+            .map(|back_on| back_on - turn)
+    })
+}
+
+/// Why `entity` can't use `skill` on its next turn, or `None` if it can.
+pub fn blocker(entity: &Entity, skill: &Skill) -> Option<String> {
+    if let Some(channelled) = channelling(entity) {
+        return Some(format!("busy channelling {}", channelled.name));
+    }
+    let state = &entity.combat;
+    let next = state.turn + 1;
+    if let Some(timer) = state.timer(skill.id) {
+        if timer.ready_on > next {
+            return Some(format!("ready in {}", turns(timer.ready_on - next)));
+        }
+    }
+    if skill.charges > 0 && state.charges_on(skill, next) == 0 {
+        return Some(match next_recharge(state, skill, next) {
+            Some(wait) => format!("out of charges, one back in {}", turns(wait)),
+            None => "out of charges".into(),
+        });
+    }
+    if skill.warmup > 0 {
+        if let Some(pending) = &state.pending {
+            if pending.resolve_on > next {
+                return Some(format!("already warming up {}", pending.skill.name));
+            }
+        }
+    }
+    None
+}
+
+/// How `skill` stands for `entity`, to show next to it.
+pub fn status(entity: &Entity, skill: &Skill) -> String {
+    let state = &entity.combat;
+    let next = state.turn + 1;
+    let ready_on = state.timer(skill.id).map_or(0, |timer| timer.ready_on);
+    let mut text = match &state.pending {
+        Some(pending) if pending.skill.id == skill.id => format!(
+            "takes effect in {}",
+            turns(pending.resolve_on.saturating_sub(state.turn))
+        ),
+        _ if ready_on > next => format!("ready in {}", turns(ready_on - next)),
+        _ if skill.charges > 0 && state.charges_on(skill, next) == 0 => "out of charges".into(),
+        _ => "ready".into(),
+    };
+    if skill.charges > 0 {
+        let left = state.charges_on(skill, next);
+        if left > 0 {
+            text.push_str(&format!(", {}/{} charges", left, skill.charges));
+        }
+        if let Some(wait) = next_recharge(state, skill, next) {
+            text.push_str(&format!(", next in {}", turns(wait)));
+        }
+    }
+    text
+}
+
+/// Whether any of `entity`'s skills has timing rules worth showing.
+pub fn has_timers(entity: &Entity) -> bool {
+    entity
+        .skills
+        .iter()
+        .any(|skill| skill.cooldown > 0 || skill.charges > 0 || skill.warmup > 0)
+}
+
+/// The skills of `entity` with how each stands.
+pub fn skills_string(entity: &Entity) -> String {
+    let mut text = String::new();
+    for (i, skill) in entity.skills.iter().enumerate() {
+        text.push_str(&format!(
+            "\n    {}. {} ({})",
+            i + 1,
+            skill.name,
+            status(entity, skill)
+        ));
+    }
+    text
+}
+
+/// Start `entity`'s next turn. Returns a warmed-up skill that takes effect
+/// now, if there is one; if the entity `acts` not this turn, the skill
+/// waits for the next.
+pub fn start_turn(entity: &mut Entity, acts: bool) -> Option<Skill> {
+    let state = &mut entity.combat;
+    state.turn += 1;
+    match &mut state.pending {
+        Some(pending) if pending.resolve_on <= state.turn && !acts => {
+            pending.resolve_on = state.turn + 1;
+            None
+        }
+        Some(pending) if pending.resolve_on <= state.turn => {
+            state.pending.take().map(|pending| pending.skill)
+        }
+        _ => None,
+    }
+}
+
+/// Record that `entity` used `skill` this turn, starting its cooldown and
+/// warm-up and spending a charge.
+pub fn spend(entity: &mut Entity, skill: &Skill) {
+    let state = &mut entity.combat;
+    let turn = state.turn;
+    if skill.warmup > 0 {
+        state.pending = Some(Pending {
+            skill: skill.clone(),
+            resolve_on: turn + skill.warmup,
+        });
+    }
+    if skill.cooldown == 0 && skill.charges == 0 {
+        return;
+    }
+    let timer = state.timer_mut(skill.id);
+    timer.ready_on = turn + skill.cooldown + 1;
+    if skill.charges > 0 {
+        timer.recharges.push(if skill.recharge > 0 {
+            turn + skill.recharge
+        } else {
+            u32::MAX
+        });
+    }
+}
+
+/// The ready skill of `entity` with the most power, for entities that
+/// choose for themselves.
+pub fn pick_skill(entity: &Entity) -> Option<Skill> {
+    entity
+        .skills
+        .iter()
+        .filter(|skill| blocker(entity, skill).is_none())
+        .max_by_key(|skill| skill.power)
+        .cloned()
+}
+
+/// Forget everything about the encounter `entity` was in.
+pub fn end_encounter(entity: &mut Entity) {
+    effect::clear(entity);
+    entity.combat = CombatState::default();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::effect::{self, Effect};
+    use crate::game::fixtures::{fighter, skill};
+
+    fn mage(skills: Vec<Skill>) -> Entity {
+        fighter(1, "Mira", 60, 4, skills)
+    }
+
+    /// Take a turn using `skill`, the way `combat::take_turn` does.
+    fn use_skill(entity: &mut Entity, skill: &Skill) {
+        assert_eq!(start_turn(entity, true), None);
+        assert_eq!(blocker(entity, skill), None);
+        spend(entity, skill);
+    }
+
+    #[test]
+    fn cooldowns_count_the_users_own_turns() {
+        let mut cleave = skill(2, "Cleave", 13);
+        cleave.cooldown = 2;
+        let mut mira = mage(vec![cleave.clone()]);
+        use_skill(&mut mira, &cleave);
+        assert_eq!(blocker(&mira, &cleave).as_deref(), Some("ready in 2 turns"));
+        start_turn(&mut mira, true);
+        assert_eq!(blocker(&mira, &cleave).as_deref(), Some("ready in 1 turn"));
+        start_turn(&mut mira, true);
+        assert_eq!(blocker(&mira, &cleave), None);
+    }
+
+    #[test]
+    fn spent_charges_come_back_one_at_a_time() {
+        let mut fireball = skill(2, "Fireball", 10);
+        fireball.charges = 2;
+        fireball.recharge = 3;
+        let mut mira = mage(vec![fireball.clone()]);
+        use_skill(&mut mira, &fireball);
+        assert_eq!(
+            status(&mira, &fireball),
+            "ready, 1/2 charges, next in 2 turns"
+        );
+        use_skill(&mut mira, &fireball);
+        assert_eq!(
+            blocker(&mira, &fireball).as_deref(),
+            Some("out of charges, one back in 1 turn")
+        );
+        start_turn(&mut mira, true);
+        assert_eq!(
+            status(&mira, &fireball),
+            "ready, 1/2 charges, next in 1 turn"
+        );
+        start_turn(&mut mira, true);
+        assert_eq!(status(&mira, &fireball), "ready, 2/2 charges");
+    }
+
+    #[test]
+    fn charges_without_a_recharge_last_the_encounter() {
+        let mut wolf = skill(3, "Call Spirit Wolf", 0);
+        wolf.charges = 1;
+        let mut mira = mage(vec![wolf.clone()]);
+        use_skill(&mut mira, &wolf);
+        for _ in 0..5 {
+            start_turn(&mut mira, true);
+            assert_eq!(blocker(&mira, &wolf).as_deref(), Some("out of charges"));
+        }
+        end_encounter(&mut mira);
+        assert_eq!(blocker(&mira, &wolf), None);
+    }
+
+    #[test]
+    fn warmups_take_effect_later_and_leave_the_user_free() {
+        let strike = skill(1, "Strike", 4);
+        let mut meteor = skill(2, "Meteor", 24);
+        meteor.warmup = 2;
+        let mut mira = mage(vec![strike.clone(), meteor.clone()]);
+        use_skill(&mut mira, &meteor);
+        assert_eq!(status(&mira, &meteor), "takes effect in 2 turns");
+        assert_eq!(
+            blocker(&mira, &meteor).as_deref(),
+            Some("already warming up Meteor")
+        );
+        use_skill(&mut mira, &strike);
+        assert_eq!(start_turn(&mut mira, true), Some(meteor));
+        assert!(mira.combat.pending.is_none());
+    }
+
+    #[test]
+    fn lost_turns_hold_up_a_channel() {
+        let mut meteor = skill(2, "Meteor", 24);
+        meteor.warmup = 2;
+        meteor.channelled = true;
+        let strike = skill(1, "Strike", 4);
+        let mut mira = mage(vec![strike.clone(), meteor.clone()]);
+        use_skill(&mut mira, &meteor);
+        assert_eq!(
+            blocker(&mira, &strike).as_deref(),
+            Some("busy channelling Meteor")
+        );
+
+        // A knockback pushes the channel back as far as it pushes her
+        let mut shove = skill(3, "Shove", 0);
+        shove.effects = vec![Effect::Knockback { turns: 1 }];
+        let mut goblin = fighter(2, "Goblin", 30, 2, vec![shove.clone()]);
+        effect::execute(&shove, &mut goblin, &mut mira);
+        assert!(!effect::start_turn(&mut mira));
+        assert_eq!(start_turn(&mut mira, false), None);
+        assert_eq!(start_turn(&mut mira, true), None);
+        assert_eq!(channelling(&mira).map(|skill| skill.id), Some(2));
+
+        // Losing the turn it was due waits for the next one she gets
+        assert_eq!(start_turn(&mut mira, false), None);
+        assert_eq!(start_turn(&mut mira, true), Some(meteor));
+        assert_eq!(channelling(&mira), None);
+        assert_eq!(blocker(&mira, &strike), None);
+    }
+}
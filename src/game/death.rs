@@ -0,0 +1,149 @@
+// src/game/death.rs
+use crate::game::config::DeathPolicy;
+use crate::game::error::{GameError, GameResult};
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::{combat, cooldown, dungeon, world};
+
+/// What became of a character that died.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeathOutcome {
+    /// Moved to the graveyard; no character is selected any more.
+    Buried,
+    /// Back at camp with full HP, minus the penalty.
+    Revived,
+}
+
+/// Apply `policy` to the current character, who has just died fighting the
+/// current enemy.
+pub fn resolve_death(state: &mut GameState, policy: DeathPolicy) -> GameResult<DeathOutcome> {
+    let cause = match state.get_enemy() {
+        Ok(enemy) => format!("Slain by {}", enemy.name),
+        Err(_) => "Died".to_string(),
+    };
+    let index = state
+        .player_index
+        .ok_or_else(|| GameError::InvalidState("No character loaded.".into()))?;
+    let outcome = resolve_death_at(state, index, policy, &cause)?;
+    if outcome == DeathOutcome::Revived {
+        combat::end_encounter(state);
+        // The enemy that did it stays behind
+        state.remove_enemy(state.enemy_index);
+        if state.world.is_empty() {
+            state.populate_enemies()?;
+        } else {
+            dungeon::end_run(state);
+            let start = state.world.start.clone();
+            world::enter_room(state, &start)?;
+        }
+    }
+    Ok(outcome)
+}
+
+/// Apply `policy` to every character that is already dead, e.g. in a save
+/// from before death policies existed.
+pub fn settle_fallen(state: &mut GameState, policy: DeathPolicy) -> GameResult<()> {
+    let mut index = 0;
+    while index < state.players.len() {
+        if state.players[index].stats.hp > 0
+            || resolve_death_at(state, index, policy, "Died")? == DeathOutcome::Revived
+        {
+            index += 1;
+        }
+    }
+    Ok(())
+}
+
+fn resolve_death_at(
+    state: &mut GameState,
+    index: usize,
+    policy: DeathPolicy,
+    cause: &str,
+) -> GameResult<DeathOutcome> {
+    if policy == DeathPolicy::Permadeath {
+        let name = state.players[index].name.clone();
+        state.bury_player(index, cause)?;
+        say!(
+            "{} has been laid to rest in the graveyard. Cause of death: {}.",
+            name,
+            cause
+        );
+        return Ok(DeathOutcome::Buried);
+    }
+    // Statuses change stats directly, so they have to come off before the
+    // character is healed up
+    cooldown::end_encounter(&mut state.players[index]);
+    let full_hp = state.full_hp(&state.players[index]).max(1);
+    let (gold_loss, xp_loss) = policy.penalty();
+    let player = &mut state.players[index];
+    let gold_lost = player.gold * gold_loss / 100;
+    let xp_lost = player.experience * xp_loss / 100;
+    player.gold -= gold_lost;
+    player.experience -= xp_lost;
+    player.stats.hp = full_hp;
+    say!(
+        "{} wakes up back at camp, {} gold and {} XP poorer.",
+        player.name,
+        gold_lost,
+        xp_lost
+    );
+    Ok(DeathOutcome::Revived)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::effect::{self, Effect, Status, Target};
+    use crate::game::fixtures::{self, skill};
+    use crate::game::stats::Stats;
+
+    /// Weaken the current character, as a goblin's curse would.
+    fn curse(state: &mut GameState) {
+        let mut curse = skill(3, "Curse", 0);
+        curse.effects = vec![Effect::ApplyStatus {
+            status: Status {
+                name: "cursed".to_string(),
+                turns: 5,
+                modifier: Stats {
+                    hp: 0,
+                    attack: -3,
+                    defense: 0,
+                    agility: 0,
+                },
+                per_turn: 0,
+                stun: false,
+            },
+            target: Target::Foe,
+            chance: 100,
+        }];
+        let mut goblin = state.enemies[0].clone();
+        effect::execute(&curse, &mut goblin, state.get_player_mut().unwrap());
+    }
+
+    #[test]
+    fn revived_characters_shed_their_statuses() {
+        let mut state = fixtures::arena();
+        curse(&mut state);
+        let player = state.get_player_mut().unwrap();
+        assert_eq!(player.stats.attack, 1);
+        player.stats.hp = 0;
+
+        let outcome = resolve_death(&mut state, DeathPolicy::Revive).unwrap();
+        assert_eq!(outcome, DeathOutcome::Revived);
+        let player = state.get_player().unwrap();
+        assert!(player.combat.statuses.is_empty());
+        assert!(player.combat.is_idle());
+        assert_eq!(player.stats.attack, 4);
+        assert_eq!(player.stats.hp, 60);
+    }
+
+    #[test]
+    fn permadeath_buries_the_character() {
+        let mut state = fixtures::arena();
+        state.get_player_mut().unwrap().stats.hp = 0;
+        let outcome = resolve_death(&mut state, DeathPolicy::Permadeath).unwrap();
+        assert_eq!(outcome, DeathOutcome::Buried);
+        assert!(state.players.is_empty());
+        assert_eq!(state.graveyard[0].cause, "Slain by Goblin");
+    }
+}
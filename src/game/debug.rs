@@ -0,0 +1,254 @@
+// src/game/debug.rs
+use crate::game::command::{resolve_index, CommandSpec};
+use crate::game::error::{GameError, GameResult};
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::rng;
+use std::cell::Cell;
+use std::fs;
+
+thread_local! {
+    static VERBOSE_COMBAT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether damage rolls print how they were worked out.
+pub fn verbose_combat() -> bool {
+    VERBOSE_COMBAT.with(Cell::get)
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> GameResult<T> {
+    value
+        .parse()
+        .map_err(|_| GameError::InvalidInput(format!("'{}' is not a valid {}", value, what)))
+}
+
+/// Runs one of the `--debug` commands from `command::GAME_COMMANDS`.
+pub fn process_command(
+    state: &mut GameState,
+    spec: &CommandSpec,
+    args: &[String],
+) -> GameResult<()> {
+    match spec.name {
+        "spawn" => {
+            let index = resolve_index(&state.npc_ents, spec.arg(args, 0)?, "NPC")?;
+            let npc = state.npc_ents[index].clone();
+            say!("Spawned {} (id {}).", npc.name, npc.id);
+            state.create_enemy(npc);
+            state.set_enemy(state.enemies.len() - 1)
+        }
+        "give" => {
+            let index = resolve_index(&state.items, spec.arg(args, 0)?, "item")?;
+            let item = state.items[index].clone();
+            let count: u32 = match args.get(1) {
+                Some(count) => parse_number(count, "count")?,
+                None => 1,
+            };
+            let player = state.get_player_mut()?;
+            for _ in 0..count {
+                player.add_item_to_inventory(item.clone());
+            }
+            say!("Gave {} x{}.", item.name, count);
+            Ok(())
+        }
+        "set" => {
+            let name = spec.arg(args, 0)?;
+            let value: i32 = parse_number(spec.arg(args, 1)?, "stat value")?;
+            let player = state.get_player_mut()?;
+            let stat = player.stats.get_mut(name).ok_or_else(|| {
+                GameError::InvalidInput(format!(
+                    "unknown stat '{}'; use hp, attack, defense or agility",
+                    name
+                ))
+            })?;
+            *stat = value;
+            say!("Set {} to {}.", name.to_ascii_lowercase(), value);
+            Ok(())
+        }
+        "heal" => {
+            let full_hp = state.full_hp(state.get_player()?);
+            let player = state.get_player_mut()?;
+            player.stats.hp = match args.first() {
+                Some(amount) => player.stats.hp + parse_number::<i32>(amount, "amount")?,
+                None => player.stats.hp.max(full_hp),
+            };
+            say!("{} has {} HP.", player.name, player.stats.hp);
+            Ok(())
+        }
+        "kill" => {
+            let enemy = state.get_enemy()?;
+            say!("{} is defeated!", enemy.name);
+            // No rewards, but it counts for dungeons, quests and reputation
+            super::enemy_defeated(state)
+        }
+        "seed" => {
+            let seed: u64 = parse_number(spec.arg(args, 0)?, "seed")?;
+            rng::seed(seed);
+            say!("Seeded the random number generator with {}.", seed);
+            Ok(())
+        }
+        "dump" => {
+            let json = serde_json::to_string_pretty(state)
+                .map_err(|e| GameError::Content(format!("cannot serialize game state: {}", e)))?;
+            match args.first() {
+                Some(file) => {
+                    fs::write(file, json)?;
+                    say!("Dumped game state to {}.", file);
+                }
+                None => say!("{}", json),
+            }
+            Ok(())
+        }
+        "verbose" => {
+            let on = match args.first().map(|s| s.to_ascii_lowercase()) {
+                Some(switch) if switch == "on" => true,
+                Some(switch) if switch == "off" => false,
+                Some(switch) => {
+                    return Err(GameError::InvalidInput(format!(
+                        "expected 'on' or 'off', got '{}'",
+                        switch
+                    )))
+                }
+                None => !verbose_combat(),
+            };
+            VERBOSE_COMBAT.with(|verbose| verbose.set(on));
+            say!(
+                "Verbose combat math {}.",
+                if on { "enabled" } else { "disabled" }
+            );
+            Ok(())
+        }
+        _ => Err(GameError::InvalidState(format!(
+            "'{}' can't be used here.",
+            spec.name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::command::{self, GAME_COMMANDS};
+    use crate::game::fixtures;
+    use crate::game::item::{Item, ItemType};
+    use crate::game::log;
+    use crate::game::stats::Stats;
+
+    fn run(state: &mut GameState, input: &str) -> (GameResult<()>, Vec<String>) {
+        let mut words = input.split_whitespace();
+        let spec = command::find(GAME_COMMANDS, words.next().unwrap(), true).unwrap();
+        let args: Vec<String> = words.map(str::to_string).collect();
+        log::capture(|| process_command(state, spec, &args))
+    }
+
+    fn ok(state: &mut GameState, input: &str) -> Vec<String> {
+        let (result, lines) = run(state, input);
+        result.unwrap();
+        lines
+    }
+
+    fn missing(state: &mut GameState, input: &str) -> bool {
+        matches!(run(state, input).0, Err(GameError::InvalidInput(message)) if message.starts_with("missing"))
+    }
+
+    #[test]
+    fn spawn_adds_and_selects_an_enemy() {
+        let mut state = fixtures::arena();
+        state.enemies.clear();
+        assert_eq!(ok(&mut state, "spawn goblin"), ["Spawned Goblin (id 100)."]);
+        assert_eq!(ok(&mut state, "spawn 100"), ["Spawned Goblin (id 100)."]);
+        assert_eq!(state.enemies.len(), 2);
+        assert_eq!(state.enemy_index, 1);
+        assert!(missing(&mut state, "spawn"));
+    }
+
+    #[test]
+    fn give_adds_items() {
+        let mut state = fixtures::arena();
+        state.items = vec![Item::_new(
+            1,
+            "Potion",
+            "",
+            ItemType::Consumable,
+            Stats::new(),
+        )];
+        assert_eq!(ok(&mut state, "give potion 3"), ["Gave Potion x3."]);
+        assert_eq!(ok(&mut state, "give 1"), ["Gave Potion x1."]);
+        assert_eq!(state.get_player().unwrap().inventory.len(), 4);
+        assert!(run(&mut state, "give potion many").0.is_err());
+        assert!(missing(&mut state, "give"));
+    }
+
+    #[test]
+    fn set_changes_a_stat() {
+        let mut state = fixtures::arena();
+        assert_eq!(ok(&mut state, "set Attack 9"), ["Set attack to 9."]);
+        assert_eq!(state.get_player().unwrap().stats.attack, 9);
+        assert!(run(&mut state, "set luck 9").0.is_err());
+        assert!(run(&mut state, "set attack lots").0.is_err());
+        assert!(missing(&mut state, "set attack"));
+        assert!(missing(&mut state, "set"));
+    }
+
+    #[test]
+    fn heal_tops_up_or_adds() {
+        let mut state = fixtures::arena();
+        state.get_player_mut().unwrap().stats.hp = 10;
+        assert_eq!(ok(&mut state, "heal 5"), ["Mira has 15 HP."]);
+        assert_eq!(ok(&mut state, "heal"), ["Mira has 60 HP."]);
+    }
+
+    #[test]
+    fn kill_defeats_the_current_enemy() {
+        let mut state = fixtures::arena();
+        let enemies = state.enemies.len();
+        assert_eq!(ok(&mut state, "kill")[0], "Goblin is defeated!");
+        assert_eq!(state.enemies.len(), enemies - 1);
+    }
+
+    #[test]
+    fn seed_makes_rolls_repeat() {
+        let mut state = fixtures::arena();
+        ok(&mut state, "seed 42");
+        let first: Vec<u32> = (0..5).map(|_| rng::gen_range(0..1000)).collect();
+        ok(&mut state, "seed 42");
+        let second: Vec<u32> = (0..5).map(|_| rng::gen_range(0..1000)).collect();
+        assert_eq!(first, second);
+        assert!(run(&mut state, "seed soon").0.is_err());
+        assert!(missing(&mut state, "seed"));
+    }
+
+    #[test]
+    fn dump_writes_the_state() {
+        let mut state = fixtures::arena();
+        let printed = ok(&mut state, "dump").join("\n");
+        let expected = serde_json::to_value(&state).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&printed).unwrap(),
+            expected
+        );
+
+        let dir = fixtures::temp_dir("dump");
+        let file = dir.join("state.json");
+        ok(&mut state, &format!("dump {}", file.display()));
+        let written = fs::read_to_string(&file).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&written).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn verbose_switches_combat_math() {
+        let mut state = fixtures::arena();
+        assert_eq!(
+            ok(&mut state, "verbose on"),
+            ["Verbose combat math enabled."]
+        );
+        assert!(verbose_combat());
+        ok(&mut state, "verbose");
+        assert!(!verbose_combat());
+        assert!(run(&mut state, "verbose maybe").0.is_err());
+        assert!(!verbose_combat());
+    }
+}
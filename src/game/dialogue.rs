@@ -0,0 +1,424 @@
+// src/game/dialogue.rs
+use crate::game::command::resolve_index;
+use crate::game::entity::Entity;
+use crate::game::error::{GameError, GameResult};
+use crate::game::faction;
+use crate::game::item::Item;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::quest::{self, QuestEvent};
+use crate::game::world;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DialoguesWrapper {
+    pub(crate) dialogues: Vec<Dialogue>,
+}
+
+/// What an NPC has to say, from `dialogue.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dialogue {
+    pub npc: u32,
+    /// Id of the node a conversation opens with.
+    pub start: String,
+    pub nodes: Vec<Node>,
+}
+
+/// One thing the NPC says, and what the player can answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Node {
+    pub id: String,
+    pub text: String,
+    /// Run in order whenever the conversation reaches this node.
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    /// No choices, or none the player qualifies for, ends the conversation.
+    #[serde(default)]
+    pub choices: Vec<Choice>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Choice {
+    pub text: String,
+    /// Node the choice leads to; none ends the conversation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    /// All must hold for the choice to be offered.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    QuestNotStarted {
+        quest: u32,
+    },
+    QuestActive {
+        quest: u32,
+    },
+    QuestCompleted {
+        quest: u32,
+    },
+    /// Carry at least `count` of item `item`.
+    HasItem {
+        item: u32,
+        #[serde(default = "one")]
+        count: u32,
+    },
+    /// Stat `stat` (hp, attack, defense or agility) is at least `min`.
+    Stat {
+        stat: String,
+        min: i32,
+    },
+    /// Reputation with faction `faction` is at least `min`.
+    Reputation {
+        faction: String,
+        min: i32,
+    },
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    GiveItem {
+        item: u32,
+    },
+    StartQuest {
+        quest: u32,
+    },
+    /// Offer items for gold until the player leaves the room.
+    OpenShop {
+        stock: Vec<Stock>,
+    },
+    /// The NPC turns on the player and becomes the current enemy.
+    StartCombat,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stock {
+    pub item: u32,
+    /// Price to a neutral customer, see `faction::price`.
+    pub price: u32,
+}
+
+/// A conversation a character is having, saved with the character. It
+/// lasts until the character leaves the room.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub npc: u32,
+    /// Node waiting for the player to choose; `None` once the talking is
+    /// over.
+    pub node: Option<String>,
+    /// What the NPC sells, once they have opened their shop.
+    #[serde(default)]
+    pub shop: Vec<Stock>,
+}
+
+impl Dialogue {
+    pub fn node(&self, id: &str) -> Option<&Node> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+}
+
+impl Condition {
+    fn holds(&self, state: &GameState, player: &Entity) -> bool {
+        match self {
+            Condition::QuestNotStarted { quest } => quest::status(state, *quest).is_none(),
+            Condition::QuestActive { quest } => quest::status(state, *quest) == Some(false),
+            Condition::QuestCompleted { quest } => quest::status(state, *quest) == Some(true),
+            Condition::HasItem { item, count } => {
+                player
+                    .inventory
+                    .iter()
+                    .chain(player.equipment.iter())
+                    .filter(|carried| carried.id == *item)
+                    .count() as u32
+                    >= *count
+            }
+            Condition::Stat { stat, min } => player.stats.get(stat).is_some_and(|v| v >= *min),
+            Condition::Reputation { faction, min } => {
+                faction::reputation(state, Some(player), faction) >= *min
+            }
+        }
+    }
+}
+
+/// The dialogue of NPC `npc`, if they have one.
+pub fn dialogue_of(state: &GameState, npc: u32) -> Option<&Dialogue> {
+    state.dialogues.iter().find(|dialogue| dialogue.npc == npc)
+}
+
+/// The people in the current room who will still talk to the player, as
+/// NPC definitions.
+pub fn people(state: &GameState) -> GameResult<Vec<Entity>> {
+    let (_, room) = world::current_room(state)?;
+    let mut people = Vec::new();
+    for id in &room.npcs {
+        let npc = state.npc(*id)?;
+        if !faction::is_hostile(state, &npc) {
+            people.push(npc);
+        }
+    }
+    Ok(people)
+}
+
+/// The choices of `node` the current character qualifies for.
+fn open_choices<'a>(state: &GameState, node: &'a Node) -> GameResult<Vec<&'a Choice>> {
+    let player = state.get_player()?;
+    Ok(node
+        .choices
+        .iter()
+        .filter(|choice| {
+            choice
+                .conditions
+                .iter()
+                .all(|condition| condition.holds(state, player))
+        })
+        .collect())
+}
+
+/// Start talking to someone in the current room.
+pub fn talk(state: &mut GameState, npc_ref: &str) -> GameResult<()> {
+    state.get_player()?;
+    let people = people(state)?;
+    if people.is_empty() {
+        return Err(GameError::NotFound(
+            "there is nobody here to talk to".into(),
+        ));
+    }
+    let npc = people[resolve_index(&people, npc_ref, "person")?].clone();
+    let Some(dialogue) = dialogue_of(state, npc.id) else {
+        say!("{} has nothing to say.", npc.name);
+        return Ok(());
+    };
+    let start = dialogue.start.clone();
+    state.get_player_mut()?.conversation = Some(Conversation {
+        npc: npc.id,
+        node: None,
+        shop: Vec::new(),
+    });
+    quest::notify(state, QuestEvent::Talked(npc.id))?;
+    enter_node(state, &npc, &start)
+}
+
+/// Answer the conversation with the `choice`th of the offered choices.
+pub fn choose(state: &mut GameState, choice: &str) -> GameResult<()> {
+    let (npc, node_id) = match &state.get_player()?.conversation {
+        Some(Conversation {
+            npc,
+            node: Some(node),
+            ..
+        }) => (*npc, node.clone()),
+        _ => {
+            return Err(GameError::InvalidState(
+                "You are not talking to anyone.".into(),
+            ))
+        }
+    };
+    let npc = state.npc(npc)?;
+    let node = dialogue_of(state, npc.id)
+        .and_then(|dialogue| dialogue.node(&node_id))
+        .cloned()
+        .ok_or_else(|| GameError::Content(format!("no dialogue node '{}'", node_id)))?;
+    let choices = open_choices(state, &node)?;
+    let number: usize = choice
+        .trim()
+        .parse()
+        .ok()
+        .filter(|n| (1..=choices.len()).contains(n))
+        .ok_or_else(|| {
+            GameError::InvalidInput(format!(
+                "'{}' is not a choice; pick 1 to {}",
+                choice,
+                choices.len()
+            ))
+        })?;
+    let choice = choices[number - 1];
+    say!("> {}", choice.text);
+    match choice.next.clone() {
+        Some(next) => enter_node(state, &npc, &next),
+        None => {
+            end(state);
+            Ok(())
+        }
+    }
+}
+
+/// Say node `id`, run its actions and offer its choices.
+fn enter_node(state: &mut GameState, npc: &Entity, id: &str) -> GameResult<()> {
+    let node = dialogue_of(state, npc.id)
+        .and_then(|dialogue| dialogue.node(id))
+        .cloned()
+        .ok_or_else(|| GameError::Content(format!("no dialogue node '{}'", id)))?;
+    say!("{}: \"{}\"", npc.name, node.text);
+    for action in &node.actions {
+        run_action(state, npc, action)?;
+        if state.get_player()?.conversation.is_none() {
+            // The NPC attacked
+            return Ok(());
+        }
+    }
+    let choices = open_choices(state, &node)?;
+    if choices.is_empty() {
+        end(state);
+        return Ok(());
+    }
+    let mut text = String::new();
+    for (i, choice) in choices.iter().enumerate() {
+        text.push_str(&format!("\n    {}. {}", i + 1, choice.text));
+    }
+    say!("Choose with 'choose <number>':{}", text);
+    if let Some(conversation) = &mut state.get_player_mut()?.conversation {
+        conversation.node = Some(node.id);
+    }
+    Ok(())
+}
+
+/// Stop talking, keeping the shop open if there is one.
+fn end(state: &mut GameState) {
+    say!("The conversation ends.");
+    let Ok(player) = state.get_player_mut() else {
+        return;
+    };
+    match &mut player.conversation {
+        Some(conversation) if !conversation.shop.is_empty() => conversation.node = None,
+        _ => player.conversation = None,
+    }
+}
+
+fn run_action(state: &mut GameState, npc: &Entity, action: &Action) -> GameResult<()> {
+    match action {
+        Action::GiveItem { item } => {
+            let item = state
+                .get_item_by_id(*item)
+                .ok_or_else(|| GameError::Content(format!("no item with id {}", item)))?;
+            say!("{} gives you {}.", npc.name, item.name);
+            state.get_player_mut()?.add_item_to_inventory(item);
+            quest::notify(state, QuestEvent::PickedUp)
+        }
+        Action::StartQuest { quest } => quest::start(state, *quest),
+        Action::OpenShop { stock } => {
+            if let Some(conversation) = &mut state.get_player_mut()?.conversation {
+                conversation.shop = stock.clone();
+            }
+            show_shop(state)
+        }
+        Action::StartCombat => {
+            state.get_player_mut()?.conversation = None;
+            say!("{} attacks!", npc.name);
+            state.create_enemy(npc.clone());
+            state.set_enemy(state.enemies.len() - 1)
+        }
+    }
+}
+
+/// The items of the open shop along with what the shopkeeper charges the
+/// current character for them.
+fn shop_items(state: &GameState) -> GameResult<Vec<(Item, u32)>> {
+    let conversation = match &state.get_player()?.conversation {
+        Some(conversation) if !conversation.shop.is_empty() => conversation,
+        _ => {
+            return Err(GameError::InvalidState(
+                "Nobody here is selling anything.".into(),
+            ))
+        }
+    };
+    let shopkeeper = state.npc(conversation.npc)?;
+    Ok(conversation
+        .shop
+        .iter()
+        .filter_map(|stock| {
+            state
+                .get_item_by_id(stock.item)
+                .map(|item| (item, faction::price(state, &shopkeeper, stock.price)))
+        })
+        .collect())
+}
+
+/// List what the open shop sells.
+pub fn show_shop(state: &GameState) -> GameResult<()> {
+    let mut text = String::from("For sale:");
+    for (i, (item, price)) in shop_items(state)?.iter().enumerate() {
+        text.push_str(&format!("\n    {}. {} - {} gold", i + 1, item.name, price));
+    }
+    say!("{}\nBuy with 'buy <item>'.", text);
+    Ok(())
+}
+
+/// Buy an item from the open shop.
+pub fn buy(state: &mut GameState, item_ref: &str) -> GameResult<()> {
+    let stock = shop_items(state)?;
+    let items: Vec<Item> = stock.iter().map(|(item, _)| item.clone()).collect();
+    let (item, price) = stock[resolve_index(&items, item_ref, "item for sale")?].clone();
+    let player = state.get_player_mut()?;
+    if player.gold < price {
+        return Err(GameError::InvalidState(format!(
+            "{} costs {} gold; you have {}.",
+            item.name, price, player.gold
+        )));
+    }
+    player.gold -= price;
+    say!("You buy {} for {} gold.", item.name, price);
+    player.add_item_to_inventory(item);
+    quest::notify(state, QuestEvent::PickedUp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+
+    /// The village with Marta in the square, who says hello.
+    fn village() -> GameState {
+        let mut state = fixtures::village();
+        let mut marta = fixtures::fighter(200, "Marta", 20, 1, Vec::new());
+        marta.faction = Some("villagers".to_string());
+        state.npc_ents.push(marta);
+        state.world.zones[0].rooms[0].npcs.push(200);
+        state.dialogues.push(Dialogue {
+            npc: 200,
+            start: "hello".to_string(),
+            nodes: vec![Node {
+                id: "hello".to_string(),
+                text: "Hello there.".to_string(),
+                actions: Vec::new(),
+                choices: vec![Choice {
+                    text: "Goodbye.".to_string(),
+                    next: None,
+                    conditions: Vec::new(),
+                }],
+            }],
+        });
+        state
+    }
+
+    #[test]
+    fn conversations_stay_with_their_character() {
+        let mut state = village();
+        talk(&mut state, "Marta").unwrap();
+
+        state.set_player(1).unwrap();
+        assert!(state.get_player().unwrap().conversation.is_none());
+        assert!(matches!(
+            choose(&mut state, "1"),
+            Err(GameError::InvalidState(_))
+        ));
+
+        state.set_player(0).unwrap();
+        choose(&mut state, "1").unwrap();
+        assert!(state.get_player().unwrap().conversation.is_none());
+    }
+
+    #[test]
+    fn leaving_the_room_ends_the_conversation() {
+        let mut state = village();
+        talk(&mut state, "Marta").unwrap();
+        world::walk(&mut state, "north").unwrap();
+        assert!(state.get_player().unwrap().conversation.is_none());
+    }
+}
@@ -0,0 +1,471 @@
+// src/game/dungeon.rs
+use crate::game::error::{GameError, GameResult};
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::rng;
+use crate::game::world::{self, Room, Spawn, Zone};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Room ids of generated floors start with this, so they never clash with
+/// the rooms of the world map.
+const ROOM_PREFIX: &str = "dungeon/";
+
+const DIRECTIONS: [(&str, &str, (i32, i32)); 4] = [
+    ("north", "south", (0, -1)),
+    ("south", "north", (0, 1)),
+    ("east", "west", (1, 0)),
+    ("west", "east", (-1, 0)),
+];
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct DungeonsWrapper {
+    pub(crate) themes: Vec<DungeonTheme>,
+}
+
+/// What a dungeon is built from, from `dungeons.json`. World rooms with a
+/// `dungeon` field lead into the theme with that id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DungeonTheme {
+    pub id: String,
+    pub name: String,
+    /// Description of every ordinary room.
+    pub description: String,
+    /// Names given to ordinary rooms at random.
+    #[serde(default)]
+    pub room_names: Vec<String>,
+    /// Floors down to the boss, which waits on the last one.
+    pub floors: u32,
+    pub min_rooms: u32,
+    pub max_rooms: u32,
+    /// NPC ids that roam the floors.
+    pub enemies: Vec<u32>,
+    /// How much tougher enemies get on every floor further down.
+    #[serde(default = "one")]
+    pub levels_per_floor: u32,
+    pub boss: u32,
+    pub boss_level: u32,
+    /// Item ids found in treasure rooms.
+    #[serde(default)]
+    pub treasure: Vec<u32>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// A dungeon a character is in, saved with the character.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DungeonRun {
+    pub theme: String,
+    pub seed: u64,
+    /// Floor number, from 1.
+    pub depth: u32,
+    /// World room the player came down from and returns to.
+    pub entrance: String,
+    /// The floor the player is on. Rebuilt from `seed` and `depth` it is
+    /// always the same, but it also records a defeated boss.
+    pub floor: Zone,
+    /// Room with the stairway down, or the boss on the last floor.
+    pub stairs: String,
+    /// Whether the boss has been defeated.
+    pub cleared: bool,
+}
+
+impl DungeonRun {
+    pub fn room(&self, id: &str) -> Option<(&Zone, &Room)> {
+        self.floor
+            .rooms
+            .iter()
+            .find(|room| room.id == id)
+            .map(|room| (&self.floor, room))
+    }
+}
+
+/// Build floor `depth` of a `theme` dungeon. The same seed, theme and depth
+/// always give the same rooms, exits, spawns and treasure. Returns the floor
+/// and the id of its stairway room.
+pub fn generate_floor(theme: &DungeonTheme, seed: u64, depth: u32) -> (Zone, String) {
+    let mut gen = rng::generator(seed, depth as u64);
+    let count = gen
+        .gen_range(theme.min_rooms.max(2)..theme.max_rooms.max(theme.min_rooms).max(2) + 1)
+        as usize;
+    let id = |index: usize| format!("{}{}/{}", ROOM_PREFIX, depth, index);
+
+    // Rooms are cells of a grid, each new one dug out next to an old one
+    let mut cells = vec![(0, 0)];
+    let mut exits: Vec<BTreeMap<String, String>> = vec![BTreeMap::new()];
+    while cells.len() < count {
+        let from = gen.gen_range(0..cells.len());
+        let (direction, back, (dx, dy)) = DIRECTIONS[gen.gen_range(0..DIRECTIONS.len())];
+        let cell = (cells[from].0 + dx, cells[from].1 + dy);
+        if cells.contains(&cell) {
+            continue;
+        }
+        let index = cells.len();
+        cells.push(cell);
+        exits[from].insert(direction.to_string(), id(index));
+        exits.push(BTreeMap::from([(back.to_string(), id(from))]));
+    }
+
+    // The stairway is as far from the landing as the floor goes
+    let mut distance = vec![usize::MAX; count];
+    distance[0] = 0;
+    let mut queue = VecDeque::from([0]);
+    while let Some(index) = queue.pop_front() {
+        for target in exits[index].values() {
+            let next = room_index(target);
+            if distance[next] == usize::MAX {
+                distance[next] = distance[index] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    let stairs = (0..count).max_by_key(|i| (distance[*i], *i)).unwrap_or(0);
+    let treasure = (count > 2).then(|| loop {
+        let index = gen.gen_range(1..count);
+        if index != stairs {
+            break index;
+        }
+    });
+
+    let last_floor = depth >= theme.floors;
+    let min_level = 1 + (depth - 1) * theme.levels_per_floor;
+    let spawns: Vec<Spawn> = theme
+        .enemies
+        .iter()
+        .map(|npc| Spawn {
+            npc: *npc,
+            min_level,
+            max_level: min_level + theme.levels_per_floor,
+        })
+        .collect();
+
+    let mut rooms = Vec::with_capacity(count);
+    for (index, exits) in exits.into_iter().enumerate() {
+        let mut room = Room {
+            id: id(index),
+            name: match theme.room_names.len() {
+                0 => "Chamber".to_string(),
+                n => theme.room_names[gen.gen_range(0..n)].clone(),
+            },
+            description: theme.description.clone(),
+            exits,
+            spawns: spawns.clone(),
+            max_enemies: 2,
+            items: Vec::new(),
+            npcs: Vec::new(),
+            dungeon: None,
+        };
+        if index == 0 {
+            room.name = "Landing".to_string();
+            room.description = if depth == 1 {
+                "Daylight still reaches down here. Type 'leave' to climb back out.".to_string()
+            } else {
+                "The stairs from the floor above end here.".to_string()
+            };
+            room.spawns.clear();
+        } else if index == stairs && last_floor {
+            room.name = "Lair".to_string();
+            room.description =
+                "Bones crunch underfoot. Whatever rules this place is waiting.".to_string();
+            room.spawns = vec![Spawn {
+                npc: theme.boss,
+                min_level: theme.boss_level,
+                max_level: theme.boss_level,
+            }];
+            room.max_enemies = 1;
+        } else if index == stairs {
+            room.name = "Stairway".to_string();
+            room.description =
+                "Worn steps lead further down. Type 'descend' to follow them.".to_string();
+        } else if Some(index) == treasure && !theme.treasure.is_empty() {
+            room.name = "Treasure Room".to_string();
+            room.description = "Chests and sacks line the walls.".to_string();
+            for _ in 0..gen.gen_range(1..3) {
+                room.items
+                    .push(theme.treasure[gen.gen_range(0..theme.treasure.len())]);
+            }
+        }
+        rooms.push(room);
+    }
+
+    let floor = Zone {
+        id: format!("{}{}", ROOM_PREFIX, depth),
+        name: format!("{}, floor {}", theme.name, depth),
+        rooms,
+    };
+    (floor, id(stairs))
+}
+
+/// The generator the enemies of room `id` are rolled from, if it is on the
+/// floor of `run`: the same seed meets the same enemies in every room.
+pub fn spawn_generator(run: &DungeonRun, id: &str) -> Option<StdRng> {
+    run.room(id)?;
+    // Streams past the depths, which the floors themselves use
+    let stream = (run.depth as u64) << 32 | (room_index(id) as u64 + 1);
+    Some(rng::generator(run.seed, stream))
+}
+
+/// Position of a generated room from its id.
+fn room_index(id: &str) -> usize {
+    id.rsplit('/')
+        .next()
+        .and_then(|index| index.parse().ok())
+        .unwrap_or(0)
+}
+
+fn theme<'a>(state: &'a GameState, id: &str) -> GameResult<&'a DungeonTheme> {
+    state
+        .dungeons
+        .iter()
+        .find(|theme| theme.id == id)
+        .ok_or_else(|| GameError::Content(format!("no dungeon theme '{}'", id)))
+}
+
+/// Go down: into the dungeon from its entrance, or to the next floor from a
+/// stairway. A new run uses `seed` if given, and a random seed otherwise.
+/// An enemy in the room gets one last hit in first.
+pub fn descend(state: &mut GameState, seed: Option<&str>) -> GameResult<()> {
+    state.get_player()?;
+    let (theme, seed, depth) = match state.dungeon() {
+        None => {
+            let theme_id = world::current_room(state)?
+                .1
+                .dungeon
+                .clone()
+                .ok_or_else(|| GameError::InvalidState("There is no way down here.".into()))?;
+            let seed = match seed {
+                Some(seed) => seed.parse::<u64>().map_err(|_| {
+                    GameError::InvalidInput(format!("'{}' is not a valid seed", seed))
+                })?,
+                None => rng::random(),
+            };
+            (theme(state, &theme_id)?.clone(), seed, 1)
+        }
+        Some(run) => {
+            if seed.is_some() {
+                return Err(GameError::InvalidInput(
+                    "A seed can only be given when entering a dungeon.".into(),
+                ));
+            }
+            if state.location()?.room != run.stairs {
+                return Err(GameError::InvalidState(
+                    "There are no stairs down here.".into(),
+                ));
+            }
+            let theme = theme(state, &run.theme)?.clone();
+            if run.depth >= theme.floors {
+                return Err(GameError::InvalidState(
+                    "This is the bottom of the dungeon.".into(),
+                ));
+            }
+            (theme, run.seed, run.depth + 1)
+        }
+    };
+    if !world::parting_hit(state)? {
+        return Ok(());
+    }
+
+    let (floor, stairs) = generate_floor(&theme, seed, depth);
+    let player = state.get_player_mut()?;
+    match &mut player.dungeon {
+        Some(run) => {
+            say!("You descend to floor {}.", depth);
+            run.depth = depth;
+            run.floor = floor;
+            run.stairs = stairs;
+        }
+        None => {
+            say!("You climb down into {}. (seed {})", theme.name, seed);
+            player.dungeon = Some(DungeonRun {
+                theme: theme.id,
+                seed,
+                depth,
+                entrance: player.location.room.clone(),
+                floor,
+                stairs,
+                cleared: false,
+            });
+        }
+    }
+    forget_rooms(state);
+    world::enter_room(state, &format!("{}{}/0", ROOM_PREFIX, depth))?;
+    world::look(state)
+}
+
+/// Forget the explored rooms and floor items of earlier floors and runs.
+fn forget_rooms(state: &mut GameState) {
+    let Ok(location) = state.location_mut() else {
+        return;
+    };
+    location.visited.retain(|id| !id.starts_with(ROOM_PREFIX));
+    location.floor.retain(|id, _| !id.starts_with(ROOM_PREFIX));
+}
+
+/// End the current run, if any, and return the entrance it started from.
+pub fn end_run(state: &mut GameState) -> Option<String> {
+    let run = state.get_player_mut().ok()?.dungeon.take()?;
+    forget_rooms(state);
+    Some(run.entrance)
+}
+
+/// Climb out of the dungeon, giving up the run unless the boss is dead.
+/// An enemy in the room gets one last hit in first.
+pub fn leave(state: &mut GameState) -> GameResult<()> {
+    let cleared = match state.dungeon() {
+        Some(run) => run.cleared,
+        None => return Err(GameError::InvalidState("You are not in a dungeon.".into())),
+    };
+    if !world::parting_hit(state)? {
+        return Ok(());
+    }
+    if let Some(entrance) = end_run(state) {
+        if cleared {
+            say!("You climb back out, victorious.");
+        } else {
+            say!("You climb back out, leaving the dungeon unconquered.");
+        }
+        world::enter_room(state, &entrance)?;
+        world::look(state)?;
+    }
+    Ok(())
+}
+
+/// Called when the current enemy has been defeated: killing the boss in its
+/// lair clears the dungeon for good.
+pub fn enemy_defeated(state: &mut GameState) -> GameResult<()> {
+    let enemy_id = state.get_enemy()?.id;
+    let Some(run) = state.dungeon() else {
+        return Ok(());
+    };
+    let theme = theme(state, &run.theme)?;
+    if run.cleared
+        || run.depth < theme.floors
+        || run.stairs != state.location()?.room
+        || enemy_id != theme.boss
+    {
+        return Ok(());
+    }
+    say!(
+        "You have conquered {}! Type 'leave' to return to the surface.",
+        theme.name
+    );
+    if let Some(run) = &mut state.get_player_mut()?.dungeon {
+        run.cleared = true;
+        let stairs = run.stairs.clone();
+        if let Some(lair) = run.floor.rooms.iter_mut().find(|room| room.id == stairs) {
+            lair.spawns.clear();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+
+    fn crypt() -> DungeonTheme {
+        DungeonTheme {
+            id: "crypt".to_string(),
+            name: "The Crypt".to_string(),
+            description: "Damp stone walls.".to_string(),
+            room_names: vec!["Ossuary".to_string(), "Vault".to_string()],
+            floors: 3,
+            min_rooms: 4,
+            max_rooms: 8,
+            enemies: vec![1, 2, 3],
+            levels_per_floor: 2,
+            boss: 9,
+            boss_level: 10,
+            treasure: vec![5, 6],
+        }
+    }
+
+    fn run(seed: u64, depth: u32) -> DungeonRun {
+        let (floor, stairs) = generate_floor(&crypt(), seed, depth);
+        DungeonRun {
+            theme: "crypt".to_string(),
+            seed,
+            depth,
+            entrance: "crypt_gate".to_string(),
+            floor,
+            stairs,
+            cleared: false,
+        }
+    }
+
+    #[test]
+    fn same_seed_and_depth_give_the_same_floor() {
+        for depth in 1..=3 {
+            assert_eq!(
+                generate_floor(&crypt(), 42, depth),
+                generate_floor(&crypt(), 42, depth)
+            );
+        }
+        assert_ne!(
+            generate_floor(&crypt(), 42, 1),
+            generate_floor(&crypt(), 43, 1)
+        );
+    }
+
+    #[test]
+    fn floors_connect_every_room_to_the_landing() {
+        let (floor, stairs) = generate_floor(&crypt(), 7, 3);
+        assert!((4..=8).contains(&floor.rooms.len()));
+        assert_eq!(floor.rooms[room_index(&stairs)].name, "Lair");
+        assert!(floor.rooms[0].spawns.is_empty());
+        for room in &floor.rooms[1..] {
+            let back = room.exits.values().any(|target| {
+                floor.rooms[room_index(target)]
+                    .exits
+                    .values()
+                    .any(|id| *id == room.id)
+            });
+            assert!(back, "{} has no way back", room.id);
+        }
+    }
+
+    #[test]
+    fn spawns_are_rolled_from_the_run_seed() {
+        let run = run(42, 2);
+        let room = &run.floor.rooms[1];
+        let spawns: Vec<&Spawn> = room.spawns.iter().collect();
+        let roll = || {
+            let mut gen = spawn_generator(&run, &room.id).unwrap();
+            world::roll_spawns(&spawns, room.max_enemies, &mut gen)
+        };
+        let rolled = roll();
+        assert_eq!(rolled, roll());
+        assert!((1..=room.max_enemies as usize).contains(&rolled.len()));
+        for (npc, level) in rolled {
+            assert!(crypt().enemies.contains(&npc));
+            assert!((3..=5).contains(&level));
+        }
+        assert!(spawn_generator(&run, "town_square").is_none());
+    }
+
+    #[test]
+    fn runs_belong_to_the_character_on_them() {
+        let mut state = fixtures::village();
+        state.dungeons.push(crypt());
+        state.world.zones[0].rooms[1].dungeon = Some("crypt".to_string());
+        world::walk(&mut state, "north").unwrap();
+        descend(&mut state, Some("42")).unwrap();
+        assert_eq!(state.location().unwrap().room, "dungeon/1/0");
+
+        state.set_player(1).unwrap();
+        assert!(state.dungeon().is_none());
+        assert_eq!(state.location().unwrap().room, "square");
+        assert!(world::find_room(&state, "dungeon/1/0").is_none());
+
+        state.set_player(0).unwrap();
+        assert_eq!(state.dungeon().map(|run| run.seed), Some(42));
+        assert_eq!(state.location().unwrap().room, "dungeon/1/0");
+        leave(&mut state).unwrap();
+        assert_eq!(state.location().unwrap().room, "woods");
+    }
+}
@@ -0,0 +1,602 @@
+// src/game/effect.rs
+use crate::game::{
+    combat, cooldown, debug, entity::Entity, log::say, rng, skills::Skill, stats::Stats,
+};
+use serde::{Deserialize, Serialize};
+
+/// One thing a skill does, from the `effects` list of its definition in
+/// `skills.json`. A skill's effects run in order, see `execute`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Effect {
+    /// Hurt the foe `hits` times, rolling to hit for each; the user heals
+    /// `drain` percent of the damage dealt.
+    Damage {
+        #[serde(default)]
+        formula: Formula,
+        #[serde(default = "one")]
+        hits: u32,
+        #[serde(default)]
+        drain: u32,
+    },
+    /// Give the target back HP, up to its full health.
+    Heal {
+        #[serde(default)]
+        amount: Formula,
+        #[serde(default = "Target::user")]
+        target: Target,
+    },
+    /// Put `status` on the target, `chance` percent of the time.
+    ApplyStatus {
+        status: Status,
+        #[serde(default = "Target::foe")]
+        target: Target,
+        #[serde(default = "always")]
+        chance: u32,
+    },
+    /// Take statuses off the target: the one named `status`, or all of them.
+    Dispel {
+        #[serde(default)]
+        status: Option<String>,
+        #[serde(default = "Target::user")]
+        target: Target,
+    },
+    /// Push the foe back in the turn order: it loses its next `turns` turns,
+    /// and anything it is warming up takes as much longer.
+    Knockback {
+        #[serde(default = "one")]
+        turns: u32,
+    },
+    /// Call `count` of the NPC `npc` into the fight, on the user's side.
+    Summon {
+        npc: u32,
+        #[serde(default = "one")]
+        count: u32,
+    },
+}
+
+fn one() -> u32 {
+    1
+}
+
+fn always() -> u32 {
+    100
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    User,
+    Foe,
+}
+
+impl Target {
+    fn user() -> Self {
+        Target::User
+    }
+
+    fn foe() -> Self {
+        Target::Foe
+    }
+}
+
+/// How much a damage or heal effect comes to: `base`, plus `power` times a
+/// roll of 0 up to the skill's power, plus each of the user's stats and
+/// the target's defense times its own factor. Left out, it is what skills
+/// have always dealt: the roll plus the user's attack.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Formula {
+    pub base: f32,
+    pub power: f32,
+    pub attack: f32,
+    pub defense: f32,
+    pub agility: f32,
+    pub level: f32,
+    /// Negative to let the target's armour soak some of it up.
+    pub target_defense: f32,
+}
+
+impl Default for Formula {
+    fn default() -> Self {
+        Self {
+            base: 0.0,
+            power: 1.0,
+            attack: 1.0,
+            defense: 0.0,
+            agility: 0.0,
+            level: 0.0,
+            target_defense: 0.0,
+        }
+    }
+}
+
+impl Formula {
+    /// Whether the formula rolls the skill's power at all.
+    pub fn rolls(&self) -> bool {
+        self.power != 0.0
+    }
+
+    fn amount(&self, roll: u32, user: &Entity, target: &Entity) -> u32 {
+        let amount = self.base
+            + self.power * roll as f32
+            + self.attack * user.stats.attack as f32
+            + self.defense * user.stats.defense as f32
+            + self.agility * user.stats.agility as f32
+            + self.level * user.level as f32
+            + self.target_defense * target.stats.defense as f32;
+        amount.round().max(0.0) as u32
+    }
+
+    fn roll(&self, skill: &Skill, user: &Entity, target: &Entity) -> u32 {
+        // A zero-power skill rolls nothing
+        let roll = if skill.power > 0 {
+            rng::gen_range(0..skill.power)
+        } else {
+            0
+        };
+        let amount = self.amount(roll, user, target);
+        if debug::verbose_combat() {
+            say!(
+                "  [{}: {} rolled {} (0..{}), coming to {}]",
+                user.name,
+                skill.name,
+                roll,
+                skill.power,
+                amount
+            );
+        }
+        amount
+    }
+
+    /// Lowest and highest amount the formula can come to for `skill`.
+    fn range(&self, skill: &Skill, user: &Entity, target: &Entity) -> (u32, u32) {
+        let low = self.amount(0, user, target);
+        let high = self.amount(skill.power.saturating_sub(1), user, target);
+        (low.min(high), low.max(high))
+    }
+}
+
+/// A lasting condition from `apply_status`, named so it reads after "is",
+/// like "burning": stat changes while it lasts, HP lost or regained at the
+/// start of each of the holder's turns, and maybe no turns at all.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub name: String,
+    /// Turns of the holder's it lasts.
+    pub turns: u32,
+    #[serde(default = "Stats::new")]
+    pub modifier: Stats,
+    /// Negative for poison and burns, positive for regeneration.
+    #[serde(default)]
+    pub per_turn: i32,
+    /// Whether the holder loses its turns while it lasts.
+    #[serde(default)]
+    pub stun: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveStatus {
+    pub status: Status,
+    pub turns_left: u32,
+}
+
+/// Creatures a skill calls in, for the caller to bring into the fight, see
+/// `combat::place`.
+pub struct Summon {
+    pub npc: u32,
+    pub count: u32,
+    /// Level of the summoner, which the creatures come at.
+    pub level: u32,
+}
+
+/// The entity an effect aimed at `target` lands on.
+fn pick<'a>(target: Target, user: &'a mut Entity, foe: &'a mut Entity) -> &'a mut Entity {
+    match target {
+        Target::User => user,
+        Target::Foe => foe,
+    }
+}
+
+/// Give `entity` up to `amount` HP back, no further than its full health
+/// when that is known. Returns the HP it got.
+fn heal(entity: &mut Entity, amount: u32) -> u32 {
+    let full_hp = entity.combat.max_hp;
+    let hp = entity.stats.hp;
+    entity.stats.hp = if full_hp > 0 {
+        (hp + amount as i32).min(full_hp.max(hp))
+    } else {
+        hp + amount as i32
+    };
+    (entity.stats.hp - hp) as u32
+}
+
+/// Put `status` on `entity`, or start it over if it is there already.
+fn apply_status(entity: &mut Entity, status: &Status) {
+    let statuses = &mut entity.combat.statuses;
+    match statuses
+        .iter_mut()
+        .find(|active| active.status.name == status.name)
+    {
+        Some(active) => active.turns_left = active.turns_left.max(status.turns),
+        None => {
+            entity.stats.apply_modifier(status.modifier);
+            statuses.push(ActiveStatus {
+                status: status.clone(),
+                turns_left: status.turns,
+            });
+        }
+    }
+}
+
+/// Take the statuses `entity` has that `matches` picks off it, undoing what
+/// they did to its stats. Returns their names.
+fn remove_statuses(entity: &mut Entity, matches: impl Fn(&ActiveStatus) -> bool) -> Vec<String> {
+    let mut removed = Vec::new();
+    let mut kept = Vec::new();
+    for active in std::mem::take(&mut entity.combat.statuses) {
+        if matches(&active) {
+            entity.stats.apply_modifier(negate(active.status.modifier));
+            removed.push(active.status.name);
+        } else {
+            kept.push(active);
+        }
+    }
+    entity.combat.statuses = kept;
+    removed
+}
+
+fn negate(stats: Stats) -> Stats {
+    Stats {
+        hp: -stats.hp,
+        attack: -stats.attack,
+        defense: -stats.defense,
+        agility: -stats.agility,
+    }
+}
+
+/// Take every status off `entity` without a word, for the end of an
+/// encounter.
+pub fn clear(entity: &mut Entity) {
+    remove_statuses(entity, |_| true);
+}
+
+/// Run the effects of `skill`, used by `user` against `foe`, in order.
+/// Damage rolls to hit; statuses and knockbacks aimed at the foe only land
+/// if the damage before them did. Returns who the skill summons.
+pub fn execute(skill: &Skill, user: &mut Entity, foe: &mut Entity) -> Vec<Summon> {
+    // The skill is only named when the user has others to choose from
+    let with = if user.skills.len() > 1 {
+        format!(" with {}", skill.name)
+    } else {
+        String::new()
+    };
+    let effects = skill.effects();
+    // Damage says what it was done with; anything else is announced first
+    if !matches!(effects.first(), Some(Effect::Damage { .. })) {
+        say!("{} uses {}.", user.name, skill.name);
+    }
+    let mut missed = false;
+    let mut summons = Vec::new();
+    for effect in effects {
+        match effect {
+            Effect::Damage {
+                formula,
+                hits,
+                drain,
+            } => {
+                let mut landed = 0;
+                for _ in 0..hits {
+                    if foe.stats.hp <= 0 {
+                        break;
+                    }
+                    if !lands(user, foe) {
+                        say!("{} attacks {}{} but misses!", user.name, foe.name, with);
+                        continue;
+                    }
+                    landed += 1;
+                    let damage = formula.roll(skill, user, foe);
+                    foe.stats.hp -= damage as i32;
+                    say!(
+                        "{} attacks {}{} for {} damage!",
+                        user.name,
+                        foe.name,
+                        with,
+                        damage
+                    );
+                    let drained = heal(user, damage * drain / 100);
+                    if drained > 0 {
+                        say!("{} drains {} HP.", user.name, drained);
+                    }
+                }
+                missed = landed == 0;
+            }
+            Effect::Heal { amount, target } => {
+                let amount = match target {
+                    Target::User => amount.roll(skill, user, user),
+                    Target::Foe => amount.roll(skill, user, foe),
+                };
+                let target = pick(target, user, foe);
+                if target.stats.hp <= 0 {
+                    continue;
+                }
+                match heal(target, amount) {
+                    0 => say!("{} is already at full health.", target.name),
+                    healed => say!("{} recovers {} HP.", target.name, healed),
+                }
+            }
+            Effect::ApplyStatus {
+                status,
+                target,
+                chance,
+            } => {
+                if target == Target::Foe && missed {
+                    continue;
+                }
+                let target = pick(target, user, foe);
+                if target.stats.hp <= 0 {
+                    continue;
+                }
+                if rng::gen_range(0..100) >= chance {
+                    say!("{} resists being {}.", target.name, status.name);
+                    continue;
+                }
+                apply_status(target, &status);
+                say!(
+                    "{} is {} for {}.",
+                    target.name,
+                    status.name,
+                    cooldown::turns(status.turns)
+                );
+            }
+            Effect::Dispel { status, target } => {
+                let target = pick(target, user, foe);
+                let removed = remove_statuses(target, |active| {
+                    status
+                        .as_ref()
+                        .is_none_or(|name| *name == active.status.name)
+                });
+                for name in removed {
+                    say!("{} is no longer {}.", target.name, name);
+                }
+            }
+            Effect::Knockback { turns } => {
+                if missed || foe.stats.hp <= 0 {
+                    continue;
+                }
+                foe.combat.knocked_back += turns;
+                if let Some(pending) = &mut foe.combat.pending {
+                    pending.resolve_on += turns;
+                }
+                say!(
+                    "{} knocks {} back by {}!",
+                    user.name,
+                    foe.name,
+                    cooldown::turns(turns)
+                );
+            }
+            Effect::Summon { npc, count } => summons.push(Summon {
+                npc,
+                count,
+                level: user.level,
+            }),
+        }
+    }
+    summons
+}
+
+/// Roll whether `user` hits `foe`.
+fn lands(user: &Entity, foe: &Entity) -> bool {
+    let chance = combat::hit_chance(user, foe);
+    let roll = rng::gen_range(0..100);
+    if debug::verbose_combat() {
+        say!(
+            "  [{}: to hit rolled {} (0..100) against {}%]",
+            user.name,
+            roll,
+            chance
+        );
+    }
+    roll < chance
+}
+
+/// Start `entity`'s turn as far as its statuses and knockbacks go: HP
+/// changes land and statuses run down. Returns whether it gets to act.
+pub fn start_turn(entity: &mut Entity) -> bool {
+    let mut acts = true;
+    if entity.combat.knocked_back > 0 {
+        entity.combat.knocked_back -= 1;
+        say!("{} is still reeling and loses the turn.", entity.name);
+        acts = false;
+    }
+    // Statuses last through the holder's last turn of them and go after
+    for name in remove_statuses(entity, |active| active.turns_left == 0) {
+        say!("{} is no longer {}.", entity.name, name);
+    }
+    for i in 0..entity.combat.statuses.len() {
+        let status = entity.combat.statuses[i].status.clone();
+        if status.per_turn < 0 {
+            entity.stats.hp += status.per_turn;
+            say!(
+                "{} takes {} damage while {}.",
+                entity.name,
+                -status.per_turn,
+                status.name
+            );
+        } else if status.per_turn > 0 {
+            let healed = heal(entity, status.per_turn as u32);
+            if healed > 0 {
+                say!(
+                    "{} recovers {} HP while {}.",
+                    entity.name,
+                    healed,
+                    status.name
+                );
+            }
+        }
+        if status.stun && acts {
+            say!("{} is {} and loses the turn.", entity.name, status.name);
+            acts = false;
+        }
+        let active = &mut entity.combat.statuses[i];
+        active.turns_left = active.turns_left.saturating_sub(1);
+    }
+    acts && entity.stats.hp > 0
+}
+
+/// Lowest and highest damage `skill` can deal in one use by `user` against
+/// `foe`, counting every hit; `None` if it deals none.
+pub fn damage_range(skill: &Skill, user: &Entity, foe: &Entity) -> Option<(u32, u32)> {
+    let mut range = None;
+    for effect in skill.effects() {
+        if let Effect::Damage { formula, hits, .. } = effect {
+            let (low, high) = formula.range(skill, user, foe);
+            let (total_low, total_high) = range.unwrap_or((0, 0));
+            range = Some((total_low + low * hits, total_high + high * hits));
+        }
+    }
+    range
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::{fighter, skill};
+
+    fn skill_with(power: u32, effects: Vec<Effect>) -> Skill {
+        let mut skill = skill(10, "Test", power);
+        skill.effects = effects;
+        skill
+    }
+
+    fn burning() -> Status {
+        Status {
+            name: "burning".to_string(),
+            turns: 2,
+            modifier: Stats {
+                hp: 0,
+                attack: -2,
+                defense: 0,
+                agility: 0,
+            },
+            per_turn: -3,
+            stun: false,
+        }
+    }
+
+    #[test]
+    fn formulas_add_up_every_factor() {
+        let mut user = fighter(1, "Mira", 30, 10, Vec::new());
+        user.stats.defense = 4;
+        user.level = 3;
+        let mut target = fighter(2, "Goblin", 30, 2, Vec::new());
+        target.stats.defense = 3;
+        let formula = Formula {
+            base: 2.0,
+            power: 1.0,
+            attack: 0.5,
+            defense: 0.25,
+            agility: 0.0,
+            level: 2.0,
+            target_defense: -1.0,
+        };
+        // 2 + 4 + 5 + 1 + 6 - 3
+        assert_eq!(formula.amount(4, &user, &target), 15);
+        assert_eq!(
+            formula.range(&skill(10, "Hit", 5), &user, &target),
+            (11, 15)
+        );
+        target.stats.defense = 100;
+        assert_eq!(formula.amount(0, &user, &target), 0);
+        assert!(!Formula {
+            power: 0.0,
+            ..Formula::default()
+        }
+        .rolls());
+    }
+
+    #[test]
+    fn the_default_formula_is_the_roll_plus_attack() {
+        let user = fighter(1, "Mira", 30, 7, Vec::new());
+        let target = fighter(2, "Goblin", 30, 2, Vec::new());
+        let hit = skill_with(6, Vec::new());
+        assert_eq!(damage_range(&hit, &user, &target), Some((7, 12)));
+        let heal = skill_with(
+            6,
+            vec![Effect::Heal {
+                amount: Formula::default(),
+                target: Target::User,
+            }],
+        );
+        assert_eq!(damage_range(&heal, &user, &target), None);
+    }
+
+    #[test]
+    fn drain_heals_the_user_up_to_full_health() {
+        rng::seed(1);
+        let drain = skill_with(
+            1,
+            vec![Effect::Damage {
+                formula: Formula {
+                    base: 10.0,
+                    power: 0.0,
+                    attack: 0.0,
+                    ..Formula::default()
+                },
+                hits: 1,
+                drain: 50,
+            }],
+        );
+        let mut user = fighter(1, "Mira", 20, 0, Vec::new());
+        user.combat.max_hp = 22;
+        user.stats.agility = 100;
+        let mut foe = fighter(2, "Goblin", 30, 0, Vec::new());
+        execute(&drain, &mut user, &mut foe);
+        assert_eq!((user.stats.hp, foe.stats.hp), (22, 20));
+        user.stats.hp = 10;
+        execute(&drain, &mut user, &mut foe);
+        assert_eq!((user.stats.hp, foe.stats.hp), (15, 10));
+    }
+
+    #[test]
+    fn statuses_last_their_turns_and_then_wear_off() {
+        let mut goblin = fighter(2, "Goblin", 30, 5, Vec::new());
+        apply_status(&mut goblin, &burning());
+        assert_eq!(goblin.stats.attack, 3);
+        for hp in [27, 24] {
+            assert!(start_turn(&mut goblin));
+            assert_eq!((goblin.stats.hp, goblin.stats.attack), (hp, 3));
+        }
+        assert!(start_turn(&mut goblin));
+        assert_eq!((goblin.stats.hp, goblin.stats.attack), (24, 5));
+        assert!(goblin.combat.statuses.is_empty());
+    }
+
+    #[test]
+    fn statuses_are_not_stacked_and_clear_undoes_them() {
+        let mut goblin = fighter(2, "Goblin", 30, 5, Vec::new());
+        apply_status(&mut goblin, &burning());
+        start_turn(&mut goblin);
+        apply_status(&mut goblin, &burning());
+        assert_eq!(goblin.stats.attack, 3);
+        assert_eq!(goblin.combat.statuses[0].turns_left, 2);
+        clear(&mut goblin);
+        assert_eq!(goblin.stats.attack, 5);
+        assert!(goblin.combat.statuses.is_empty());
+    }
+
+    #[test]
+    fn stuns_take_the_turn() {
+        let mut goblin = fighter(2, "Goblin", 30, 5, Vec::new());
+        let stun = Status {
+            name: "stunned".to_string(),
+            turns: 1,
+            modifier: Stats::new(),
+            per_turn: 0,
+            stun: true,
+        };
+        apply_status(&mut goblin, &stun);
+        assert!(!start_turn(&mut goblin));
+        assert!(start_turn(&mut goblin));
+    }
+}
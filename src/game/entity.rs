@@ -1,11 +1,18 @@
 // src/game/entity.rs
 use crate::game::{
+    cooldown::{self, CombatState},
+    dialogue::Conversation,
+    dungeon::DungeonRun,
+    error::{GameError, GameResult},
     item::{Item, ItemType},
+    log::say,
+    quest::QuestProgress,
     skills::Skill,
     stats::Stats,
+    world::Location,
 };
-use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entity {
@@ -15,6 +22,61 @@ pub struct Entity {
     pub inventory: Vec<Item>,
     pub equipment: Vec<Item>,
     pub skills: Vec<Skill>,
+    #[serde(default)]
+    pub gold: u32,
+    #[serde(default)]
+    pub experience: u32,
+    #[serde(default = "first_level")]
+    pub level: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quests: Vec<QuestProgress>,
+    /// Faction id of an NPC; characters have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub faction: Option<String>,
+    /// A character's reputation with each faction it has dealt with.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub reputation: BTreeMap<String, i32>,
+    /// Class and race ids of a character; older characters have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub class: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub race: Option<u32>,
+    /// A character's stats at full health without equipment, once they were
+    /// bought or trained rather than worked out from class and level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<Stats>,
+    /// Attribute points earned from levelling and not spent yet.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub attribute_points: u32,
+    /// Skill points earned from levelling and not spent in a skill tree yet.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub skill_points: u32,
+    /// Cooldowns, charges and warm-ups in the current encounter.
+    #[serde(default, skip_serializing_if = "CombatState::is_idle")]
+    pub combat: CombatState,
+    /// Where a character is in the world, and what it has picked up there.
+    #[serde(default, skip_serializing_if = "Location::is_nowhere")]
+    pub location: Location,
+    /// The dungeon a character is exploring, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dungeon: Option<DungeonRun>,
+    /// Who a character is talking to, if anyone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversation: Option<Conversation>,
+}
+
+fn is_zero(value: &u32) -> bool {
+    *value == 0
+}
+
+fn first_level() -> u32 {
+    1
+}
+
+/// HP of an entity with `base` HP at level 1 once it reaches `level`: a
+/// tenth more for every level above the first.
+pub fn level_hp(base: i32, level: u32) -> i32 {
+    base + base * level.saturating_sub(1) as i32 / 10
 }
 
 impl Entity {
@@ -27,15 +89,34 @@ impl Entity {
             inventory: Vec::new(),
             equipment: Vec::new(),
             skills: Vec::new(),
+            gold: 0,
+            experience: 0,
+            level: 1,
+            quests: Vec::new(),
+            faction: None,
+            reputation: BTreeMap::new(),
+            class: None,
+            race: None,
+            base: None,
+            attribute_points: 0,
+            skill_points: 0,
+            combat: CombatState::default(),
+            location: Location::default(),
+            dungeon: None,
+            conversation: None,
         }
     }
 
-    pub fn damage_roll(&self, skill: &Skill) -> u32 {
-        let mut rng = rand::thread_rng();
-        let dmg_roll: u32 = rng.gen_range(0..skill.power);
-        let atck: u32 = self.stats.attack as u32;
-        let damage = dmg_roll + atck;
-        damage
+    /// Raise a level 1 entity to `level`: more HP, and one more attack,
+    /// defense and agility per level above the first.
+    pub fn scale_to_level(&mut self, level: u32) {
+        let gain = level.saturating_sub(self.level) as i32;
+        let equipment_hp: i32 = self.equipment.iter().map(|i| i.stat_modifier.hp).sum();
+        self.stats.hp = level_hp(self.stats.hp - equipment_hp, level) + equipment_hp;
+        self.stats.attack += gain;
+        self.stats.defense += gain;
+        self.stats.agility += gain;
+        self.level = level.max(self.level);
     }
 
     // Apply item stat modifiers to an entity
@@ -65,61 +146,96 @@ impl Entity {
         }
     }
 
-    pub fn equip_item(&mut self, item: Item) {
-        self.un_apply_equipment();
-        if self.is_item_in_inventory(&item) {
-            if item.item_type == ItemType::Consumable {
-                return;
-            } else if self.is_item_equipped(&item) {
-                return;
-            } else if self.is_equipment_slot_taken(&item) {
-                return;
-            } else {
-                let eq_item = item.clone();
-                self.equipment.push(eq_item);
-                self.remove_item_from_inventory(&item);
-            }
-        } else {
-            println!("Item not in inventory.");
+    pub fn equip_item(&mut self, item: Item) -> GameResult<()> {
+        if !self.is_item_in_inventory(&item) {
+            return Err(GameError::NotFound(format!(
+                "{} is not in the inventory",
+                item.name
+            )));
         }
+        if item.item_type == ItemType::Consumable {
+            return Err(GameError::InvalidInput(format!(
+                "{} cannot be equipped",
+                item.name
+            )));
+        }
+        if self.is_item_equipped(&item) || self.is_equipment_slot_taken(&item) {
+            return Err(GameError::InvalidState(format!(
+                "{:?} slot is already taken",
+                item.item_type
+            )));
+        }
+        self.un_apply_equipment();
+        let eq_item = item.clone();
+        self.equipment.push(eq_item);
+        self.remove_item_from_inventory(&item);
         self.apply_equipment();
+        Ok(())
     }
 
-    pub fn use_item(&mut self, item: Item) {
-        if self.is_item_in_inventory(&item) {
-            if item.item_type == ItemType::Consumable {
-                self.stats.apply_modifier(item.stat_modifier);
-                self.remove_item_from_inventory(&item);
-                println!("Used item: {}", item.name);
-            } else {
-                println!("Item is not a consumable.");
-            }
-        } else {
-            println!("Item not in inventory.");
+    pub fn use_item(&mut self, item: Item) -> GameResult<()> {
+        if !self.is_item_in_inventory(&item) {
+            return Err(GameError::NotFound(format!(
+                "{} is not in the inventory",
+                item.name
+            )));
         }
+        if item.item_type != ItemType::Consumable {
+            return Err(GameError::InvalidInput(format!(
+                "{} is not a consumable",
+                item.name
+            )));
+        }
+        self.stats.apply_modifier(item.stat_modifier);
+        self.remove_item_from_inventory(&item);
+        say!("Used item: {}", item.name);
+        Ok(())
     }
 
     fn remove_item_from_inventory(&mut self, item: &Item) {
         self.inventory.retain(|x| x != item);
     }
 
-    pub fn unequip_item(&mut self, item: Item) {
-        if self.equipment.contains(&item) {
-            self.equipment.retain(|x| x != &item);
-            self.stats.hp -= item.stat_modifier.hp;
-            self.stats.attack -= item.stat_modifier.attack;
-            self.stats.defense -= item.stat_modifier.defense;
-            self.stats.agility -= item.stat_modifier.agility;
-            println!("Unequipped item: {}", item.name);
-            self.add_item_to_inventory(item);
+    pub fn unequip_item(&mut self, item: Item) -> GameResult<()> {
+        if !self.equipment.contains(&item) {
+            return Err(GameError::NotFound(format!(
+                "{} is not equipped",
+                item.name
+            )));
         }
+        self.equipment.retain(|x| x != &item);
+        self.stats.hp -= item.stat_modifier.hp;
+        self.stats.attack -= item.stat_modifier.attack;
+        self.stats.defense -= item.stat_modifier.defense;
+        self.stats.agility -= item.stat_modifier.agility;
+        say!("Unequipped item: {}", item.name);
+        self.add_item_to_inventory(item);
+        Ok(())
     }
 
     // Get entity string for displaying in the UI.
     pub fn get_entity_string(&self) -> String {
+        let mut points = String::new();
+        if self.attribute_points > 0 {
+            points.push_str(&format!("  Attribute points: {}", self.attribute_points));
+        }
+        if self.skill_points > 0 {
+            points.push_str(&format!("  Skill points: {}", self.skill_points));
+        }
+        for active in &self.combat.statuses {
+            let left = match active.turns_left {
+                0 => "wearing off".to_string(),
+                left => format!("{} left", cooldown::turns(left)),
+            };
+            points.push_str(&format!("  {} ({})", active.status.name, left));
+        }
         format!(
-            "Name: {}\n\tStats:\n{}\n\tInventory:\n{}\tEquipment:\n{}",
+            "Name: {}\n\tLevel: {}  Gold: {}  XP: {}{}\n\tStats:\n{}\n\tInventory:\n{}\tEquipment:\n{}",
             self.name,
+            self.level,
+            self.gold,
+            self.experience,
+            points,
             self.stats.get_stats_string(),
             self.get_inventory_string(),
             self.get_equipment_string()
@@ -128,57 +244,67 @@ impl Entity {
 
     pub fn get_inventory_string(&self) -> String {
         let mut inventory_string = String::new();
-        let mut i = 1;
-        for item in &self.inventory {
-            inventory_string.push_str(&format!("\t\t{}. {}\n", i, item.name));
-            i += 1;
+        for (i, item) in self.inventory.iter().enumerate() {
+            inventory_string.push_str(&format!("\t\t{}. {}\n", i + 1, item.name));
         }
         inventory_string
     }
 
     pub fn get_equipment_string(&self) -> String {
         let mut equipment_string = String::new();
-        let mut i = 1;
-        for item in &self.equipment {
-            equipment_string.push_str(&format!("\t\t{}. {}\n", i, item.name));
-            i += 1;
+        for (i, item) in self.equipment.iter().enumerate() {
+            equipment_string.push_str(&format!("\t\t{}. {}\n", i + 1, item.name));
         }
         equipment_string
     }
 
-    pub fn get_skills_string(&self) -> String {
+    pub fn _get_skills_string(&self) -> String {
         let mut skills_string = String::new();
-        let mut i = 0;
-        for skill in &self.skills {
-            i += 1;
-            skills_string.push_str(&format!("ID: {}, Name: {}", i, skill.name));
+        for (i, skill) in self.skills.iter().enumerate() {
+            skills_string.push_str(&format!("ID: {}, Name: {}", i + 1, skill.name));
         }
         skills_string
     }
 
-    pub fn get_skill(&self, index: usize) -> &Skill {
-        let mut i = 0;
-        for skill in &self.skills {
-            if i == index {
-                return skill;
-            }
-            i += 1;
-        }
-        panic!("Skill not found.");
+    pub fn get_skill(&self, index: usize) -> GameResult<&Skill> {
+        self.skills.get(index).ok_or_else(|| {
+            GameError::NotFound(format!("{} has no skill number {}", self.name, index + 1))
+        })
     }
 
-    pub fn get_equipment(&self, item_id: usize) -> &Item {
-        if item_id != 0 && item_id <= self.equipment.len() {
-            return &self.equipment[item_id - 1];
-        }
-        panic!("Item not found in equipment.");
+    pub fn get_equipment(&self, index: usize) -> GameResult<&Item> {
+        self.equipment
+            .get(index)
+            .ok_or_else(|| GameError::NotFound(format!("no equipped item number {}", index + 1)))
     }
 
-    pub fn get_item(&self, item_id: usize) -> &Item {
-        if item_id != 0 && item_id <= self.inventory.len() {
-            return &self.inventory[item_id - 1];
+    pub fn get_item(&self, index: usize) -> GameResult<&Item> {
+        self.inventory
+            .get(index)
+            .ok_or_else(|| GameError::NotFound(format!("no inventory item number {}", index + 1)))
+    }
+
+    /// Replace this entity's skills, inventory and equipment with the current
+    /// definitions of the same ids, keeping stats in line with the new
+    /// equipment modifiers. Returns the ids that could not be found; those
+    /// keep their old definition.
+    pub fn refresh_definitions(&mut self, skills: &[Skill], items: &[Item]) -> Vec<u32> {
+        let mut missing = Vec::new();
+        self.un_apply_equipment();
+        for skill in &mut self.skills {
+            match skills.iter().find(|s| s.id == skill.id) {
+                Some(new_skill) => *skill = new_skill.clone().at_rank(skill.rank),
+                None => missing.push(skill.id),
+            }
         }
-        panic!("Item not found in inventory.");
+        for item in self.inventory.iter_mut().chain(self.equipment.iter_mut()) {
+            match items.iter().find(|i| i.id == item.id) {
+                Some(new_item) => *item = new_item.clone(),
+                None => missing.push(item.id),
+            }
+        }
+        self.apply_equipment();
+        missing
     }
 
     pub fn add_item_to_inventory(&mut self, item: Item) {
@@ -190,7 +316,7 @@ impl Entity {
     }
 
     fn is_item_equipped(&self, item: &Item) -> bool {
-        self.equipment.contains(&item)
+        self.equipment.contains(item)
     }
 
     fn is_equipment_slot_taken(&self, item: &Item) -> bool {
@@ -0,0 +1,41 @@
+// src/game/error.rs
+use std::fmt;
+use std::io;
+
+/// Everything that can go wrong in the game API.
+#[derive(Debug)]
+pub enum GameError {
+    /// The player typed something that can't be used, e.g. a non-number
+    /// where an index was expected.
+    InvalidInput(String),
+    /// A referenced skill, item, character or enemy doesn't exist.
+    NotFound(String),
+    /// The request is valid but can't be carried out right now, e.g.
+    /// attacking with no character loaded.
+    InvalidState(String),
+    /// A content, config or save file is malformed.
+    Content(String),
+    Io(io::Error),
+}
+
+pub type GameResult<T> = Result<T, GameError>;
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            GameError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            GameError::InvalidState(msg) => write!(f, "{}", msg),
+            GameError::Content(msg) => write!(f, "Content error: {}", msg),
+            GameError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<io::Error> for GameError {
+    fn from(e: io::Error) -> Self {
+        GameError::Io(e)
+    }
+}
@@ -0,0 +1,279 @@
+// src/game/faction.rs
+use crate::game::entity::Entity;
+use crate::game::error::GameResult;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Reputation always stays within `-MAX_REPUTATION..=MAX_REPUTATION`.
+pub const MAX_REPUTATION: i32 = 100;
+/// Members of a faction you are below this with attack on sight.
+pub const HOSTILE_BELOW: i32 = -20;
+/// From this reputation on a faction counts you as a friend.
+pub const FRIENDLY_FROM: i32 = 20;
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct FactionsWrapper {
+    pub(crate) factions: Vec<Faction>,
+}
+
+/// A faction from `factions.json`. Every NPC belongs to one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Faction {
+    pub id: String,
+    pub name: String,
+    /// Reputation new characters start with.
+    #[serde(default)]
+    pub reputation: i32,
+    /// How this faction feels about others, from -100 (sworn enemies) to
+    /// 100 (allies). Reputation gained or lost with another faction is
+    /// passed on here in that proportion.
+    #[serde(default)]
+    pub relations: BTreeMap<String, i32>,
+    /// Reputation lost for killing a member.
+    #[serde(default = "default_change")]
+    pub kill_penalty: i32,
+    /// Reputation gained for finishing a quest a member gave you.
+    #[serde(default = "default_change")]
+    pub help_bonus: i32,
+}
+
+fn default_change() -> i32 {
+    10
+}
+
+/// How a faction treats a character.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Standing {
+    Hostile,
+    Neutral,
+    Friendly,
+}
+
+impl Standing {
+    pub fn of(reputation: i32) -> Self {
+        if reputation < HOSTILE_BELOW {
+            Standing::Hostile
+        } else if reputation >= FRIENDLY_FROM {
+            Standing::Friendly
+        } else {
+            Standing::Neutral
+        }
+    }
+}
+
+impl fmt::Display for Standing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Standing::Hostile => write!(f, "hostile"),
+            Standing::Neutral => write!(f, "neutral"),
+            Standing::Friendly => write!(f, "friendly"),
+        }
+    }
+}
+
+fn faction<'a>(state: &'a GameState, id: &str) -> Option<&'a Faction> {
+    state.factions.iter().find(|faction| faction.id == id)
+}
+
+/// `player`'s reputation with faction `id`; with no player, what a new
+/// character starts with.
+pub fn reputation(state: &GameState, player: Option<&Entity>, id: &str) -> i32 {
+    player
+        .and_then(|player| player.reputation.get(id).copied())
+        .or_else(|| faction(state, id).map(|faction| faction.reputation))
+        .unwrap_or(0)
+}
+
+/// How `npc`'s faction treats the current character.
+pub fn standing(state: &GameState, npc: &Entity) -> Standing {
+    match &npc.faction {
+        Some(id) => Standing::of(reputation(state, state.get_player().ok(), id)),
+        // Only characters have no faction, and they don't get along
+        None => Standing::Hostile,
+    }
+}
+
+pub fn is_hostile(state: &GameState, npc: &Entity) -> bool {
+    standing(state, npc) == Standing::Hostile
+}
+
+/// Change the current character's reputation with faction `id` by `delta`,
+/// and with every other faction in proportion to how it feels about `id`.
+fn change(state: &mut GameState, id: &str, delta: i32) -> GameResult<()> {
+    let mut changes = vec![(id.to_string(), delta)];
+    for other in state.factions.iter().filter(|other| other.id != id) {
+        if let Some(relation) = other.relations.get(id) {
+            changes.push((other.id.clone(), delta * relation / MAX_REPUTATION));
+        }
+    }
+    let starting: Vec<i32> = changes
+        .iter()
+        .map(|(id, _)| reputation(state, None, id))
+        .collect();
+    let names: Vec<String> = changes
+        .iter()
+        .map(|(id, _)| faction(state, id).map_or_else(|| id.clone(), |f| f.name.clone()))
+        .collect();
+    let player = state.get_player_mut()?;
+    for (((id, delta), start), name) in changes.into_iter().zip(starting).zip(names) {
+        if delta == 0 {
+            continue;
+        }
+        let value = player.reputation.entry(id).or_insert(start);
+        let before = *value;
+        *value = (before + delta).clamp(-MAX_REPUTATION, MAX_REPUTATION);
+        if *value != before {
+            say!(
+                "Reputation with {}: {:+} ({})",
+                name,
+                *value - before,
+                Standing::of(*value)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The current character killed `npc`.
+pub fn killed(state: &mut GameState, npc: &Entity) -> GameResult<()> {
+    let Some(id) = &npc.faction else {
+        return Ok(());
+    };
+    let penalty = faction(state, id).map_or(default_change(), |f| f.kill_penalty);
+    change(state, id, -penalty)
+}
+
+/// The current character did a member of faction `id` a good turn.
+pub fn helped(state: &mut GameState, id: &str) -> GameResult<()> {
+    let bonus = faction(state, id).map_or(default_change(), |f| f.help_bonus);
+    change(state, id, bonus)
+}
+
+/// What a member of `npc`'s faction charges the current character for
+/// something worth `price`: friends get a discount, others pay extra.
+pub fn price(state: &GameState, npc: &Entity, price: u32) -> u32 {
+    let reputation = npc
+        .faction
+        .as_ref()
+        .map_or(0, |id| reputation(state, state.get_player().ok(), id));
+    let factor = (2 * MAX_REPUTATION - reputation) as u32;
+    (price * factor).div_ceil(2 * MAX_REPUTATION as u32).max(1)
+}
+
+/// List the current character's standing with every faction.
+pub fn list(state: &GameState) -> GameResult<()> {
+    let player = state.get_player()?;
+    if state.factions.is_empty() {
+        say!("There are no factions.");
+        return Ok(());
+    }
+    let mut text = String::from("Reputation:");
+    for faction in &state.factions {
+        let value = reputation(state, Some(player), &faction.id);
+        text.push_str(&format!(
+            "\n    {}: {} ({})",
+            faction.name,
+            value,
+            Standing::of(value)
+        ));
+    }
+    say!("{}", text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+    use crate::game::log;
+
+    /// The arena with the goblins' faction, which the villagers hate.
+    fn factions() -> GameState {
+        let mut state = fixtures::arena();
+        state.factions = vec![
+            Faction {
+                id: "monsters".to_string(),
+                name: "Monsters".to_string(),
+                reputation: -50,
+                relations: BTreeMap::new(),
+                kill_penalty: 30,
+                help_bonus: 10,
+            },
+            Faction {
+                id: "villagers".to_string(),
+                name: "Villagers".to_string(),
+                reputation: 0,
+                relations: BTreeMap::from([("monsters".to_string(), -50)]),
+                kill_penalty: 10,
+                help_bonus: 10,
+            },
+        ];
+        state
+    }
+
+    fn set_reputation(state: &mut GameState, id: &str, value: i32) {
+        let player = state.get_player_mut().unwrap();
+        player.reputation.insert(id.to_string(), value);
+    }
+
+    #[test]
+    fn standing_changes_at_the_thresholds() {
+        assert_eq!(Standing::of(-MAX_REPUTATION), Standing::Hostile);
+        assert_eq!(Standing::of(HOSTILE_BELOW - 1), Standing::Hostile);
+        assert_eq!(Standing::of(HOSTILE_BELOW), Standing::Neutral);
+        assert_eq!(Standing::of(FRIENDLY_FROM - 1), Standing::Neutral);
+        assert_eq!(Standing::of(FRIENDLY_FROM), Standing::Friendly);
+    }
+
+    #[test]
+    fn hostility_follows_reputation() {
+        let mut state = factions();
+        let goblin = state.npc(100).unwrap();
+        assert!(is_hostile(&state, &goblin));
+        set_reputation(&mut state, "monsters", HOSTILE_BELOW);
+        assert!(!is_hostile(&state, &goblin));
+        state.get_player_mut().unwrap().reputation.clear();
+        assert_eq!(reputation(&state, state.get_player().ok(), "monsters"), -50);
+        assert!(is_hostile(&state, &goblin));
+    }
+
+    #[test]
+    fn kills_cost_reputation_and_please_rivals() {
+        let mut state = factions();
+        set_reputation(&mut state, "monsters", 0);
+        let goblin = state.npc(100).unwrap();
+        let (result, lines) = log::capture(|| killed(&mut state, &goblin));
+        result.unwrap();
+        let player = state.get_player().unwrap();
+        assert_eq!(player.reputation["monsters"], -30);
+        assert_eq!(player.reputation["villagers"], 15);
+        assert_eq!(
+            lines,
+            [
+                "Reputation with Monsters: -30 (hostile)",
+                "Reputation with Villagers: +15 (neutral)"
+            ]
+        );
+
+        set_reputation(&mut state, "monsters", -90);
+        killed(&mut state, &goblin).unwrap();
+        assert_eq!(
+            state.get_player().unwrap().reputation["monsters"],
+            -MAX_REPUTATION
+        );
+    }
+
+    #[test]
+    fn prices_follow_standing() {
+        let mut state = factions();
+        let goblin = state.npc(100).unwrap();
+        for (value, expected) in [(-100, 150), (-21, 111), (0, 100), (20, 90), (100, 50)] {
+            set_reputation(&mut state, "monsters", value);
+            assert_eq!(price(&state, &goblin, 100), expected, "at {}", value);
+        }
+        assert_eq!(price(&state, &goblin, 1), 1);
+    }
+}
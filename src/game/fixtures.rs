@@ -0,0 +1,107 @@
+// src/game/fixtures.rs
+//! Small game states for the unit tests to play in.
+use crate::game::entity::Entity;
+use crate::game::persistence::GameState;
+use crate::game::rng;
+use crate::game::skills::{Skill, SkillClass, SkillTarget, SkillType};
+use crate::game::world::{Room, World, Zone};
+use std::fs;
+use std::path::PathBuf;
+
+/// An active skill hitting one target for `power` plus the user's attack.
+pub fn skill(id: u32, name: &str, power: u32) -> Skill {
+    Skill::_new(
+        id,
+        name,
+        "",
+        SkillType::Active,
+        SkillTarget::SingleTarget,
+        SkillClass::Physical,
+        power,
+        0,
+    )
+}
+
+/// An entity with `hp`, `attack` and `skills`.
+pub fn fighter(id: u32, name: &str, hp: i32, attack: i32, skills: Vec<Skill>) -> Entity {
+    let mut entity = Entity::new(id, name);
+    entity.stats.hp = hp;
+    entity.stats.attack = attack;
+    entity.stats.agility = 5;
+    entity.skills = skills;
+    entity
+}
+
+/// The classic arena without a world: Mira, selected, against a full set
+/// of goblins that are hostile to her. Reseeds the RNG, so every test
+/// rolls the same.
+pub fn arena() -> GameState {
+    rng::seed(1);
+    let mut state = GameState::new();
+    state.skills = vec![skill(1, "Strike", 4), skill(2, "Claw", 3)];
+    let template = fighter(1, "Adventurer", 60, 4, vec![state.skills[0].clone()]);
+    let mut goblin = fighter(100, "Goblin", 30, 2, vec![state.skills[1].clone()]);
+    goblin.faction = Some("monsters".to_string());
+    state.entities = vec![template.clone(), goblin.clone()];
+    state.pc_ents = vec![template.clone()];
+    state.npc_ents = vec![goblin];
+
+    let mut mira = template;
+    mira.name = "Mira".to_string();
+    mira.reputation.insert("monsters".to_string(), -100);
+    state.create_player(mira);
+    state.set_player(0).unwrap();
+    state.populate_enemies().unwrap();
+    state
+}
+
+/// An empty room with `exits`, as direction and room id.
+pub fn room(id: &str, exits: &[(&str, &str)]) -> Room {
+    Room {
+        id: id.to_string(),
+        name: id.to_string(),
+        description: String::new(),
+        exits: exits
+            .iter()
+            .map(|(direction, target)| (direction.to_string(), target.to_string()))
+            .collect(),
+        spawns: Vec::new(),
+        max_enemies: 0,
+        items: Vec::new(),
+        npcs: Vec::new(),
+        dungeon: None,
+    }
+}
+
+/// The arena turned into a safe village, a square with woods to the north,
+/// where Mira, selected, and Bren live.
+pub fn village() -> GameState {
+    let mut state = arena();
+    state.enemies.clear();
+    state.world = World {
+        start: "square".to_string(),
+        zones: vec![Zone {
+            id: "village".to_string(),
+            name: "Village".to_string(),
+            rooms: vec![
+                room("square", &[("north", "woods")]),
+                room("woods", &[("south", "square")]),
+            ],
+        }],
+    };
+    let mut bren = state.players[0].clone();
+    bren.id = 2;
+    bren.name = "Bren".to_string();
+    state.create_player(bren);
+    state.set_player(0).unwrap();
+    state
+}
+
+/// A fresh, empty directory under the system temp dir, unique to `name` and
+/// this test run.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ultimate-end-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,158 @@
+// src/game/format.rs
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::path::Path;
+
+/// File formats content files can be written in, picked by file extension.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Toml,
+    Ron,
+}
+
+pub const FORMATS: [Format; 3] = [Format::Json, Format::Toml, Format::Ron];
+
+impl Format {
+    /// Detect the format from the extension of `path`, if it is a known one.
+    pub fn from_extension(path: &str) -> Option<Format> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        FORMATS
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+
+    /// Same as `from_extension`, but falls back to JSON.
+    pub fn from_path(path: &str) -> Format {
+        Format::from_extension(path).unwrap_or(Format::Json)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Toml => "toml",
+            Format::Ron => "ron",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, data: &str) -> Result<T, String> {
+        match self {
+            Format::Json => serde_json::from_str(data).map_err(|e| e.to_string()),
+            Format::Toml => toml::from_str(data).map_err(|e| e.to_string()),
+            Format::Ron => ron::from_str(data).map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String, String> {
+        match self {
+            Format::Json => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string_pretty(value).map_err(|e| e.to_string()),
+            Format::Ron => ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+                .map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Find the file to load for `path`.
+///
+/// If `path` itself doesn't exist, the same file with one of the other
+/// supported extensions is used instead, so `assets/skills.json` may be
+/// authored as `assets/skills.toml` or `assets/skills.ron`. Returns `path`
+/// unchanged when no variant exists.
+pub fn resolve_variant(path: &str) -> String {
+    if Path::new(path).exists() {
+        return path.to_string();
+    }
+    for format in FORMATS {
+        let candidate = Path::new(path).with_extension(format.extension());
+        if candidate.exists() {
+            return candidate.to_string_lossy().into_owned();
+        }
+    }
+    path.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::content::convert_file;
+    use crate::game::error::GameError;
+    use crate::game::fixtures;
+    use crate::game::persistence::{EntitiesWrapper, ItemsWrapper, SkillsWrapper};
+    use std::fs;
+
+    /// Send `value` through `format` and back, returning it as JSON.
+    fn round_trip<T: Serialize + DeserializeOwned>(value: &T, format: Format) -> serde_json::Value {
+        let text = format.serialize(value).unwrap();
+        let back: T = format.parse(&text).unwrap();
+        serde_json::to_value(&back).unwrap()
+    }
+
+    fn asset<T: DeserializeOwned>(file: &str) -> T {
+        let data = fs::read_to_string(Path::new("assets").join(file)).unwrap();
+        Format::Json.parse(&data).unwrap()
+    }
+
+    #[test]
+    fn assets_survive_toml_and_ron() {
+        let skills: SkillsWrapper = asset("skills.json");
+        let items: ItemsWrapper = asset("items.json");
+        let entities: EntitiesWrapper = asset("entities.json");
+        for format in [Format::Toml, Format::Ron] {
+            assert_eq!(
+                round_trip(&skills, format),
+                serde_json::to_value(&skills).unwrap()
+            );
+            assert_eq!(
+                round_trip(&items, format),
+                serde_json::to_value(&items).unwrap()
+            );
+            assert_eq!(
+                round_trip(&entities, format),
+                serde_json::to_value(&entities).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn formats_follow_the_extension() {
+        assert_eq!(Format::from_extension("a/skills.TOML"), Some(Format::Toml));
+        assert_eq!(Format::from_extension("skills.ron"), Some(Format::Ron));
+        assert_eq!(Format::from_extension("skills.yaml"), None);
+        assert_eq!(Format::from_path("skills"), Format::Json);
+    }
+
+    #[test]
+    fn convert_goes_there_and_back() {
+        let dir = fixtures::temp_dir("convert");
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        for via in ["skills.toml", "skills.ron"] {
+            convert_file("assets/skills.json", &path(via)).unwrap();
+            convert_file(&path(via), &path("skills.json")).unwrap();
+            let converted: SkillsWrapper = Format::Json
+                .parse(&fs::read_to_string(path("skills.json")).unwrap())
+                .unwrap();
+            let original: SkillsWrapper = asset("skills.json");
+            assert_eq!(
+                serde_json::to_value(&converted).unwrap(),
+                serde_json::to_value(&original).unwrap()
+            );
+        }
+        let unknown = convert_file("assets/skills.json", &path("skills.yaml"));
+        let written = Path::new(&path("skills.yaml")).exists();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(unknown, Err(GameError::InvalidInput(_))));
+        assert!(!written);
+    }
+
+    #[test]
+    fn resolve_variant_finds_other_formats() {
+        let dir = fixtures::temp_dir("variant");
+        let json = dir.join("world.json").to_string_lossy().into_owned();
+        assert_eq!(resolve_variant(&json), json);
+        fs::write(dir.join("world.ron"), "()").unwrap();
+        let resolved = resolve_variant(&json);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(resolved, dir.join("world.ron").to_string_lossy());
+    }
+}
@@ -0,0 +1,265 @@
+// src/game/history.rs
+use crate::game::config::{Config, DeathPolicy, Difficulty};
+use crate::game::console::Console;
+use crate::game::death::{resolve_death, DeathOutcome};
+use crate::game::error::{GameError, GameResult};
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::process_command;
+use std::collections::VecDeque;
+
+/// Game commands that only show the state, so never need a snapshot.
+const READ_ONLY_COMMANDS: &[&str] = &[
+    "status",
+    "show_enemies",
+    "show_inventory",
+    "look",
+    "map",
+    "reputation",
+    "skills",
+    "quests",
+    "quest",
+    "help",
+    "seed",
+    "dump",
+    "verbose",
+];
+
+/// Snapshots of the game state before recent turns, for `undo`, and the
+/// rules for what happens when the character dies.
+pub struct History {
+    difficulty: Difficulty,
+    depth: usize,
+    death_policy: DeathPolicy,
+    snapshots: VecDeque<GameState>,
+}
+
+impl History {
+    /// Keeps up to `config.undo_depth` turns on casual difficulty, none
+    /// otherwise.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            difficulty: config.difficulty,
+            depth: if config.difficulty == Difficulty::Casual {
+                config.undo_depth
+            } else {
+                0
+            },
+            death_policy: config.death_policy,
+            snapshots: VecDeque::new(),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.snapshots.is_empty()
+    }
+
+    /// Run a game command, or `undo`, keeping a snapshot if the command
+    /// succeeded and can change the state.
+    ///
+    /// When the character dies the death policy is applied straight away,
+    /// unless the death can still be undone; then the next command other
+    /// than `undo` applies it instead of running.
+    pub fn process_command(
+        &mut self,
+        state: &mut GameState,
+        console: &mut Console,
+        command: &str,
+        args: Vec<String>,
+    ) -> GameResult<Option<DeathOutcome>> {
+        if command == "undo" {
+            self.undo(state, args.first().map(|s| s.as_str()))?;
+            return Ok(None);
+        }
+        if !state.is_player_alive() {
+            return self.settle(state).map(Some);
+        }
+        if self.depth == 0 || READ_ONLY_COMMANDS.contains(&command) {
+            process_command(state, console, command, args)?;
+        } else {
+            let before = state.clone();
+            process_command(state, console, command, args)?;
+            self.snapshots.push_back(before);
+            if self.snapshots.len() > self.depth {
+                self.snapshots.pop_front();
+            }
+        }
+        if state.is_player_alive() {
+            return Ok(None);
+        }
+        if self.can_undo() {
+            say!("You died! Type 'undo' to take it back.");
+            return Ok(None);
+        }
+        self.settle(state).map(Some)
+    }
+
+    /// Apply the death policy to the current, dead, character.
+    pub fn settle(&mut self, state: &mut GameState) -> GameResult<DeathOutcome> {
+        say!("You died!");
+        self.snapshots.clear();
+        resolve_death(state, self.death_policy)
+    }
+
+    fn undo(&mut self, state: &mut GameState, turns: Option<&str>) -> GameResult<()> {
+        match self.difficulty {
+            Difficulty::Casual => {}
+            Difficulty::Normal => {
+                return Err(GameError::InvalidState(
+                    "Undo is only available on casual difficulty.".into(),
+                ))
+            }
+            Difficulty::Ironman => {
+                return Err(GameError::InvalidState(
+                    "There is no undo in ironman mode.".into(),
+                ))
+            }
+        }
+        let turns = match turns {
+            Some(turns) => turns
+                .parse::<usize>()
+                .ok()
+                .filter(|&turns| turns > 0)
+                .ok_or_else(|| {
+                    GameError::InvalidInput(format!("'{}' is not a number of turns", turns))
+                })?,
+            None => 1,
+        };
+        if turns > self.snapshots.len() {
+            return Err(GameError::InvalidState(format!(
+                "Can only undo {} turn(s).",
+                self.snapshots.len()
+            )));
+        }
+        let keep = self.snapshots.len() - turns;
+        let restored = self.snapshots.drain(keep..).next();
+        if let Some(restored) = restored {
+            *state = restored;
+        }
+        say!("Undid {} turn(s).", turns);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+
+    fn same_state(a: &GameState, b: &GameState) -> bool {
+        serde_json::to_value(a).unwrap() == serde_json::to_value(b).unwrap()
+    }
+
+    fn casual(undo_depth: usize) -> History {
+        History::new(&Config {
+            difficulty: Difficulty::Casual,
+            undo_depth,
+            ..Config::default()
+        })
+    }
+
+    fn run(history: &mut History, state: &mut GameState, command: &str, args: &[&str]) {
+        let args = args.iter().map(|arg| arg.to_string()).collect();
+        let mut console = Console::replay(&[]);
+        history
+            .process_command(state, &mut console, command, args)
+            .unwrap();
+    }
+
+    fn undo(history: &mut History, state: &mut GameState, turns: &str) -> GameResult<()> {
+        let mut console = Console::replay(&[]);
+        history
+            .process_command(state, &mut console, "undo", vec![turns.to_string()])
+            .map(|_| ())
+    }
+
+    #[test]
+    fn undo_restores_the_state_before_the_command() {
+        let mut state = fixtures::arena();
+        let mut history = casual(10);
+        let before = state.clone();
+        run(&mut history, &mut state, "attack", &["1"]);
+        assert!(!same_state(&before, &state));
+        undo(&mut history, &mut state, "1").unwrap();
+        assert!(same_state(&before, &state));
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn commands_that_change_nothing_are_not_undone() {
+        let mut state = fixtures::arena();
+        let mut history = casual(10);
+        run(&mut history, &mut state, "status", &[]);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn failed_commands_are_not_undone() {
+        let mut state = fixtures::arena();
+        let mut history = casual(10);
+        let mut console = Console::replay(&[]);
+        assert!(history
+            .process_command(&mut state, &mut console, "attack", vec!["99".into()])
+            .is_err());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn read_only_commands_are_game_commands() {
+        for name in READ_ONLY_COMMANDS {
+            assert!(
+                crate::game::command::GAME_COMMANDS
+                    .iter()
+                    .any(|spec| spec.name == *name),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn only_the_latest_turns_up_to_the_limit_are_kept() {
+        let mut state = fixtures::arena();
+        let mut history = casual(2);
+        run(&mut history, &mut state, "attack", &["1"]);
+        let after_first = state.clone();
+        run(&mut history, &mut state, "attack", &["1"]);
+        run(&mut history, &mut state, "attack", &["1"]);
+        match undo(&mut history, &mut state, "3") {
+            Err(GameError::InvalidState(message)) => {
+                assert_eq!(message, "Can only undo 2 turn(s).")
+            }
+            other => panic!("undoing past the limit gave {:?}", other),
+        }
+        undo(&mut history, &mut state, "2").unwrap();
+        assert!(same_state(&after_first, &state));
+    }
+
+    #[test]
+    fn undo_with_nothing_to_undo_leaves_the_state_alone() {
+        let mut state = fixtures::arena();
+        let before = state.clone();
+        let mut history = casual(10);
+        assert!(matches!(
+            undo(&mut history, &mut state, "1"),
+            Err(GameError::InvalidState(_))
+        ));
+        assert!(matches!(
+            undo(&mut history, &mut state, "none"),
+            Err(GameError::InvalidInput(_))
+        ));
+        assert!(same_state(&before, &state));
+    }
+
+    #[test]
+    fn undo_is_only_for_casual_difficulty() {
+        let mut state = fixtures::arena();
+        let mut history = History::new(&Config::default());
+        run(&mut history, &mut state, "attack", &["1"]);
+        assert!(!history.can_undo());
+        assert!(matches!(
+            undo(&mut history, &mut state, "1"),
+            Err(GameError::InvalidState(_))
+        ));
+    }
+}
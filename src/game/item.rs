@@ -20,6 +20,9 @@ pub struct Item {
     /// For equipment, this might be a bonus added to the base stats.
     /// For consumables, it might be applied once.
     pub stat_modifier: Stats,
+    /// Free-form labels, e.g. `heavy`, that classes and races can forbid.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 impl Item {
@@ -36,6 +39,7 @@ impl Item {
             description: description.to_string(),
             item_type,
             stat_modifier,
+            tags: Vec::new(),
         }
     }
 
@@ -44,10 +48,6 @@ impl Item {
         self.id
     }
 
-    pub fn name(&self) -> &str {
-        &self.name
-    }
-
     pub fn _description(&self) -> &str {
         &self.description
     }
@@ -0,0 +1,33 @@
+// src/game/log.rs
+use std::cell::RefCell;
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Print a game message, like `println!`, unless a frontend is capturing
+/// them with `capture`.
+macro_rules! say {
+    () => {
+        $crate::game::log::message(String::new())
+    };
+    ($($arg:tt)*) => {
+        $crate::game::log::message(format!($($arg)*))
+    };
+}
+pub(crate) use say;
+
+pub fn message(text: String) {
+    CAPTURED.with(|captured| match captured.borrow_mut().as_mut() {
+        Some(lines) => lines.extend(text.lines().map(str::to_string)),
+        None => println!("{}", text),
+    });
+}
+
+/// Run `f` and return the messages it produced instead of printing them.
+pub fn capture<T>(f: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let previous = CAPTURED.with(|captured| captured.borrow_mut().replace(Vec::new()));
+    let result = f();
+    let lines = CAPTURED.with(|captured| std::mem::replace(&mut *captured.borrow_mut(), previous));
+    (result, lines.unwrap_or_default())
+}
@@ -1,148 +1,304 @@
+pub mod character;
 pub mod combat;
+pub mod command;
+pub mod config;
+pub mod console;
+pub mod content;
+pub mod cooldown;
+pub mod death;
+pub mod debug;
+pub mod dialogue;
+pub mod dungeon;
+pub mod effect;
 pub mod entity;
+pub mod error;
+pub mod faction;
+#[cfg(test)]
+pub mod fixtures;
+pub mod format;
+pub mod history;
 pub mod item;
+pub mod log;
 pub mod persistence;
+pub mod quest;
+pub mod replay;
+pub mod rng;
+pub mod skill_tree;
 pub mod skills;
 pub mod stats;
+pub mod validation;
+pub mod world;
 
-/// Processes a command string by updating the game state accordingly.
+use command::{resolve_index, GAME_COMMANDS};
+use console::Console;
+use error::{GameError, GameResult};
+use log::say;
+use quest::QuestEvent;
 
+/// Processes a command string by updating the game state accordingly.
+/// `command` may be any name or alias from `command::GAME_COMMANDS`.
+/// Follow-up prompts for missing arguments go through `console`.
 pub fn process_command(
     state: &mut persistence::GameState,
+    console: &mut Console,
     command: &str,
     args: Vec<String>,
-) -> Result<(), String> {
-    match command.to_lowercase().as_str() {
-        "attack" | "a" => {
+) -> GameResult<()> {
+    // Debug-only commands are filtered out by `command::parse`
+    let spec = command::find(GAME_COMMANDS, command, true).ok_or_else(|| {
+        GameError::InvalidInput(format!(
+            "unknown command '{}'. Type 'help' for a list of commands.",
+            command
+        ))
+    })?;
+    if spec.debug {
+        return debug::process_command(state, spec, &args);
+    }
+    match spec.name {
+        "attack" => {
             if state.entities.len() < 2 {
-                return Err("Not enough entities to engage in combat.".into());
+                return Err(GameError::InvalidState(
+                    "Not enough entities to engage in combat.".into(),
+                ));
             }
 
-            let player = &mut state.players[state.player_index];
-            let enemy = &mut state.enemies[state.enemy_index];
-            let mut _skill_id = 1;
-
-            if args.len() < 1 {
-                // Print player skills and ask for input
-                println!("Player Skills: {:?}", player.get_skills_string());
-                println!("Enter skill id to use:");
-                let mut input = String::new();
-                std::io::stdin().read_line(&mut input).unwrap();
-                _skill_id = input.trim().parse::<usize>().unwrap();
-            // else if args[0] is a number
-            } else {
-                _skill_id = args[0].parse::<usize>().unwrap();
+            if let Some(enemy_ref) = args.get(1) {
+                let index = resolve_index(&state.enemies, enemy_ref, "enemy")?;
+                state.set_enemy(index)?;
             }
+            let (player, enemy) = state.get_combatants()?;
 
-            let skill = &mut player.get_skill(_skill_id - 1).clone();
+            let skill = match (cooldown::channelling(player), args.first()) {
+                // The channel takes the turn whatever the player picks
+                (Some(channelled), _) => channelled.clone(),
+                (None, Some(skill_ref)) => player
+                    .get_skill(resolve_index(&player.skills, skill_ref, "skill")?)?
+                    .clone(),
+                (None, None) => {
+                    console.require_interactive("skill")?;
+                    // Print player skills and ask for input
+                    say!("Skills:{}", cooldown::skills_string(player));
+                    let skill_ref = console.ask("Enter skill to use: ")?;
+                    player
+                        .get_skill(resolve_index(&player.skills, &skill_ref, "skill")?)?
+                        .clone()
+                }
+            };
 
             // execute a combat round
-            combat::attack_entity(player, enemy, skill);
-            combat::combat_round(enemy, player);
+            let summons = combat::attack_entity(player, enemy, &skill)?;
+            combat::place(state, summons, true)?;
+            combat::allies_turn(state)?;
+            let (player, enemy) = state.get_combatants()?;
+            if enemy.stats.hp > 0 {
+                let summons = combat::combat_round(enemy, player)?;
+                combat::place(state, summons, false)?;
+            }
+            let (player, enemy) = state.get_combatants()?;
+            if cooldown::has_timers(player) && player.stats.hp > 0 && enemy.stats.hp > 0 {
+                say!("Skills:{}", cooldown::skills_string(player));
+            }
 
             if !state.is_enemy_alive() {
-                println!("Enemy defeated!");
-                // remove enemy from the state
-                state.remove_enemy(state.enemy_index);
+                say!("Enemy defeated!");
+                state.reward_kill()?;
+                enemy_defeated(state)?;
             }
             Ok(())
         }
         "run" => {
             if state.entities.len() < 2 {
-                return Err("Not enough entities to run away.".into());
+                return Err(GameError::InvalidState(
+                    "Not enough entities to run away.".into(),
+                ));
             }
-            combat::combat_round(
-                &mut state.enemies[state.enemy_index],
-                &mut state.players[state.player_index],
-            );
+            // In a world you flee to a neighbouring room
+            let exit = if state.world.is_empty() {
+                None
+            } else {
+                Some(world::random_exit(state)?)
+            };
+            let (player, enemy) = state.get_combatants()?;
+            let summons = combat::combat_round(enemy, player)?;
+            combat::place(state, summons, false)?;
             if !state.is_player_alive() {
                 return Ok(());
             }
 
-            println!("You ran away!");
+            say!("You ran away!");
+            if let Some(exit) = exit {
+                return world::walk(state, &exit);
+            }
 
-            println!("A New Enemy Approaches!");
+            say!("A New Enemy Approaches!");
 
-            let new_enemy_index = rand::random::<usize>() % state.enemies.len();
-            state.set_enemy(new_enemy_index);
+            let new_enemy_index = rng::random::<usize>() % state.enemies.len();
+            state.set_enemy(new_enemy_index)?;
+            combat::end_encounter(state);
 
-            println!("New Enemy: {:?}", state.enemies[state.enemy_index].name);
+            say!("New Enemy: {:?}", state.get_enemy()?.name);
 
             Ok(())
         }
         "status" => {
             if state.entities.len() < 2 {
-                return Err("Not enough entities to display status.".into());
+                return Err(GameError::InvalidState(
+                    "Not enough entities to display status.".into(),
+                ));
+            }
+            let player = state.get_player()?;
+            say!("[PLAYER]\n{}", player.get_entity_string());
+            // Rooms of the world map may have no enemies in them
+            if let Ok(enemy) = state.get_enemy() {
+                say!("[ENEMY]\n{}", enemy.get_entity_string());
+            }
+            for ally in &state.allies {
+                say!("[ALLY]\n{}", ally.get_entity_string());
             }
-            let player = &state.players[state.player_index];
-            let enemy = &state.enemies[state.enemy_index];
-
-            println!("[PLAYER]\n{}", player.get_entity_string());
-            println!("[ENEMY]\n{}", enemy.get_entity_string());
             Ok(())
         }
         "show_enemies" => {
             for enemy in &state.enemies {
-                println!("{:?}", enemy);
+                say!("{:?}", enemy);
             }
             Ok(())
         }
         "show_inventory" => {
-            let player = &state.players[state.player_index];
+            let player = state.get_player()?;
             for item in &player.inventory {
-                println!("{:?}", item);
+                say!("{:?}", item);
             }
             Ok(())
         }
         "equip" => {
-            let player = &mut state.players[state.player_index];
-            println!("Inventory: {}", player.get_inventory_string());
-            println!("Enter item id to equip:");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
-            let item_id = input.trim().parse::<usize>().unwrap();
-
-            let item = player.get_item(item_id).clone();
-            player.equip_item(item);
-            Ok(())
+            let player = state.get_player_mut()?;
+            let item_ref = match args.first() {
+                Some(item_ref) => item_ref.clone(),
+                None => {
+                    console.require_interactive("item")?;
+                    say!("Inventory: {}", player.get_inventory_string());
+                    console.ask("Enter item to equip: ")?
+                }
+            };
+            let item_index = resolve_index(&player.inventory, &item_ref, "item")?;
+
+            let item = player.get_item(item_index)?.clone();
+            character::check_equip(state, state.get_player()?, &item)?;
+            state.get_player_mut()?.equip_item(item)
         }
         "unequip" => {
-            let player = &mut state.players[state.player_index];
-            println!("Equipment: {:?}", player.get_equipment_string());
-            println!("Enter item id to unequip:");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
-            let item_id = input.trim().parse::<usize>().unwrap();
-
-            let item = player.get_equipment(item_id).clone();
-            player.unequip_item(item);
-            Ok(())
+            let player = state.get_player_mut()?;
+            let item_ref = match args.first() {
+                Some(item_ref) => item_ref.clone(),
+                None => {
+                    console.require_interactive("item")?;
+                    say!("Equipment: {:?}", player.get_equipment_string());
+                    console.ask("Enter item to unequip: ")?
+                }
+            };
+            let item_index = resolve_index(&player.equipment, &item_ref, "equipped item")?;
+
+            let item = player.get_equipment(item_index)?.clone();
+            player.unequip_item(item)
         }
         "pickup_sword" => {
-            let player = &mut state.players[state.player_index];
-            let sword = state.items[0].clone();
+            let sword = state
+                .items
+                .first()
+                .cloned()
+                .ok_or_else(|| GameError::NotFound("there is no sword to pick up".into()))?;
+            let player = state.get_player_mut()?;
             player.add_item_to_inventory(sword);
             // player.equip_item(player.inventory[0]);
-            Ok(())
+            quest::notify(state, QuestEvent::PickedUp)
         }
         "use_item" => {
-            let player = &mut state.players[state.player_index];
-            println!("\tInventory:\n{}", player.get_inventory_string());
-            println!("Enter item id to use:");
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input).unwrap();
-            let item_id = input.trim().parse::<usize>().unwrap();
-
-            let item = player.get_item(item_id).clone();
-            player.use_item(item);
-            Ok(())
+            let player = state.get_player_mut()?;
+            let item_ref = match args.first() {
+                Some(item_ref) => item_ref.clone(),
+                None => {
+                    console.require_interactive("item")?;
+                    say!("\tInventory:\n{}", player.get_inventory_string());
+                    console.ask("Enter item to use: ")?
+                }
+            };
+            let item_index = resolve_index(&player.inventory, &item_ref, "item")?;
+
+            let item = player.get_item(item_index)?.clone();
+            player.use_item(item)
         }
+        "look" => world::look(state),
+        "go" => world::go(state, spec.arg(&args, 0)?),
+        "map" => world::map(state),
+        "take" => world::take(state, console, args.first().map(|s| s.as_str())),
+        "quests" => quest::list(state),
+        "reputation" => faction::list(state),
+        "skills" => skill_tree::list(state),
+        "learn" => skill_tree::learn(state, spec.arg(&args, 0)?),
+        "train" => character::train(
+            state,
+            args.first().map(|s| s.as_str()),
+            args.get(1).map(|s| s.as_str()),
+        ),
+        "quest" => quest::show(state, spec.arg(&args, 0)?),
+        "accept" => quest::accept(state, spec.arg(&args, 0)?),
+        "talk" => dialogue::talk(state, spec.arg(&args, 0)?),
+        "choose" => dialogue::choose(state, spec.arg(&args, 0)?),
+        "buy" => dialogue::buy(state, spec.arg(&args, 0)?),
+        "descend" => dungeon::descend(state, args.first().map(|s| s.as_str())),
+        "leave" => dungeon::leave(state),
         "help" => {
-            println!(
-                "Available commands: attack, run, (un)equip, use_item, status, show_enemies, show_inventory, help, exit"
+            say!(
+                "{}",
+                command::help(GAME_COMMANDS, args.first().map(|s| s.as_str()), false)?
             );
             Ok(())
         }
-        _ => Err("Unknown command. Type 'help' for a list of commands.".into()),
+        _ => Err(GameError::InvalidState(format!(
+            "'{}' can't be used here.",
+            spec.name
+        ))),
+    }
+}
+
+/// Let everything that cares know the current enemy has been defeated, then
+/// remove it.
+fn enemy_defeated(state: &mut persistence::GameState) -> GameResult<()> {
+    let enemy = state.get_enemy()?.clone();
+    dungeon::enemy_defeated(state)?;
+    quest::notify(state, QuestEvent::Killed(enemy.id))?;
+    faction::killed(state, &enemy)?;
+    state.remove_enemy(state.enemy_index);
+    // In the arena every enemy is a fight of its own
+    if state.enemies.is_empty() || state.world.is_empty() {
+        combat::end_encounter(state);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replays and the TUI hand commands over without `command::parse`.
+    fn run_bare(command: &str) -> GameResult<()> {
+        let mut state = persistence::GameState::new();
+        let mut console = Console::replay(&[]);
+        process_command(&mut state, &mut console, command, Vec::new())
+    }
+
+    #[test]
+    fn commands_without_their_arguments_fail_instead_of_panicking() {
+        for command in [
+            "go", "learn", "quest", "accept", "talk", "choose", "buy", "spawn", "give", "set",
+            "seed",
+        ] {
+            match run_bare(command) {
+                Err(GameError::InvalidInput(message)) => {
+                    assert!(message.starts_with("missing"), "{}: {}", command, message)
+                }
+                other => panic!("{}: expected missing argument, got {:?}", command, other),
+            }
+        }
     }
 }
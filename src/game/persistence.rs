@@ -1,11 +1,24 @@
 // src/game/persistence.rs
-use crate::game::entity::Entity;
+use crate::game::character::{self, Archetype, PointBuy};
+use crate::game::content::{Content, ContentEntity};
+use crate::game::cooldown::CombatState;
+use crate::game::dialogue::Dialogue;
+use crate::game::dungeon::{DungeonRun, DungeonTheme};
+use crate::game::entity::{level_hp, Entity};
+use crate::game::error::{GameError, GameResult};
+use crate::game::faction::{self, Faction};
+use crate::game::log::say;
+use crate::game::quest::Quest;
+use crate::game::rng;
+use crate::game::skill_tree::SkillTree;
+use crate::game::validation::Issue;
+use crate::game::world::{self, Location, World};
 use crate::game::{item::Item, skills::Skill, stats::Stats};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::io::{self, ErrorKind};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct GameState {
     pub entities: Vec<Entity>,
     pub pc_ents: Vec<Entity>,
@@ -15,35 +28,101 @@ pub struct GameState {
 
     pub players: Vec<Entity>,
     pub enemies: Vec<Entity>,
+    /// Creatures fighting on the player's side in the current encounter.
+    #[serde(default)]
+    pub allies: Vec<Entity>,
 
-    pub player_index: usize,
+    /// The selected character, if any.
+    #[serde(default)]
+    pub player_index: Option<usize>,
     pub enemy_index: usize,
+
+    /// Characters lost to permadeath.
+    #[serde(default)]
+    pub graveyard: Vec<Grave>,
+
+    /// Rooms to explore; empty for the classic arena.
+    #[serde(default)]
+    pub world: World,
+    /// Themes dungeons are generated from.
+    #[serde(default)]
+    pub dungeons: Vec<DungeonTheme>,
+    #[serde(default)]
+    pub quests: Vec<Quest>,
+    #[serde(default)]
+    pub dialogues: Vec<Dialogue>,
+    #[serde(default)]
+    pub factions: Vec<Faction>,
+    /// What new characters are made of; without any, every character is a
+    /// copy of the player template.
+    #[serde(default)]
+    pub classes: Vec<Archetype>,
+    #[serde(default)]
+    pub races: Vec<Archetype>,
+    #[serde(default)]
+    pub point_buy: PointBuy,
+    #[serde(default)]
+    pub skill_trees: Vec<SkillTree>,
+    /// Skill points characters earn on every level up.
+    #[serde(default)]
+    pub skill_points_per_level: u32,
     // Add additional fields if needed (e.g., current level, settings, etc.)
 }
 
-#[derive(Serialize, Deserialize)]
-struct RawEntity {
-    id: u32,
-    name: String,
-    stats: Stats,
-    inventory: Vec<u32>,
-    equipment: Vec<u32>,
-    skills: Vec<u32>,
+/// A character that died for good, and how.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Grave {
+    pub character: Entity,
+    pub cause: String,
+}
+
+/// How the game state is encoded on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SaveFormat {
+    /// Pretty-printed JSON, easy to read and edit by hand.
+    Json,
+    /// MessagePack with named fields: compact and quick to write, and still
+    /// tolerant of fields being added to the state later on.
+    Binary,
+}
+
+impl SaveFormat {
+    /// File extension for saves written in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Json => "json",
+            SaveFormat::Binary => "msgpack",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RawEntity {
+    pub(crate) id: u32,
+    pub(crate) name: String,
+    pub(crate) stats: Stats,
+    pub(crate) inventory: Vec<u32>,
+    pub(crate) equipment: Vec<u32>,
+    pub(crate) skills: Vec<u32>,
+    /// Makes the entity an NPC; entities without one are characters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) faction: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct EntitiesWrapper {
-    entities: Vec<RawEntity>,
+pub(crate) struct EntitiesWrapper {
+    pub(crate) entities: Vec<RawEntity>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct ItemsWrapper {
-    items: Vec<Item>,
+pub(crate) struct ItemsWrapper {
+    pub(crate) items: Vec<Item>,
 }
 
 #[derive(Serialize, Deserialize)]
-struct SkillsWrapper {
-    skills: Vec<Skill>,
+pub(crate) struct SkillsWrapper {
+    pub(crate) skills: Vec<Skill>,
 }
 
 const SPAWN_LIMIT: usize = 10;
@@ -59,54 +138,66 @@ impl GameState {
             skills: Vec::new(),
             players: Vec::new(),
             enemies: Vec::new(),
-            player_index: 0,
+            allies: Vec::new(),
+            player_index: None,
             enemy_index: 0,
+            graveyard: Vec::new(),
+            world: World::default(),
+            dungeons: Vec::new(),
+            quests: Vec::new(),
+            dialogues: Vec::new(),
+            factions: Vec::new(),
+            classes: Vec::new(),
+            races: Vec::new(),
+            point_buy: PointBuy::default(),
+            skill_trees: Vec::new(),
+            skill_points_per_level: 0,
         }
     }
 
-    /// Load the game state from a JSON file at the given path.
-    pub fn load_from_file(path: &str) -> io::Result<Self> {
-        // Read the file to a string
-        let data = fs::read_to_string(path)?;
-        // Deserialize the JSON string into a GameState struct
-        let state: GameState = serde_json::from_str(&data).map_err(|e| {
-            io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!("Deserialization error: {}", e),
-            )
-        })?;
-        Ok(state)
+    /// Load the game state from a file at the given path.
+    /// The encoding is detected from the contents, so JSON and binary saves
+    /// both load regardless of the file name.
+    pub fn load_from_file(path: &str) -> GameResult<Self> {
+        let data = fs::read(path)?;
+        // A JSON save is an object, binary saves never start with `{`
+        let is_json = data
+            .iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|byte| *byte == b'{');
+        let state: Result<GameState, String> = if is_json {
+            serde_json::from_slice(&data).map_err(|e| e.to_string())
+        } else {
+            rmp_serde::from_slice(&data).map_err(|e| e.to_string())
+        };
+        state.map_err(|e| GameError::Content(format!("Deserialization error: {}", e)))
     }
 
-    /// Save the current game state to a JSON file at the given path.
-    pub fn save_to_file(&self, path: &str) -> io::Result<()> {
-        // Serialize the GameState struct to a pretty JSON string
-        let data = serde_json::to_string_pretty(self).map_err(|e| {
-            io::Error::new(io::ErrorKind::Other, format!("Serialization error: {}", e))
-        })?;
-        // Write the JSON string to a file
-        fs::write(path, data)
+    /// Save the current game state to a file at the given path.
+    pub fn save_to_file(&self, path: &str, format: SaveFormat) -> GameResult<()> {
+        let data = match format {
+            SaveFormat::Json => serde_json::to_vec_pretty(self).map_err(|e| e.to_string()),
+            SaveFormat::Binary => rmp_serde::to_vec_named(self).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| GameError::Content(format!("Serialization error: {}", e)))?;
+        fs::write(path, data)?;
+        Ok(())
     }
 
     /// Load Enities
-    pub fn load_entities(&mut self, path: &str) -> io::Result<()> {
-        let data = fs::read_to_string(path)?;
+    pub(crate) fn load_entities(&mut self, entities: Vec<ContentEntity>) {
         // entity skills, inventory and equipment are saved as ids or names
         // we need to convert them to actual objects
         // we can do this by iterating over the entities and checking that the ids
         // exist in the GameState Items and Skills
         // if they do, we can replace the id with the actual object
         // if they don't, we must handle it gracefully
-        // NEW WAY
-        // parse data to replace ids with actual objects
-        let wrapper: EntitiesWrapper = serde_json::from_str(&data).map_err(|e| {
-            io::Error::new(
-                ErrorKind::InvalidData,
-                format!("Deserialization error loading entities: {}", e),
-            )
-        })?;
-        // iterate over entities and replace ids with actual objects
-        for entity in wrapper.entities {
+        for ContentEntity {
+            file,
+            index,
+            raw: entity,
+        } in entities
+        {
             let mut new_entity = Entity {
                 id: entity.id,
                 name: entity.name,
@@ -114,6 +205,21 @@ impl GameState {
                 inventory: Vec::new(),
                 equipment: Vec::new(),
                 skills: Vec::new(),
+                gold: 0,
+                experience: 0,
+                level: 1,
+                quests: Vec::new(),
+                faction: entity.faction,
+                reputation: BTreeMap::new(),
+                class: None,
+                race: None,
+                base: None,
+                attribute_points: 0,
+                skill_points: 0,
+                combat: CombatState::default(),
+                location: Location::default(),
+                dungeon: None,
+                conversation: None,
             };
             for i in 0..entity.skills.len() {
                 let skill_id = entity.skills[i];
@@ -121,10 +227,15 @@ impl GameState {
                 if let Some(skill) = skill {
                     new_entity.skills.push(skill);
                 } else {
-                    println!(
-                        "Skill with id {} not found for entity {}",
-                        skill_id, new_entity.name
+                    let issue = Issue::warning(
+                        &file,
+                        format!("entities[{}].skills[{}]", index, i),
+                        format!(
+                            "skill with id {} not found for entity {}",
+                            skill_id, new_entity.name
+                        ),
                     );
+                    say!("{}", issue);
                 }
             }
             for i in 0..entity.inventory.len() {
@@ -133,10 +244,15 @@ impl GameState {
                 if let Some(item) = item {
                     new_entity.inventory.push(item);
                 } else {
-                    println!(
-                        "Item with id {} not found for entity {}",
-                        item_id, new_entity.name
+                    let issue = Issue::warning(
+                        &file,
+                        format!("entities[{}].inventory[{}]", index, i),
+                        format!(
+                            "item with id {} not found for entity {}",
+                            item_id, new_entity.name
+                        ),
                     );
+                    say!("{}", issue);
                 }
             }
             for i in 0..entity.equipment.len() {
@@ -145,83 +261,107 @@ impl GameState {
                 if let Some(item) = item {
                     let c_item = item.clone();
                     new_entity.inventory.push(item);
-                    new_entity.equip_item(c_item);
+                    if let Err(e) = new_entity.equip_item(c_item) {
+                        let issue = Issue::warning(
+                            &file,
+                            format!("entities[{}].equipment[{}]", index, i),
+                            format!(
+                                "{} stays in the inventory of {}: {}",
+                                item_id, new_entity.name, e
+                            ),
+                        );
+                        say!("{}", issue);
+                    }
                 } else {
-                    println!(
-                        "Item with id {} not found for entity {}",
-                        item_id, new_entity.name
+                    let issue = Issue::warning(
+                        &file,
+                        format!("entities[{}].equipment[{}]", index, i),
+                        format!(
+                            "item with id {} not found for entity {}",
+                            item_id, new_entity.name
+                        ),
                     );
+                    say!("{}", issue);
                 }
             }
             self.entities.push(new_entity);
         }
 
-        // OLD WAY
-        // let wrapper: EntitiesWrapper = serde_json::from_str(&data).map_err(|e| {
-        //     io::Error::new(
-        //         ErrorKind::InvalidData,
-        //         format!("Deserialization error: {}", e),
-        //     )
-        // })?;
-        // self.entities = wrapper.entities;
-
         for entity in self.entities.clone() {
-            if entity.id < 1000 && entity.id >= 100 {
-                self.pc_ents.push(entity);
-            } else if entity.id >= 1000 {
+            if entity.faction.is_some() {
                 self.npc_ents.push(entity);
+            } else {
+                self.pc_ents.push(entity);
             }
         }
-
-        Ok(())
     }
 
-    pub fn load_items(&mut self, path: &str) -> io::Result<()> {
-        let data = fs::read_to_string(path)?;
-        let wrapper: ItemsWrapper = serde_json::from_str(&data).map_err(|e| {
-            io::Error::new(
-                ErrorKind::InvalidData,
-                format!("Deserialization error loading items: {}", e),
-            )
-        })?;
-        self.items = wrapper.items;
+    /// Replace the game data with merged base and pack content.
+    /// Live players and enemies keep their progress but pick up the new
+    /// skill and item definitions.
+    pub fn reload(&mut self, content: Content) -> GameResult<()> {
+        say!("Reloading game data...");
+        self.skills = content.skills;
+        self.items = content.items;
+        self.entities.clear();
+        self.pc_ents.clear();
+        self.npc_ents.clear();
+        self.load_entities(content.entities);
+        self.world = content.world;
+        self.dungeons = content.dungeons;
+        self.quests = content.quests;
+        self.dialogues = content.dialogues;
+        self.factions = content.factions;
+        self.classes = content.classes;
+        self.races = content.races;
+        self.point_buy = content.point_buy;
+        self.skill_trees = content.skill_trees;
+        self.skill_points_per_level = content.skill_points_per_level;
+
+        self.refresh_live_entities();
+        if self.world.is_empty() {
+            for player in &mut self.players {
+                player.location = Location::default();
+            }
+            return self.populate_enemies();
+        }
+        let lost = self
+            .location()
+            .is_ok_and(|location| world::find_room(self, &location.room).is_none());
+        if lost {
+            let start = self.world.start.clone();
+            world::enter_room(self, &start)?;
+        }
         Ok(())
     }
 
-    pub fn load_skills(&mut self, path: &str) -> io::Result<()> {
-        let data = fs::read_to_string(path)?;
-        let wrapper: SkillsWrapper = serde_json::from_str(&data).map_err(|e| {
-            io::Error::new(
-                ErrorKind::InvalidData,
-                format!("Deserialization error loading skills: {}", e),
-            )
-        })?;
-        self.skills = wrapper.skills;
-        Ok(())
+    /// Re-resolve the skills and items of every player and enemy by id.
+    pub fn refresh_live_entities(&mut self) {
+        for entity in self.players.iter_mut().chain(self.enemies.iter_mut()) {
+            let missing = entity.refresh_definitions(&self.skills, &self.items);
+            for id in missing {
+                say!(
+                    "Definition with id {} no longer exists; {} keeps the old one.",
+                    id,
+                    entity.name
+                );
+            }
+        }
     }
 
-    pub fn reload(
-        &mut self,
-        entities_path: &str,
-        items_path: &str,
-        skills_path: &str,
-    ) -> io::Result<()> {
-        println!("Reloading game data...");
-        self.load_skills(skills_path)?;
-        self.load_items(items_path)?;
-        self.load_entities(entities_path)?;
-
-        self.populate_enemies();
-        Ok(())
+    fn _get_entity_by_id(&self, id: u32) -> Option<Entity> {
+        self.entities.iter().find(|entity| entity.id == id).cloned()
     }
 
-    fn _get_entity_by_id(&self, id: u32) -> Option<Entity> {
-        for entity in self.entities.clone() {
-            if entity.id == id {
-                return Some(entity);
-            }
-        }
-        None
+    /// An id no character has had yet, buried ones included.
+    pub fn next_player_id(&self) -> u32 {
+        self.players
+            .iter()
+            .chain(self.graveyard.iter().map(|grave| &grave.character))
+            .map(|player| player.id)
+            .max()
+            .unwrap_or(0)
+            + 1
     }
 
     pub fn create_player(&mut self, entity: Entity) {
@@ -232,7 +372,7 @@ impl GameState {
         self.enemies.push(entity);
     }
 
-    pub fn spawn_enemy(&mut self) {
+    pub fn spawn_enemy(&mut self) -> GameResult<()> {
         // let enemy = self.enemies[self.enemy_index].clone();
         // let mut new_enemy = enemy.clone();
         // self.enemies.push(new_enemy);
@@ -240,62 +380,175 @@ impl GameState {
         let item_drop_rate = ITEM_DROP_RATE;
 
         if self.enemies.len() < spawn_limit {
-            let rand_index = rand::random::<usize>() % self.npc_ents.len();
-            let enemy = self.npc_ents[rand_index].clone();
-            let mut new_enemy = enemy.clone();
-            let gets_item = rand::random::<f32>() < item_drop_rate;
+            let mut new_enemy = self.random_npc()?;
+            let gets_item = rng::random::<f32>() < item_drop_rate;
             if gets_item {
-                let item = self.items[rand::random::<usize>() % self.items.len()].clone();
-                if !item.is_consumable() {
-                    let c_item = item.clone();
-                    new_enemy.inventory.push(item);
-                    new_enemy.equip_item(c_item);
-                } else {
-                    new_enemy.inventory.push(item);
-                }
+                self.give_random_item(&mut new_enemy);
             }
             self.create_enemy(new_enemy);
         }
+        Ok(())
+    }
+
+    /// Put a random item in `entity`'s inventory, equipping it if it can.
+    pub fn give_random_item(&self, entity: &mut Entity) {
+        if self.items.is_empty() {
+            return;
+        }
+        let item = self.items[rng::random::<usize>() % self.items.len()].clone();
+        if !item.is_consumable() {
+            let c_item = item.clone();
+            entity.inventory.push(item);
+            // If the slot is taken the item just stays in the inventory
+            entity.equip_item(c_item).ok();
+        } else {
+            entity.inventory.push(item);
+        }
     }
 
-    pub fn populate_enemies(&mut self) {
+    /// A fresh copy of the NPC definition with `id`.
+    pub fn npc(&self, id: u32) -> GameResult<Entity> {
+        self.npc_ents
+            .iter()
+            .find(|npc| npc.id == id)
+            .cloned()
+            .ok_or_else(|| GameError::Content(format!("no NPC with id {}", id)))
+    }
+
+    /// A fresh copy of a random NPC definition, out of those hostile to the
+    /// current character.
+    pub fn random_npc(&self) -> GameResult<Entity> {
+        let hostile: Vec<&Entity> = self
+            .npc_ents
+            .iter()
+            .filter(|npc| faction::is_hostile(self, npc))
+            .collect();
+        if hostile.is_empty() {
+            return Err(GameError::Content(
+                "no hostile NPC definitions to spawn enemies from".into(),
+            ));
+        }
+        let rand_index = rng::random::<usize>() % hostile.len();
+        Ok(hostile[rand_index].clone())
+    }
+
+    pub fn populate_enemies(&mut self) -> GameResult<()> {
         while self.enemies.len() < SPAWN_LIMIT {
-            self.spawn_enemy();
+            self.spawn_enemy()?;
         }
+        Ok(())
     }
 
     pub fn remove_enemy(&mut self, index: usize) {
-        self.enemies.remove(index);
+        if index < self.enemies.len() {
+            self.enemies.remove(index);
+        }
+        if self.enemy_index >= self.enemies.len() {
+            self.enemy_index = 0;
+        }
     }
 
     /// Set Player Index
-    pub fn set_player(&mut self, index: usize) {
-        self.player_index = index;
+    pub fn set_player(&mut self, index: usize) -> GameResult<()> {
+        let player = self
+            .players
+            .get(index)
+            .ok_or_else(|| GameError::NotFound(format!("no character number {}", index + 1)))?;
+        if player.stats.hp <= 0 {
+            return Err(GameError::InvalidState(format!(
+                "{} is dead and can't be played.",
+                player.name
+            )));
+        }
+        self.player_index = Some(index);
+        if self.world.is_empty() {
+            return Ok(());
+        }
+        // Characters pick up where they left off, mid-conversation too, or
+        // start at the beginning
+        let room = &self.location()?.room;
+        let (room, conversation) = match world::find_room(self, room) {
+            Some(_) => (room.clone(), self.get_player_mut()?.conversation.take()),
+            None => (self.world.start.clone(), None),
+        };
+        world::enter_room(self, &room)?;
+        self.get_player_mut()?.conversation = conversation;
+        Ok(())
+    }
+
+    /// Get Player
+    pub fn get_player(&self) -> GameResult<&Entity> {
+        self.player_index
+            .and_then(|index| self.players.get(index))
+            .ok_or_else(|| GameError::InvalidState("No character loaded.".into()))
     }
 
     /// Get Mutable Player
-    pub fn _get_player(&mut self) -> &mut Entity {
-        &mut self.players[self.player_index]
+    pub fn get_player_mut(&mut self) -> GameResult<&mut Entity> {
+        self.player_index
+            .and_then(|index| self.players.get_mut(index))
+            .ok_or_else(|| GameError::InvalidState("No character loaded.".into()))
+    }
+
+    /// Where the current character is.
+    pub fn location(&self) -> GameResult<&Location> {
+        Ok(&self.get_player()?.location)
+    }
+
+    pub fn location_mut(&mut self) -> GameResult<&mut Location> {
+        Ok(&mut self.get_player_mut()?.location)
+    }
+
+    /// The dungeon the current character is exploring, if any.
+    pub fn dungeon(&self) -> Option<&DungeonRun> {
+        self.get_player().ok()?.dungeon.as_ref()
     }
 
     /// Is Player Alive
     pub fn is_player_alive(&self) -> bool {
-        self.players[self.player_index].stats.hp > 0
+        self.get_player().is_ok_and(|player| player.stats.hp > 0)
     }
 
     /// Set Enemy Index
-    pub fn set_enemy(&mut self, index: usize) {
+    pub fn set_enemy(&mut self, index: usize) -> GameResult<()> {
+        if index >= self.enemies.len() {
+            return Err(GameError::NotFound(format!(
+                "no enemy number {}",
+                index + 1
+            )));
+        }
         self.enemy_index = index;
+        Ok(())
+    }
+
+    /// Get Enemy
+    pub fn get_enemy(&self) -> GameResult<&Entity> {
+        self.enemies
+            .get(self.enemy_index)
+            .ok_or_else(|| GameError::InvalidState("There is no enemy to fight.".into()))
     }
 
-    /// Get Mutable Enemy
-    pub fn _get_enemy(&mut self) -> &mut Entity {
-        &mut self.enemies[self.enemy_index]
+    /// Get the current player and enemy, both mutable.
+    pub fn get_combatants(&mut self) -> GameResult<(&mut Entity, &mut Entity)> {
+        // Heals in the fight go up to full health
+        let player_hp = self.get_player().map_or(0, |player| self.full_hp(player));
+        let enemy_hp = self.get_enemy().map_or(0, |enemy| self.full_hp(enemy));
+        let player = self
+            .player_index
+            .and_then(|index| self.players.get_mut(index))
+            .ok_or_else(|| GameError::InvalidState("No character loaded.".into()))?;
+        let enemy = self
+            .enemies
+            .get_mut(self.enemy_index)
+            .ok_or_else(|| GameError::InvalidState("There is no enemy to fight.".into()))?;
+        player.combat.max_hp = player_hp;
+        enemy.combat.max_hp = enemy_hp;
+        Ok((player, enemy))
     }
 
     /// Is Enemy Alive
     pub fn is_enemy_alive(&self) -> bool {
-        self.enemies[self.enemy_index].stats.hp > 0
+        self.get_enemy().is_ok_and(|enemy| enemy.stats.hp > 0)
     }
 
     pub fn _is_item(&self, id: u32) -> bool {
@@ -317,44 +570,224 @@ impl GameState {
     }
 
     pub fn get_skill_by_id(&self, id: u32) -> Option<Skill> {
-        for skill in self.skills.clone() {
-            if skill.id == id {
-                return Some(skill);
-            }
-        }
-        None
+        self.skills.iter().find(|skill| skill.id == id).cloned()
     }
 
     pub fn get_item_by_id(&self, id: u32) -> Option<Item> {
-        for item in self.items.clone() {
-            if item.id == id {
-                return Some(item);
-            }
-        }
-        None
+        self.items.iter().find(|item| item.id == id).cloned()
     }
 
-    pub fn get_default_player(&self) -> Entity {
-        self.entities[0].clone()
+    pub fn get_default_player(&self) -> GameResult<Entity> {
+        self.entities
+            .first()
+            .cloned()
+            .ok_or_else(|| GameError::Content("no player template loaded".into()))
+    }
+
+    /// HP `entity` has when fully healed: the HP of its definition (the
+    /// player template for characters without a class) at its level, with
+    /// the definition's equipment swapped for its own.
+    pub fn full_hp(&self, entity: &Entity) -> i32 {
+        if let Some(full_hp) = character::full_hp(self, entity) {
+            return full_hp;
+        }
+        let definition = self
+            .npc_ents
+            .iter()
+            .find(|npc| npc.id == entity.id)
+            .or_else(|| self.entities.first());
+        let equipment_hp = |e: &Entity| {
+            e.equipment
+                .iter()
+                .map(|item| item.stat_modifier.hp)
+                .sum::<i32>()
+        };
+        match definition {
+            Some(definition) => {
+                level_hp(definition.stats.hp - equipment_hp(definition), entity.level)
+                    + equipment_hp(entity)
+            }
+            None => entity.stats.hp,
+        }
     }
 
     pub fn _get_skills_string(&self) -> String {
         let mut skills = String::new();
-        let mut i = 1;
-        for skill in self.skills.clone() {
-            skills.push_str(&format!("{}. {}\n", i, skill._get_skill_string()));
-            i += 1;
+        for (i, skill) in self.skills.iter().enumerate() {
+            skills.push_str(&format!("{}. {}\n", i + 1, skill._get_skill_string()));
         }
         skills
     }
 
+    /// Give the player experience and gold for defeating the current enemy:
+    /// a tenth of its full HP in XP and its attack in gold.
+    pub fn reward_kill(&mut self) -> GameResult<()> {
+        let enemy = self.get_enemy()?;
+        let experience = (self.full_hp(enemy) / 10).max(1) as u32;
+        let gold = enemy.stats.attack.max(0) as u32;
+        self.get_player_mut()?.gold += gold;
+        say!("You gain {} XP and {} gold.", experience, gold);
+        character::gain_experience(self, experience)
+    }
+
+    /// Move the character at `index` to the graveyard.
+    pub fn bury_player(&mut self, index: usize, cause: &str) -> GameResult<()> {
+        if index >= self.players.len() {
+            return Err(GameError::NotFound(format!(
+                "no character number {}",
+                index + 1
+            )));
+        }
+        let character = self.players.remove(index);
+        self.graveyard.push(Grave {
+            character,
+            cause: cause.to_string(),
+        });
+        // Whoever played the buried character has to pick another
+        self.player_index = match self.player_index {
+            Some(selected) if selected > index => Some(selected - 1),
+            Some(selected) if selected == index => None,
+            selected => selected,
+        };
+        Ok(())
+    }
+
+    pub fn get_graveyard_string(&self) -> String {
+        let mut graves = String::new();
+        for (i, grave) in self.graveyard.iter().enumerate() {
+            let stats = grave.character.stats;
+            graves.push_str(&format!(
+                "{}. {}: {}\n\tAttack {}  Defense {}  Agility {}  Gold {}  XP {}\n",
+                i + 1,
+                grave.character.name,
+                grave.cause,
+                stats.attack,
+                stats.defense,
+                stats.agility,
+                grave.character.gold,
+                grave.character.experience
+            ));
+        }
+        graves
+    }
+
     pub fn get_players_string(&self) -> String {
         let mut players = String::new();
-        let mut i = 1;
-        for player in self.players.clone() {
-            players.push_str(&format!("{}. {}\n", i, player.get_entity_string()));
-            i += 1;
+        for (i, player) in self.players.iter().enumerate() {
+            players.push_str(&format!("{}. {}\n", i + 1, player.get_entity_string()));
         }
         players
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::log;
+
+    fn party(names: &[&str]) -> GameState {
+        let mut state = GameState::new();
+        for name in names {
+            let mut player = Entity::new(state.next_player_id(), name);
+            player.stats.hp = 10;
+            state.create_player(player);
+        }
+        state
+    }
+
+    #[test]
+    fn buried_characters_keep_their_ids() {
+        let mut state = party(&["Ayla", "Bren", "Cato"]);
+        state.bury_player(2, "Died").unwrap();
+        state.bury_player(0, "Died").unwrap();
+        assert_eq!(state.next_player_id(), 4);
+        state.create_player(Entity::new(state.next_player_id(), "Dara"));
+        let ids: Vec<u32> = state.players.iter().map(|player| player.id).collect();
+        assert_eq!(ids, [2, 4]);
+    }
+
+    #[test]
+    fn burying_the_selected_character_clears_the_selection() {
+        let mut state = party(&["Ayla", "Bren", "Cato"]);
+        state.set_player(2).unwrap();
+        state.bury_player(0, "Died").unwrap();
+        assert_eq!(state.get_player().unwrap().name, "Cato");
+        state.bury_player(1, "Died").unwrap();
+        assert_eq!(state.player_index, None);
+        assert!(state.get_player().is_err());
+    }
+
+    fn saved_and_loaded(state: &GameState, format: SaveFormat, name: &str) -> (Vec<u8>, GameState) {
+        let path = std::env::temp_dir().join(format!("save-test-{}-{}", name, std::process::id()));
+        let path = path.to_str().unwrap();
+        state.save_to_file(path, format).unwrap();
+        let data = fs::read(path).unwrap();
+        let loaded = GameState::load_from_file(path);
+        fs::remove_file(path).unwrap();
+        (data, loaded.unwrap())
+    }
+
+    #[test]
+    fn both_save_formats_round_trip_and_are_detected() {
+        let state = crate::game::fixtures::village();
+        let expected = serde_json::to_value(&state).unwrap();
+        let (json, from_json) = saved_and_loaded(&state, SaveFormat::Json, "json");
+        let (binary, from_binary) = saved_and_loaded(&state, SaveFormat::Binary, "binary");
+        assert_eq!(json.first(), Some(&b'{'));
+        assert_ne!(binary.first(), Some(&b'{'));
+        assert!(binary.len() < json.len());
+        assert_eq!(serde_json::to_value(&from_json).unwrap(), expected);
+        assert_eq!(serde_json::to_value(&from_binary).unwrap(), expected);
+    }
+
+    /// The content the village was built from, as files would provide it.
+    fn village_content(state: &GameState) -> Content {
+        let raw = |entity: &Entity| RawEntity {
+            id: entity.id,
+            name: entity.name.clone(),
+            stats: entity.stats,
+            inventory: Vec::new(),
+            equipment: Vec::new(),
+            skills: entity.skills.iter().map(|skill| skill.id).collect(),
+            faction: entity.faction.clone(),
+        };
+        let mut content = Content::new(
+            "entities.json",
+            state.entities.iter().map(raw).collect(),
+            state.items.clone(),
+            state.skills.clone(),
+        );
+        content.world = state.world.clone();
+        content
+    }
+
+    #[test]
+    fn reloading_keeps_ids_and_live_entities() {
+        let mut state = crate::game::fixtures::village();
+        let goblin = state.npc(100).unwrap();
+        state.create_enemy(goblin);
+        let before = serde_json::to_value(&state).unwrap();
+
+        for _ in 0..2 {
+            let content = village_content(&state);
+            let (result, lines) = log::capture(|| state.reload(content));
+            result.unwrap();
+            assert_eq!(lines, ["Reloading game data..."]);
+        }
+        assert_eq!(serde_json::to_value(&state).unwrap(), before);
+    }
+
+    #[test]
+    fn reload_reports_missing_definitions_in_the_log() {
+        let mut state = crate::game::fixtures::village();
+        let mut content = village_content(&state);
+        content.skills.retain(|skill| skill.id != 2);
+        let (result, lines) = log::capture(|| state.reload(content));
+        result.unwrap();
+        assert!(
+            lines[1].contains("skill with id 2 not found for entity Goblin"),
+            "{:?}",
+            lines
+        );
+    }
+}
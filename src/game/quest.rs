@@ -0,0 +1,533 @@
+// src/game/quest.rs
+use crate::game::character;
+use crate::game::command::resolve_id;
+use crate::game::entity::Entity;
+use crate::game::error::{GameError, GameResult};
+use crate::game::faction;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct QuestsWrapper {
+    pub(crate) quests: Vec<Quest>,
+}
+
+/// A quest from `quests.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quest {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    /// Quests that must be completed before this one is offered.
+    #[serde(default)]
+    pub prerequisites: Vec<u32>,
+    /// NPC who hands the quest out in dialogue. Quests without one are
+    /// taken on with `accept` once their prerequisites are met.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub giver: Option<u32>,
+    pub objectives: Vec<Objective>,
+    #[serde(default)]
+    pub rewards: Rewards,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Objective {
+    /// Defeat `count` enemies with NPC id `npc`.
+    Kill {
+        npc: u32,
+        #[serde(default = "one")]
+        count: u32,
+    },
+    /// Carry `count` of item `item`.
+    Collect {
+        item: u32,
+        #[serde(default = "one")]
+        count: u32,
+    },
+    /// Walk into room `room`.
+    Reach { room: String },
+    /// Speak with NPC `npc`.
+    Talk { npc: u32 },
+}
+
+fn one() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rewards {
+    #[serde(default)]
+    pub experience: u32,
+    #[serde(default)]
+    pub gold: u32,
+    /// Item ids put in the inventory.
+    #[serde(default)]
+    pub items: Vec<u32>,
+}
+
+/// How far a character has got with a quest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestProgress {
+    pub id: u32,
+    /// Progress of each objective, in the order of the quest's objectives.
+    pub progress: Vec<u32>,
+    pub completed: bool,
+}
+
+/// Something that happened in the game that objectives may be waiting for.
+#[derive(Debug, Clone, Copy)]
+pub enum QuestEvent<'a> {
+    Killed(u32),
+    /// Something went into the inventory.
+    PickedUp,
+    Entered(&'a str),
+    Talked(u32),
+}
+
+impl Objective {
+    /// The progress at which the objective is met.
+    pub fn target(&self) -> u32 {
+        match self {
+            Objective::Kill { count, .. } | Objective::Collect { count, .. } => *count,
+            Objective::Reach { .. } | Objective::Talk { .. } => 1,
+        }
+    }
+
+    /// Progress after `event`, starting from `progress`.
+    fn advance(&self, progress: u32, event: QuestEvent, player: &Entity) -> u32 {
+        let progress = match (self, event) {
+            (Objective::Kill { npc, .. }, QuestEvent::Killed(id)) if *npc == id => progress + 1,
+            (Objective::Reach { room }, QuestEvent::Entered(id)) if room == id => 1,
+            (Objective::Talk { npc }, QuestEvent::Talked(id)) if *npc == id => 1,
+            // Counted from what is carried, so selling or using items undoes it
+            (Objective::Collect { item, .. }, _) => carried(player, *item),
+            _ => progress,
+        };
+        progress.min(self.target())
+    }
+
+    fn describe(&self, state: &GameState) -> String {
+        let npc_name = |id: u32| {
+            state
+                .entities
+                .iter()
+                .find(|entity| entity.id == id)
+                .map_or_else(|| format!("NPC {}", id), |entity| entity.name.clone())
+        };
+        match self {
+            Objective::Kill { npc, count } => format!("Defeat {} x{}", npc_name(*npc), count),
+            Objective::Collect { item, count } => {
+                let name = state
+                    .get_item_by_id(*item)
+                    .map_or_else(|| format!("item {}", item), |item| item.name);
+                format!("Collect {} x{}", name, count)
+            }
+            Objective::Reach { room } => {
+                let name = state
+                    .world
+                    .room(room)
+                    .map_or(room.as_str(), |(_, room)| room.name.as_str());
+                format!("Reach {}", name)
+            }
+            Objective::Talk { npc } => format!("Talk to {}", npc_name(*npc)),
+        }
+    }
+}
+
+fn carried(player: &Entity, item: u32) -> u32 {
+    player
+        .inventory
+        .iter()
+        .chain(player.equipment.iter())
+        .filter(|carried| carried.id == item)
+        .count() as u32
+}
+
+impl QuestProgress {
+    fn is_done(&self, quest: &Quest) -> bool {
+        quest
+            .objectives
+            .iter()
+            .zip(&self.progress)
+            .all(|(objective, progress)| *progress >= objective.target())
+    }
+}
+
+fn quest_by_id(state: &GameState, id: u32) -> Option<&Quest> {
+    state.quests.iter().find(|quest| quest.id == id)
+}
+
+/// Whether `player` may start `quest`: not started yet, with every
+/// prerequisite completed.
+fn is_unlocked(player: &Entity, quest: &Quest) -> bool {
+    !player.quests.iter().any(|q| q.id == quest.id)
+        && quest
+            .prerequisites
+            .iter()
+            .all(|id| player.quests.iter().any(|q| q.id == *id && q.completed))
+}
+
+fn begin(player: &mut Entity, quest: &Quest) {
+    let progress = quest
+        .objectives
+        .iter()
+        .map(|objective| match objective {
+            Objective::Collect { item, count } => carried(player, *item).min(*count),
+            _ => 0,
+        })
+        .collect();
+    player.quests.push(QuestProgress {
+        id: quest.id,
+        progress,
+        completed: false,
+    });
+    say!("New quest: {}", quest.name);
+}
+
+/// Whether `player` may take `quest` on with `accept`: nobody hands it out
+/// and it is unlocked.
+fn is_available(player: &Entity, quest: &Quest) -> bool {
+    quest.giver.is_none() && is_unlocked(player, quest)
+}
+
+/// Take on a quest nobody hands out, once its prerequisites are completed.
+pub fn accept(state: &mut GameState, quest_ref: &str) -> GameResult<()> {
+    let quest = state.quests[resolve_id(&state.quests, quest_ref, "quest")?].clone();
+    let player = state.get_player()?;
+    if player.quests.iter().any(|q| q.id == quest.id) {
+        return Err(GameError::InvalidState(format!(
+            "You have already taken on {}.",
+            quest.name
+        )));
+    }
+    if let Some(giver) = quest.giver {
+        let giver = state
+            .npc(giver)
+            .map_or_else(|_| format!("NPC {}", giver), |npc| npc.name);
+        return Err(GameError::InvalidState(format!(
+            "{} is handed out by {}.",
+            quest.name, giver
+        )));
+    }
+    if !is_unlocked(player, &quest) {
+        return Err(GameError::InvalidState(format!(
+            "{} is still locked; see 'quest {}'.",
+            quest.name, quest.id
+        )));
+    }
+    begin(state.get_player_mut()?, &quest);
+    Ok(())
+}
+
+/// Start quest `id` for the current character, as handed out in dialogue.
+/// Does nothing if it is already started or still locked.
+pub fn start(state: &mut GameState, id: u32) -> GameResult<()> {
+    let quest = quest_by_id(state, id)
+        .cloned()
+        .ok_or_else(|| GameError::Content(format!("no quest with id {}", id)))?;
+    let player = state.get_player_mut()?;
+    if is_unlocked(player, &quest) {
+        begin(player, &quest);
+    }
+    Ok(())
+}
+
+/// Where the current character stands with quest `id`: `None` if not
+/// started, otherwise whether it is completed.
+pub fn status(state: &GameState, id: u32) -> Option<bool> {
+    let player = state.get_player().ok()?;
+    player
+        .quests
+        .iter()
+        .find(|q| q.id == id)
+        .map(|q| q.completed)
+}
+
+/// Update the current character's quests after `event`, handing out the
+/// rewards of any that are now complete. Does nothing without a character.
+pub fn notify(state: &mut GameState, event: QuestEvent) -> GameResult<()> {
+    if state.get_player().is_err() {
+        return Ok(());
+    }
+    let quests = state.quests.clone();
+    let player = state.get_player_mut()?;
+    let snapshot = player.clone();
+    let mut finished = Vec::new();
+    for progress in player.quests.iter_mut().filter(|q| !q.completed) {
+        let Some(quest) = quests.iter().find(|quest| quest.id == progress.id) else {
+            continue;
+        };
+        for (objective, value) in quest.objectives.iter().zip(progress.progress.iter_mut()) {
+            let before = *value;
+            *value = objective.advance(before, event, &snapshot);
+            if *value > before {
+                say!("{}: {}/{}", quest.name, value, objective.target());
+            }
+        }
+        if progress.is_done(quest) {
+            progress.completed = true;
+            finished.push(quest.clone());
+        }
+    }
+    for quest in &finished {
+        complete(state, quest)?;
+    }
+    if !finished.is_empty() {
+        // Completing a quest may unlock others
+        let player = state.get_player()?;
+        for quest in quests.iter().filter(|quest| is_available(player, quest)) {
+            say!(
+                "New quest available: {}. Type 'accept {}' to take it on.",
+                quest.name,
+                quest.id
+            );
+        }
+    }
+    Ok(())
+}
+
+fn complete(state: &mut GameState, quest: &Quest) -> GameResult<()> {
+    let items: Vec<_> = quest
+        .rewards
+        .items
+        .iter()
+        .filter_map(|id| state.get_item_by_id(*id))
+        .collect();
+    let player = state.get_player_mut()?;
+    player.gold += quest.rewards.gold;
+    say!(
+        "Quest complete: {}! You gain {} XP and {} gold.",
+        quest.name,
+        quest.rewards.experience,
+        quest.rewards.gold
+    );
+    for item in items {
+        say!("You receive {}.", item.name);
+        player.add_item_to_inventory(item);
+    }
+    character::gain_experience(state, quest.rewards.experience)?;
+    let giver_faction = quest
+        .giver
+        .and_then(|id| state.npc(id).ok())
+        .and_then(|giver| giver.faction);
+    match giver_faction {
+        Some(id) => faction::helped(state, &id),
+        None => Ok(()),
+    }
+}
+
+/// List the current character's quests, and those it can take on.
+pub fn list(state: &GameState) -> GameResult<()> {
+    let player = state.get_player()?;
+    let available: Vec<&Quest> = state
+        .quests
+        .iter()
+        .filter(|quest| is_available(player, quest))
+        .collect();
+    if player.quests.is_empty() && available.is_empty() {
+        say!("You have no quests.");
+        return Ok(());
+    }
+    let mut text = String::from("Quests:");
+    for progress in &player.quests {
+        let Some(quest) = quest_by_id(state, progress.id) else {
+            continue;
+        };
+        let status = if progress.completed {
+            "completed".to_string()
+        } else {
+            let done = quest
+                .objectives
+                .iter()
+                .zip(&progress.progress)
+                .filter(|(objective, value)| **value >= objective.target())
+                .count();
+            format!("{}/{} objectives", done, quest.objectives.len())
+        };
+        text.push_str(&format!(
+            "\n    [id {}] {} ({})",
+            quest.id, quest.name, status
+        ));
+    }
+    if player.quests.is_empty() {
+        text.push_str("\n    none yet");
+    }
+    if !available.is_empty() {
+        text.push_str("\nAvailable, take one on with 'accept <quest>':");
+        for quest in available {
+            text.push_str(&format!("\n    [id {}] {}", quest.id, quest.name));
+        }
+    }
+    say!("{}", text);
+    Ok(())
+}
+
+/// Show one quest: what to do, how far along the character is and what it
+/// pays.
+pub fn show(state: &GameState, quest_ref: &str) -> GameResult<()> {
+    let quest = &state.quests[resolve_id(&state.quests, quest_ref, "quest")?];
+    let player = state.get_player()?;
+    let progress = player.quests.iter().find(|q| q.id == quest.id);
+    let status = match progress {
+        Some(progress) if progress.completed => "Completed",
+        Some(_) => "Active",
+        None if is_available(player, quest) => "Available",
+        None => "Locked",
+    };
+    let mut text = format!(
+        "{} [{}]\n    {}\n    Objectives:",
+        quest.name, status, quest.description
+    );
+    for (i, objective) in quest.objectives.iter().enumerate() {
+        let value = progress.map_or(0, |progress| {
+            if progress.completed {
+                objective.target()
+            } else {
+                progress.progress.get(i).copied().unwrap_or(0)
+            }
+        });
+        text.push_str(&format!(
+            "\n        {} ({}/{})",
+            objective.describe(state),
+            value,
+            objective.target()
+        ));
+    }
+    if progress.is_none() && !quest.prerequisites.is_empty() {
+        let names: Vec<&str> = quest
+            .prerequisites
+            .iter()
+            .filter_map(|id| quest_by_id(state, *id))
+            .map(|quest| quest.name.as_str())
+            .collect();
+        text.push_str(&format!("\n    Requires: {}", names.join(", ")));
+    }
+    let rewards = &quest.rewards;
+    let mut reward = format!("{} XP, {} gold", rewards.experience, rewards.gold);
+    for item in rewards
+        .items
+        .iter()
+        .filter_map(|id| state.get_item_by_id(*id))
+    {
+        reward.push_str(&format!(", {}", item.name));
+    }
+    text.push_str(&format!("\n    Rewards: {}", reward));
+    say!("{}", text);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+    use crate::game::log;
+
+    fn quest(id: u32, name: &str, prerequisites: Vec<u32>, giver: Option<u32>) -> Quest {
+        Quest {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            prerequisites,
+            giver,
+            objectives: vec![Objective::Kill { npc: 100, count: 1 }],
+            rewards: Rewards::default(),
+        }
+    }
+
+    /// The arena with goblin hunts to take on, one after the other, and one
+    /// that a goblin hands out.
+    fn hunts() -> GameState {
+        let mut state = fixtures::arena();
+        state.quests = vec![
+            quest(1, "First Hunt", Vec::new(), None),
+            quest(2, "Second Hunt", vec![1], None),
+            quest(3, "Goblin Favour", Vec::new(), Some(100)),
+        ];
+        state
+    }
+
+    fn started(state: &GameState) -> Vec<u32> {
+        let player = state.get_player().unwrap();
+        player.quests.iter().map(|q| q.id).collect()
+    }
+
+    #[test]
+    fn quests_only_start_when_taken_on() {
+        let mut state = hunts();
+        notify(&mut state, QuestEvent::Killed(100)).unwrap();
+        list(&state).unwrap();
+        show(&state, "1").unwrap();
+        assert!(started(&state).is_empty());
+
+        accept(&mut state, "First Hunt").unwrap();
+        assert_eq!(started(&state), [1]);
+        assert!(matches!(
+            accept(&mut state, "1"),
+            Err(GameError::InvalidState(_))
+        ));
+    }
+
+    #[test]
+    fn locked_and_handed_out_quests_cannot_be_accepted() {
+        let mut state = hunts();
+        for quest in ["Second Hunt", "Goblin Favour"] {
+            assert!(matches!(
+                accept(&mut state, quest),
+                Err(GameError::InvalidState(_))
+            ));
+        }
+        accept(&mut state, "1").unwrap();
+        let (result, lines) = log::capture(|| notify(&mut state, QuestEvent::Killed(100)));
+        result.unwrap();
+        assert!(lines.iter().any(|line| line.contains("accept 2")));
+        assert_eq!(started(&state), [1]);
+        accept(&mut state, "2").unwrap();
+        assert_eq!(started(&state), [1, 2]);
+    }
+
+    #[test]
+    fn the_quest_list_shows_ids_and_what_can_be_taken_on() {
+        let mut state = hunts();
+        accept(&mut state, "1").unwrap();
+        let (result, lines) = log::capture(|| list(&state));
+        result.unwrap();
+        let text = lines.join("\n");
+        assert!(
+            text.contains("[id 1] First Hunt (0/1 objectives)"),
+            "{}",
+            text
+        );
+        assert!(!text.contains("Second Hunt"), "{}", text);
+        assert!(!text.contains("Goblin Favour"), "{}", text);
+
+        notify(&mut state, QuestEvent::Killed(100)).unwrap();
+        let (_, lines) = log::capture(|| list(&state));
+        let text = lines.join("\n");
+        assert!(text.contains("[id 1] First Hunt (completed)"), "{}", text);
+        assert!(text.contains("[id 2] Second Hunt"), "{}", text);
+    }
+
+    #[test]
+    fn quest_numbers_are_ids_not_positions() {
+        let mut state = fixtures::arena();
+        state.quests = vec![
+            quest(2, "Second Hunt", Vec::new(), None),
+            quest(1, "First Hunt", Vec::new(), None),
+        ];
+        accept(&mut state, "1").unwrap();
+        assert_eq!(started(&state), [1]);
+        let (result, lines) = log::capture(|| show(&state, "2"));
+        result.unwrap();
+        assert!(
+            lines[0].starts_with("Second Hunt [Available]"),
+            "{:?}",
+            lines
+        );
+        assert!(matches!(
+            accept(&mut state, "3"),
+            Err(GameError::NotFound(_))
+        ));
+    }
+}
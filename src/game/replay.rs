@@ -0,0 +1,169 @@
+// src/game/replay.rs
+use crate::game::config::Config;
+use crate::game::console::Console;
+use crate::game::content::read_content;
+use crate::game::error::{GameError, GameResult};
+use crate::game::history::History;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::rng;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// One command as it went through `process_command`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    pub command: String,
+    pub args: Vec<String>,
+    /// Answers typed at follow-up prompts, e.g. the skill for a bare `attack`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub answers: Vec<String>,
+}
+
+/// A recorded play session: enough to run it again and get the same result.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    /// Settings when recorded, so `undo` and deaths play back the same.
+    #[serde(default)]
+    pub config: Config,
+    pub start: GameState,
+    pub commands: Vec<RecordedCommand>,
+    /// State after the last command, to check playback against.
+    pub end: GameState,
+}
+
+impl Replay {
+    /// Start recording from `state`. Reseeds the game's RNG so the session
+    /// can be repeated.
+    pub fn record(state: &GameState, config: &Config) -> Self {
+        let seed = rng::fresh_seed();
+        rng::seed(seed);
+        Self {
+            seed,
+            config: config.clone(),
+            start: state.clone(),
+            commands: Vec::new(),
+            end: state.clone(),
+        }
+    }
+
+    pub fn push(&mut self, command: RecordedCommand, state: &GameState) {
+        self.commands.push(command);
+        self.end = state.clone();
+    }
+
+    pub fn save(&self, path: &str) -> GameResult<()> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| GameError::Content(format!("Serialization error: {}", e)))?;
+        fs::write(path, data)?;
+        Ok(())
+    }
+
+    pub fn load(path: &str) -> GameResult<Self> {
+        read_content(path, "replay")
+    }
+
+    /// Run the recorded commands again from the starting state and return
+    /// the final state. `before_command` is called with each command before
+    /// it runs, so a caller can step through.
+    pub fn play(&self, mut before_command: impl FnMut(usize, &RecordedCommand)) -> GameState {
+        rng::seed(self.seed);
+        let mut state = self.start.clone();
+        let mut history = History::new(&self.config);
+        for (i, recorded) in self.commands.iter().enumerate() {
+            before_command(i, recorded);
+            let mut console = Console::replay(&recorded.answers);
+            // Failed commands failed when they were recorded too
+            if let Err(e) = history.process_command(
+                &mut state,
+                &mut console,
+                &recorded.command,
+                recorded.args.clone(),
+            ) {
+                say!("Error: {}", e);
+            }
+        }
+        state
+    }
+
+    /// Whether `state` is exactly the state the recording ended in.
+    pub fn matches_end(&self, state: &GameState) -> GameResult<bool> {
+        let to_value = |state: &GameState| {
+            serde_json::to_value(state)
+                .map_err(|e| GameError::Content(format!("Serialization error: {}", e)))
+        };
+        Ok(to_value(state)? == to_value(&self.end)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::config::Difficulty;
+    use crate::game::fixtures;
+
+    /// Record a session of fighting, undoing and running away in the arena
+    /// with `seed`.
+    fn session(seed: u64) -> Replay {
+        let config = Config {
+            difficulty: Difficulty::Casual,
+            ..Config::default()
+        };
+        let mut state = fixtures::arena();
+        let mut replay = Replay::record(&state, &config);
+        replay.seed = seed;
+        rng::seed(seed);
+        let mut history = History::new(&config);
+        let commands = [
+            ("attack", vec!["1"]),
+            ("attack", vec!["Strike"]),
+            ("undo", vec![]),
+            ("attack", vec!["1", "2"]),
+            ("run", vec![]),
+            ("attack", vec!["1"]),
+        ];
+        for (command, args) in commands {
+            let recorded = RecordedCommand {
+                command: command.to_string(),
+                args: args.into_iter().map(String::from).collect(),
+                answers: Vec::new(),
+            };
+            let mut console = Console::replay(&[]);
+            history
+                .process_command(&mut state, &mut console, command, recorded.args.clone())
+                .unwrap();
+            replay.push(recorded, &state);
+        }
+        replay
+    }
+
+    #[test]
+    fn a_recording_plays_back_to_its_final_state() {
+        let replay = session(7);
+        let mut played = Vec::new();
+        let state = replay.play(|i, recorded| played.push((i, recorded.command.clone())));
+        assert_eq!(played.len(), 6);
+        assert!(replay.matches_end(&state).unwrap());
+        assert!(!replay.matches_end(&replay.start).unwrap());
+    }
+
+    #[test]
+    fn a_recording_survives_saving_and_loading() {
+        let replay = session(11);
+        let path = std::env::temp_dir().join(format!("replay-test-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        replay.save(path).unwrap();
+        let loaded = Replay::load(path);
+        fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+        assert!(loaded.matches_end(&loaded.play(|_, _| {})).unwrap());
+    }
+
+    #[test]
+    fn another_seed_fails_verification() {
+        let mut replay = session(7);
+        replay.seed = 8;
+        assert!(!replay.matches_end(&replay.play(|_, _| {})).unwrap());
+    }
+}
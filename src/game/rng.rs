@@ -0,0 +1,45 @@
+// src/game/rng.rs
+use rand::distributions::{uniform::SampleUniform, Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cell::RefCell;
+use std::ops::Range;
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+/// Every random roll in the game goes through this generator, so seeding it
+/// makes spawns, drops and damage repeatable.
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+/// A seed from the operating system, for runs that should be random but
+/// repeatable later.
+pub fn fresh_seed() -> u64 {
+    StdRng::from_entropy().gen()
+}
+
+/// A generator of its own, for things built from a seed such as dungeon
+/// floors, so they come out the same whatever else was rolled. Each
+/// `stream` of a seed rolls differently.
+pub fn generator(seed: u64, stream: u64) -> StdRng {
+    StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
+
+/// Run `f` with the shared generator, for code that takes any generator.
+pub fn with<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+pub fn random<T>() -> T
+where
+    Standard: Distribution<T>,
+{
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+pub fn gen_range<T: SampleUniform + PartialOrd>(range: Range<T>) -> T {
+    RNG.with(|rng| rng.borrow_mut().gen_range(range))
+}
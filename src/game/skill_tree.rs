@@ -0,0 +1,320 @@
+// src/game/skill_tree.rs
+use crate::game::command::resolve_index;
+use crate::game::entity::Entity;
+use crate::game::error::{GameError, GameResult};
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::skills::Skill;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SkillTreesWrapper {
+    /// Skill points characters earn on every level up.
+    #[serde(default = "one")]
+    pub(crate) points_per_level: u32,
+    pub(crate) trees: Vec<SkillTree>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+/// A skill tree from `skill_trees.json`: skills characters can learn, and
+/// rank up, with skill points.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkillTree {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Class ids that may learn from the tree; none means every character.
+    #[serde(default)]
+    pub classes: Vec<u32>,
+    pub nodes: Vec<TreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    /// Id of the skill the node teaches.
+    pub skill: u32,
+    /// Skill ids the character must know before learning this one.
+    #[serde(default)]
+    pub requires: Vec<u32>,
+    /// Character level needed to learn the skill.
+    #[serde(default = "one")]
+    pub level: u32,
+    /// Skill points to learn the skill, and again for every rank after.
+    #[serde(default = "one")]
+    pub cost: u32,
+}
+
+/// The skill trees `player` may learn from.
+fn trees_for<'a>(state: &'a GameState, player: &Entity) -> Vec<&'a SkillTree> {
+    state
+        .skill_trees
+        .iter()
+        .filter(|tree| {
+            tree.classes.is_empty()
+                || player
+                    .class
+                    .is_some_and(|class| tree.classes.contains(&class))
+        })
+        .collect()
+}
+
+fn skill_name(state: &GameState, id: u32) -> String {
+    state
+        .get_skill_by_id(id)
+        .map_or_else(|| format!("skill {}", id), |skill| skill.name)
+}
+
+/// Why `player` can't learn the skill of `node`, or its next rank, right
+/// now; `None` if they can.
+fn blocker(state: &GameState, player: &Entity, node: &TreeNode, skill: &Skill) -> Option<String> {
+    let rank = player
+        .skills
+        .iter()
+        .find(|known| known.id == skill.id)
+        .map_or(0, |known| known.rank);
+    if rank >= skill.max_rank {
+        return Some("mastered".into());
+    }
+    let mut missing = Vec::new();
+    if player.level < node.level {
+        missing.push(format!("level {}", node.level));
+    }
+    for id in &node.requires {
+        if !player.skills.iter().any(|known| known.id == *id) {
+            missing.push(skill_name(state, *id));
+        }
+    }
+    if !missing.is_empty() {
+        return Some(format!("needs {}", missing.join(", ")));
+    }
+    if player.skill_points < node.cost {
+        return Some(format!(
+            "needs {} skill points, you have {}",
+            node.cost, player.skill_points
+        ));
+    }
+    None
+}
+
+/// The nodes of every tree `player` may learn from, with their skills.
+fn learnable(state: &GameState, player: &Entity) -> Vec<(TreeNode, Skill)> {
+    trees_for(state, player)
+        .into_iter()
+        .flat_map(|tree| &tree.nodes)
+        .filter_map(|node| {
+            state
+                .get_skill_by_id(node.skill)
+                .map(|skill| (node.clone(), skill))
+        })
+        .collect()
+}
+
+/// List the current character's skills, and what it can learn next.
+pub fn list(state: &GameState) -> GameResult<()> {
+    let player = state.get_player()?;
+    let mut text = format!("Skills ({} skill points):", player.skill_points);
+    for (i, skill) in player.skills.iter().enumerate() {
+        text.push_str(&format!(
+            "\n    {}. {} (rank {}/{}) - power {}, cost {}",
+            i + 1,
+            skill.name,
+            skill.rank,
+            skill.max_rank,
+            skill.power,
+            skill.cost
+        ));
+    }
+    let mut number = 0;
+    for tree in trees_for(state, player) {
+        text.push_str(&format!("\n{}:", tree.name));
+        for node in &tree.nodes {
+            let Some(skill) = state.get_skill_by_id(node.skill) else {
+                continue;
+            };
+            number += 1;
+            let known = player.skills.iter().find(|known| known.id == skill.id);
+            let status = match (known, blocker(state, player, node, &skill)) {
+                (Some(known), None) => format!(
+                    "rank {}/{}, next rank for {} points",
+                    known.rank, skill.max_rank, node.cost
+                ),
+                (Some(known), Some(reason)) => {
+                    format!("rank {}/{}, {}", known.rank, skill.max_rank, reason)
+                }
+                (None, None) => format!("learn for {} points", node.cost),
+                (None, Some(reason)) => reason,
+            };
+            text.push_str(&format!("\n    {}. {} - {}", number, skill.name, status));
+        }
+    }
+    if number > 0 {
+        text.push_str("\nLearn with 'learn <skill>'.");
+    }
+    say!("{}", text);
+    Ok(())
+}
+
+/// Spend skill points on learning a skill from a tree, or on its next rank
+/// if the current character knows it already.
+pub fn learn(state: &mut GameState, skill_ref: &str) -> GameResult<()> {
+    let player = state.get_player()?;
+    let nodes = learnable(state, player);
+    if nodes.is_empty() {
+        return Err(GameError::NotFound(
+            "there are no skill trees to learn from".into(),
+        ));
+    }
+    let skills: Vec<Skill> = nodes.iter().map(|(_, skill)| skill.clone()).collect();
+    let (node, skill) = nodes[resolve_index(&skills, skill_ref, "skill to learn")?].clone();
+    if player
+        .skills
+        .iter()
+        .any(|known| known.id == skill.id && known.rank >= skill.max_rank)
+    {
+        return Err(GameError::InvalidState(format!(
+            "{} already has {} at its highest rank.",
+            player.name, skill.name
+        )));
+    }
+    if let Some(reason) = blocker(state, player, &node, &skill) {
+        return Err(GameError::InvalidState(format!(
+            "You can't learn {} yet: {}.",
+            skill.name, reason
+        )));
+    }
+    let player = state.get_player_mut()?;
+    player.skill_points -= node.cost;
+    match player.skills.iter_mut().find(|known| known.id == skill.id) {
+        Some(known) => {
+            *known = skill.at_rank(known.rank + 1);
+            say!(
+                "{} reaches rank {} in {}: power {}, cost {}.",
+                player.name,
+                known.rank,
+                known.name,
+                known.power,
+                known.cost
+            );
+        }
+        None => {
+            say!(
+                "{} learns {}: power {}, cost {}.",
+                player.name,
+                skill.name,
+                skill.power,
+                skill.cost
+            );
+            player.skills.push(skill);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures::{self, skill};
+    use crate::game::log;
+
+    fn node(skill: u32, requires: Vec<u32>, level: u32, cost: u32) -> TreeNode {
+        TreeNode {
+            skill,
+            requires,
+            level,
+            cost,
+        }
+    }
+
+    /// The arena with a fire tree: Fireball, which ranks up twice, and
+    /// Meteor, which needs Fireball and level 3.
+    fn fire() -> GameState {
+        let mut state = fixtures::arena();
+        let mut fireball = skill(3, "Fireball", 10);
+        fireball.max_rank = 3;
+        fireball.power_per_rank = 4;
+        fireball.cost = 5;
+        fireball.cost_per_rank = 2;
+        state.skills.push(fireball);
+        state.skills.push(skill(4, "Meteor", 24));
+        state.skill_trees = vec![SkillTree {
+            id: "fire".to_string(),
+            name: "Fire".to_string(),
+            description: String::new(),
+            classes: Vec::new(),
+            nodes: vec![node(3, Vec::new(), 1, 1), node(4, vec![3], 3, 2)],
+        }];
+        state.get_player_mut().unwrap().skill_points = 10;
+        state
+    }
+
+    fn learn_error(state: &mut GameState, skill_ref: &str) -> String {
+        match learn(state, skill_ref) {
+            Err(GameError::InvalidState(message)) => message,
+            other => panic!("expected learn {} to fail, got {:?}", skill_ref, other),
+        }
+    }
+
+    #[test]
+    fn learning_spends_skill_points_and_ranks_up() {
+        let mut state = fire();
+        learn(&mut state, "fireball").unwrap();
+        let (result, lines) = log::capture(|| learn(&mut state, "fireball"));
+        result.unwrap();
+        assert_eq!(
+            lines,
+            ["Mira reaches rank 2 in Fireball: power 14, cost 7."]
+        );
+        let player = state.get_player().unwrap();
+        assert_eq!(player.skill_points, 8);
+        let fireball = player.skills.iter().find(|skill| skill.id == 3).unwrap();
+        assert_eq!((fireball.rank, fireball.power, fireball.cost), (2, 14, 7));
+    }
+
+    #[test]
+    fn ranks_stop_at_the_highest() {
+        let mut state = fire();
+        for _ in 0..3 {
+            learn(&mut state, "Fireball").unwrap();
+        }
+        assert_eq!(
+            learn_error(&mut state, "Fireball"),
+            "Mira already has Fireball at its highest rank."
+        );
+        assert_eq!(state.get_player().unwrap().skill_points, 7);
+    }
+
+    #[test]
+    fn prerequisites_levels_and_points_gate_learning() {
+        let mut state = fire();
+        assert_eq!(
+            learn_error(&mut state, "Meteor"),
+            "You can't learn Meteor yet: needs level 3, Fireball."
+        );
+        learn(&mut state, "Fireball").unwrap();
+        state.get_player_mut().unwrap().level = 3;
+        state.get_player_mut().unwrap().skill_points = 1;
+        assert_eq!(
+            learn_error(&mut state, "Meteor"),
+            "You can't learn Meteor yet: needs 2 skill points, you have 1."
+        );
+        state.get_player_mut().unwrap().skill_points = 2;
+        learn(&mut state, "Meteor").unwrap();
+        assert!(state.get_player().unwrap().skills.iter().any(|s| s.id == 4));
+    }
+
+    #[test]
+    fn trees_can_be_kept_to_classes() {
+        let mut state = fire();
+        state.skill_trees[0].classes = vec![2];
+        assert!(matches!(
+            learn(&mut state, "Fireball"),
+            Err(GameError::NotFound(_))
+        ));
+        state.get_player_mut().unwrap().class = Some(2);
+        learn(&mut state, "Fireball").unwrap();
+    }
+}
@@ -1,3 +1,4 @@
+use crate::game::effect::{Effect, Formula};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -7,6 +8,7 @@ pub enum SkillType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 pub enum SkillTarget {
     SelfTarget,
     SingleTarget,
@@ -29,9 +31,43 @@ pub struct Skill {
     pub skill_class: SkillClass,
     pub power: u32,
     pub cost: u32,
+    /// Rank a character has the skill at; `power` and `cost` include what
+    /// the ranks above the first add.
+    #[serde(default = "first_rank")]
+    pub rank: u32,
+    /// Highest rank the skill can be learned up to in a skill tree.
+    #[serde(default = "first_rank")]
+    pub max_rank: u32,
+    #[serde(default)]
+    pub power_per_rank: u32,
+    #[serde(default)]
+    pub cost_per_rank: u32,
+    /// Turns to wait after using the skill before it can be used again.
+    #[serde(default)]
+    pub cooldown: u32,
+    /// Uses per encounter; 0 means as many as the cooldown allows.
+    #[serde(default)]
+    pub charges: u32,
+    /// Turns for a used charge to come back; 0 means it doesn't.
+    #[serde(default)]
+    pub recharge: u32,
+    /// Turns between using the skill and it taking effect.
+    #[serde(default)]
+    pub warmup: u32,
+    /// Whether the user does nothing else while it warms up.
+    #[serde(default)]
+    pub channelled: bool,
+    /// What the skill does, in order; see `Skill::effects`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effects: Vec<Effect>,
+}
+
+fn first_rank() -> u32 {
+    1
 }
 
 impl Skill {
+    #[allow(clippy::too_many_arguments)]
     pub fn _new(
         id: u32,
         name: &str,
@@ -51,7 +87,40 @@ impl Skill {
             skill_class,
             power,
             cost,
+            rank: 1,
+            max_rank: 1,
+            power_per_rank: 0,
+            cost_per_rank: 0,
+            cooldown: 0,
+            charges: 0,
+            recharge: 0,
+            warmup: 0,
+            channelled: false,
+            effects: Vec::new(),
+        }
+    }
+
+    /// This skill, defined at rank 1, raised to `rank`.
+    pub fn at_rank(mut self, rank: u32) -> Self {
+        let gained = rank.saturating_sub(self.rank);
+        self.power += self.power_per_rank * gained;
+        self.cost += self.cost_per_rank * gained;
+        self.rank = rank;
+        self
+    }
+
+    /// The effects the skill runs. A skill defined without any deals damage
+    /// the way skills always have: a roll of its power plus the user's
+    /// attack.
+    pub fn effects(&self) -> Vec<Effect> {
+        if self.effects.is_empty() {
+            return vec![Effect::Damage {
+                formula: Formula::default(),
+                hits: 1,
+                drain: 0,
+            }];
         }
+        self.effects.clone()
     }
 
     pub fn _get_skill_string(&self) -> String {
@@ -28,6 +28,28 @@ impl Stats {
         self.agility += modifier.agility;
     }
 
+    /// The value of the stat called `name`.
+    pub fn get(&self, name: &str) -> Option<i32> {
+        match name.to_ascii_lowercase().as_str() {
+            "hp" => Some(self.hp),
+            "attack" => Some(self.attack),
+            "defense" => Some(self.defense),
+            "agility" => Some(self.agility),
+            _ => None,
+        }
+    }
+
+    /// Look a stat up by name, as typed by the player.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut i32> {
+        match name.to_ascii_lowercase().as_str() {
+            "hp" => Some(&mut self.hp),
+            "attack" => Some(&mut self.attack),
+            "defense" => Some(&mut self.defense),
+            "agility" => Some(&mut self.agility),
+            _ => None,
+        }
+    }
+
     // Get stats string for displaying in the UI.
     pub fn get_stats_string(&self) -> String {
         format!(
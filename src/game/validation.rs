@@ -0,0 +1,1513 @@
+// src/game/validation.rs
+use crate::game::character::{Archetype, ClassesWrapper, PointBuy};
+use crate::game::content::{self, Content, PACK_ENTITIES_FILE, PACK_ITEMS_FILE, PACK_SKILLS_FILE};
+use crate::game::dialogue::{Action, Condition, Dialogue, DialoguesWrapper};
+use crate::game::dungeon::{DungeonTheme, DungeonsWrapper};
+use crate::game::effect::Effect;
+use crate::game::faction::{Faction, FactionsWrapper, MAX_REPUTATION};
+use crate::game::format::{self, Format};
+use crate::game::item::{Item, ItemType};
+use crate::game::persistence::{EntitiesWrapper, ItemsWrapper, RawEntity, SkillsWrapper};
+use crate::game::quest::{Objective, Quest, QuestsWrapper};
+use crate::game::skill_tree::{SkillTree, SkillTreesWrapper};
+use crate::game::skills::Skill;
+use crate::game::stats::Stats;
+use crate::game::world::World;
+use serde::de::DeserializeOwned;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A single problem found in a content file.
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    pub file: String,
+    /// JSON path of the offending value, e.g. `entities[2].stats.hp`.
+    pub path: String,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn error(file: &str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            file: file.to_string(),
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(file: &str, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            file: file.to_string(),
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{}] {}: {}: {}",
+            self.severity, self.file, self.path, self.message
+        )
+    }
+}
+
+/// Every issue found while validating the asset files.
+#[derive(Debug, Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    pub fn push(&mut self, issue: Issue) {
+        self.issues.push(issue);
+    }
+
+    pub fn extend(&mut self, issues: Vec<Issue>) {
+        self.issues.extend(issues);
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == severity)
+            .count()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        write!(
+            f,
+            "{} error(s), {} warning(s)",
+            self.error_count(),
+            self.warning_count()
+        )
+    }
+}
+
+/// Paths of the content files `validate_assets` checks. Only the entity,
+/// item and skill files are required.
+pub struct AssetFiles<'a> {
+    pub entities: &'a str,
+    pub items: &'a str,
+    pub skills: &'a str,
+    pub world: &'a str,
+    pub dungeons: &'a str,
+    pub quests: &'a str,
+    pub dialogue: &'a str,
+    pub factions: &'a str,
+    pub classes: &'a str,
+    pub skill_trees: &'a str,
+}
+
+/// Check the content `files`, plus every content pack in `packs_dir`, and
+/// report every problem found.
+///
+/// Files that fail to parse are reported once and skipped. Entity references
+/// are checked against the merged content, so pack entities may use base
+/// skills and items.
+pub fn validate_assets(files: &AssetFiles, packs_dir: &str) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let entities_path = &format::resolve_variant(files.entities);
+    let items_path = &format::resolve_variant(files.items);
+    let skills_path = &format::resolve_variant(files.skills);
+
+    let skills = read_file::<SkillsWrapper>(skills_path, &mut report).map(|w| w.skills);
+    let items = read_file::<ItemsWrapper>(items_path, &mut report).map(|w| w.items);
+    let entities = read_file::<EntitiesWrapper>(entities_path, &mut report).map(|w| w.entities);
+
+    if let Some(skills) = &skills {
+        validate_skills(skills_path, skills, &mut report);
+    }
+    if let Some(items) = &items {
+        validate_items(items_path, items, &mut report);
+    }
+    if let Some(entities) = &entities {
+        validate_roster(entities_path, entities, &mut report);
+    }
+
+    let mut merged = Content::new(
+        entities_path,
+        entities.clone().unwrap_or_default(),
+        items.clone().unwrap_or_default(),
+        skills.clone().unwrap_or_default(),
+    );
+    let entities_parsed = entities.is_some();
+    let mut skill_files = Vec::new();
+    if let Some(skills) = skills.clone() {
+        skill_files.push((skills_path.to_string(), skills));
+    }
+    let mut entity_files = Vec::new();
+    if let Some(entities) = entities {
+        entity_files.push((entities_path.to_string(), entities));
+    }
+
+    let (packs, issues) = content::discover_packs(packs_dir);
+    report.extend(issues);
+    for pack in &packs {
+        let pack_content = match pack.load() {
+            Ok(pack_content) => pack_content,
+            Err(issue) => {
+                report.push(issue);
+                continue;
+            }
+        };
+        if !pack_content.skills.is_empty() {
+            validate_skills(
+                &pack.file_path(PACK_SKILLS_FILE),
+                &pack_content.skills,
+                &mut report,
+            );
+            skill_files.push((
+                pack.file_path(PACK_SKILLS_FILE),
+                pack_content.skills.clone(),
+            ));
+        }
+        if !pack_content.items.is_empty() {
+            validate_items(
+                &pack.file_path(PACK_ITEMS_FILE),
+                &pack_content.items,
+                &mut report,
+            );
+        }
+        if !pack_content.entities.is_empty() {
+            entity_files.push((
+                pack.file_path(PACK_ENTITIES_FILE),
+                pack_content.entities.clone(),
+            ));
+        }
+        merged.merge(pack, pack_content, &mut report.issues);
+    }
+
+    // Only check references against lists whose base file parsed, otherwise
+    // every reference to a base definition would be reported as missing.
+    let merged_skills = skills.as_ref().map(|_| merged.skills.as_slice());
+    let merged_items = items.as_ref().map(|_| merged.items.as_slice());
+    let factions_path = &format::resolve_variant(files.factions);
+    let factions = if Path::new(factions_path).exists() {
+        read_file::<FactionsWrapper>(factions_path, &mut report).map(|w| w.factions)
+    } else {
+        Some(Vec::new())
+    };
+    if let Some(factions) = &factions {
+        validate_factions(factions_path, factions, &mut report);
+    }
+    for (file, entities) in &entity_files {
+        validate_entities(
+            file,
+            entities,
+            merged_items,
+            merged_skills,
+            factions.as_deref(),
+            &mut report,
+        );
+    }
+
+    let classes_path = &format::resolve_variant(files.classes);
+    let classes = if Path::new(classes_path).exists() {
+        read_file::<ClassesWrapper>(classes_path, &mut report).map(|wrapper| {
+            validate_classes(
+                classes_path,
+                &wrapper,
+                merged_items,
+                merged_skills,
+                &mut report,
+            );
+            wrapper.classes
+        })
+    } else {
+        Some(Vec::new())
+    };
+    let skill_trees_path = &format::resolve_variant(files.skill_trees);
+    if Path::new(skill_trees_path).exists() {
+        if let Some(wrapper) = read_file::<SkillTreesWrapper>(skill_trees_path, &mut report) {
+            validate_skill_trees(
+                skill_trees_path,
+                &wrapper.trees,
+                merged_skills,
+                classes.as_deref(),
+                &mut report,
+            );
+        }
+    }
+
+    let npc_ids: Option<Vec<u32>> = entities_parsed.then(|| {
+        merged
+            .entities
+            .iter()
+            .filter(|entity| entity.raw.faction.is_some())
+            .map(|entity| entity.raw.id)
+            .collect()
+    });
+    if let Some(npc_ids) = &npc_ids {
+        for (file, skills) in &skill_files {
+            validate_summons(file, skills, npc_ids, &mut report);
+        }
+    }
+    let dungeons_path = &format::resolve_variant(files.dungeons);
+    let themes = if Path::new(dungeons_path).exists() {
+        read_file::<DungeonsWrapper>(dungeons_path, &mut report).map(|w| w.themes)
+    } else {
+        Some(Vec::new())
+    };
+    if let Some(themes) = &themes {
+        validate_dungeons(
+            dungeons_path,
+            themes,
+            npc_ids.as_deref(),
+            merged_items,
+            &mut report,
+        );
+    }
+    let world_path = &format::resolve_variant(files.world);
+    let world = if Path::new(world_path).exists() {
+        read_file::<World>(world_path, &mut report)
+    } else {
+        Some(World::default())
+    };
+    if let Some(world) = &world {
+        validate_world(
+            world_path,
+            world,
+            npc_ids.as_deref(),
+            merged_items,
+            themes.as_deref(),
+            &mut report,
+        );
+    }
+    let quests_path = &format::resolve_variant(files.quests);
+    let quests = if Path::new(quests_path).exists() {
+        read_file::<QuestsWrapper>(quests_path, &mut report).map(|w| w.quests)
+    } else {
+        Some(Vec::new())
+    };
+    if let Some(quests) = &quests {
+        validate_quests(
+            quests_path,
+            quests,
+            npc_ids.as_deref(),
+            merged_items,
+            world.as_ref(),
+            &mut report,
+        );
+    }
+    let dialogue_path = &format::resolve_variant(files.dialogue);
+    if Path::new(dialogue_path).exists() {
+        if let Some(wrapper) = read_file::<DialoguesWrapper>(dialogue_path, &mut report) {
+            validate_dialogue(
+                dialogue_path,
+                &wrapper.dialogues,
+                npc_ids.as_deref(),
+                merged_items,
+                quests.as_deref(),
+                factions.as_deref(),
+                &mut report,
+            );
+        }
+    }
+
+    report
+}
+
+fn read_file<T: DeserializeOwned>(path: &str, report: &mut ValidationReport) -> Option<T> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) => {
+            report.push(Issue::error(path, "$", format!("cannot read file: {}", e)));
+            return None;
+        }
+    };
+    match Format::from_path(path).parse(&data) {
+        Ok(wrapper) => Some(wrapper),
+        Err(e) => {
+            report.push(Issue::error(path, "$", format!("cannot parse file: {}", e)));
+            None
+        }
+    }
+}
+
+/// Report every id that was already used by an earlier element of the list.
+fn check_duplicate_ids<I>(file: &str, list: &str, ids: I, report: &mut ValidationReport)
+where
+    I: Iterator<Item = u32>,
+{
+    let mut seen: HashMap<u32, usize> = HashMap::new();
+    for (i, id) in ids.enumerate() {
+        if let Some(first) = seen.get(&id) {
+            report.push(Issue::error(
+                file,
+                format!("{}[{}].id", list, i),
+                format!("duplicate id {} (first defined at {}[{}])", id, list, first),
+            ));
+        } else {
+            seen.insert(id, i);
+        }
+    }
+}
+
+fn check_name(file: &str, path: String, name: &str, report: &mut ValidationReport) {
+    if name.trim().is_empty() {
+        report.push(Issue::warning(file, path, "name is empty"));
+    }
+}
+
+fn validate_skills(file: &str, skills: &[Skill], report: &mut ValidationReport) {
+    if skills.is_empty() {
+        report.push(Issue::error(file, "skills", "no skills defined"));
+    }
+    check_duplicate_ids(file, "skills", skills.iter().map(|s| s.id), report);
+
+    for (i, skill) in skills.iter().enumerate() {
+        check_name(file, format!("skills[{}].name", i), &skill.name, report);
+        if skill.max_rank == 0 {
+            report.push(Issue::error(
+                file,
+                format!("skills[{}].max_rank", i),
+                "max_rank must be at least 1",
+            ));
+        }
+        if skill.channelled && skill.warmup == 0 {
+            report.push(Issue::warning(
+                file,
+                format!("skills[{}].channelled", i),
+                "channelled has no effect without a warmup",
+            ));
+        }
+        if skill.recharge > 0 && skill.charges == 0 {
+            report.push(Issue::warning(
+                file,
+                format!("skills[{}].recharge", i),
+                "recharge has no effect without charges",
+            ));
+        }
+        if skill.rank != 1 {
+            report.push(Issue::warning(
+                file,
+                format!("skills[{}].rank", i),
+                "definitions are rank 1; characters rank skills up by learning them",
+            ));
+        }
+        let rolls = skill.effects().iter().any(|effect| match effect {
+            Effect::Damage { formula, .. } => formula.rolls(),
+            Effect::Heal { amount, .. } => amount.rolls(),
+            _ => false,
+        });
+        if skill.power == 0 && rolls {
+            report.push(Issue::error(
+                file,
+                format!("skills[{}].power", i),
+                format!(
+                    "skill {} has zero power; damage rolls need a power above 0",
+                    skill.id
+                ),
+            ));
+        }
+        for (j, effect) in skill.effects.iter().enumerate() {
+            validate_effect(
+                file,
+                format!("skills[{}].effects[{}]", i, j),
+                effect,
+                report,
+            );
+        }
+    }
+}
+
+fn validate_effect(file: &str, path: String, effect: &Effect, report: &mut ValidationReport) {
+    match effect {
+        Effect::Damage { hits: 0, .. } => {
+            report.push(Issue::error(file, path, "hits must be at least 1"));
+        }
+        Effect::ApplyStatus { status, chance, .. } => {
+            check_name(file, format!("{}.status.name", path), &status.name, report);
+            if status.turns == 0 {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.status.turns", path),
+                    "a status must last at least 1 turn",
+                ));
+            }
+            if status.modifier.hp != 0 {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.status.modifier.hp", path),
+                    "hp in a modifier is taken once and given back; use per_turn to drain or restore HP",
+                ));
+            }
+            if *chance == 0 || *chance > 100 {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.chance", path),
+                    format!("chance {} is not between 1 and 100 percent", chance),
+                ));
+            }
+        }
+        Effect::Knockback { turns: 0 } | Effect::Summon { count: 0, .. } => {
+            report.push(Issue::warning(file, path, "the effect does nothing at 0"));
+        }
+        _ => {}
+    }
+}
+
+/// Check that every skill that summons calls up an NPC that exists.
+fn validate_summons(file: &str, skills: &[Skill], npc_ids: &[u32], report: &mut ValidationReport) {
+    for (i, skill) in skills.iter().enumerate() {
+        for (j, effect) in skill.effects.iter().enumerate() {
+            if let Effect::Summon { npc, .. } = effect {
+                if !npc_ids.contains(npc) {
+                    report.push(Issue::error(
+                        file,
+                        format!("skills[{}].effects[{}].npc", i, j),
+                        format!("unknown NPC id {}", npc),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn validate_items(file: &str, items: &[Item], report: &mut ValidationReport) {
+    if items.is_empty() {
+        report.push(Issue::error(
+            file,
+            "items",
+            "no items defined; enemy item drops need at least one item",
+        ));
+    }
+    check_duplicate_ids(file, "items", items.iter().map(|item| item.id), report);
+
+    for (i, item) in items.iter().enumerate() {
+        check_name(file, format!("items[{}].name", i), &item.name, report);
+        if item.is_consumable() && item.stat_modifier == Stats::new() {
+            report.push(Issue::warning(
+                file,
+                format!("items[{}].stat_modifier", i),
+                format!("consumable {} has no effect", item.id),
+            ));
+        }
+    }
+}
+
+/// Checks that only make sense for the base entity list, which has to stand
+/// on its own.
+fn validate_roster(file: &str, entities: &[RawEntity], report: &mut ValidationReport) {
+    match entities.first() {
+        None => report.push(Issue::error(
+            file,
+            "entities",
+            "no entities defined; entities[0] is required as the player template",
+        )),
+        Some(template) if template.faction.is_some() => report.push(Issue::error(
+            file,
+            "entities[0].faction",
+            "entities[0] is the player template but has a faction, which makes it an NPC",
+        )),
+        Some(_) => {}
+    }
+    if !entities.iter().any(|entity| entity.faction.is_some()) {
+        report.push(Issue::error(
+            file,
+            "entities",
+            "no NPC entities (entities with a faction); enemies cannot be spawned",
+        ));
+    }
+}
+
+fn validate_entities(
+    file: &str,
+    entities: &[RawEntity],
+    items: Option<&[Item]>,
+    skills: Option<&[Skill]>,
+    factions: Option<&[Faction]>,
+    report: &mut ValidationReport,
+) {
+    check_duplicate_ids(file, "entities", entities.iter().map(|e| e.id), report);
+
+    for (i, entity) in entities.iter().enumerate() {
+        let path = format!("entities[{}]", i);
+        check_name(file, format!("{}.name", path), &entity.name, report);
+        validate_stats(file, &path, &entity.stats, report);
+
+        if let (Some(factions), Some(faction)) = (factions, &entity.faction) {
+            if !factions.iter().any(|f| &f.id == faction) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.faction", path),
+                    format!("faction '{}' not found", faction),
+                ));
+            }
+        }
+
+        if entity.skills.is_empty() {
+            report.push(Issue::error(
+                file,
+                format!("{}.skills", path),
+                format!("entity {} has no skills and cannot attack", entity.id),
+            ));
+        }
+        if let Some(skills) = skills {
+            for (j, skill_id) in entity.skills.iter().enumerate() {
+                if !skills.iter().any(|skill| skill.id == *skill_id) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.skills[{}]", path, j),
+                        format!("skill with id {} not found", skill_id),
+                    ));
+                }
+            }
+        }
+        if let Some(items) = items {
+            for (j, item_id) in entity.inventory.iter().enumerate() {
+                if !items.iter().any(|item| item.id == *item_id) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.inventory[{}]", path, j),
+                        format!("item with id {} not found", item_id),
+                    ));
+                }
+            }
+            validate_equipment(file, &path, entity, items, report);
+        }
+    }
+}
+
+fn validate_stats(file: &str, path: &str, stats: &Stats, report: &mut ValidationReport) {
+    let fields = [
+        ("hp", stats.hp),
+        ("attack", stats.attack),
+        ("defense", stats.defense),
+        ("agility", stats.agility),
+    ];
+    for (name, value) in fields {
+        if value < 0 {
+            report.push(Issue::error(
+                file,
+                format!("{}.stats.{}", path, name),
+                format!("{} must not be negative (found {})", name, value),
+            ));
+        }
+    }
+    if stats.hp == 0 {
+        report.push(Issue::error(
+            file,
+            format!("{}.stats.hp", path),
+            "hp is 0; the entity would start dead",
+        ));
+    }
+}
+
+fn validate_equipment(
+    file: &str,
+    path: &str,
+    entity: &RawEntity,
+    items: &[Item],
+    report: &mut ValidationReport,
+) {
+    let mut slots: Vec<ItemType> = Vec::new();
+    for (j, item_id) in entity.equipment.iter().enumerate() {
+        let item_path = format!("{}.equipment[{}]", path, j);
+        let item = match items.iter().find(|item| item.id == *item_id) {
+            Some(item) => item,
+            None => {
+                report.push(Issue::error(
+                    file,
+                    item_path,
+                    format!("item with id {} not found", item_id),
+                ));
+                continue;
+            }
+        };
+        if item.is_consumable() {
+            report.push(Issue::warning(
+                file,
+                item_path,
+                format!(
+                    "consumable {} cannot be equipped and stays in the inventory",
+                    item_id
+                ),
+            ));
+        } else if slots.contains(&item.item_type) {
+            report.push(Issue::warning(
+                file,
+                item_path,
+                format!(
+                    "{:?} slot already taken; item {} stays in the inventory",
+                    item.item_type, item_id
+                ),
+            ));
+        } else {
+            slots.push(item.item_type.clone());
+        }
+    }
+}
+
+fn validate_world(
+    file: &str,
+    world: &World,
+    npc_ids: Option<&[u32]>,
+    items: Option<&[Item]>,
+    themes: Option<&[DungeonTheme]>,
+    report: &mut ValidationReport,
+) {
+    if world.is_empty() {
+        return;
+    }
+    if world.room(&world.start).is_none() {
+        report.push(Issue::error(
+            file,
+            "start",
+            format!("start room '{}' not found", world.start),
+        ));
+    }
+
+    let mut zone_ids = HashSet::new();
+    let mut room_ids = HashSet::new();
+    for (i, zone) in world.zones.iter().enumerate() {
+        let zone_path = format!("zones[{}]", i);
+        check_name(file, format!("{}.name", zone_path), &zone.name, report);
+        if !zone_ids.insert(zone.id.as_str()) {
+            report.push(Issue::error(
+                file,
+                format!("{}.id", zone_path),
+                format!("duplicate zone id '{}'", zone.id),
+            ));
+        }
+        for (j, room) in zone.rooms.iter().enumerate() {
+            let path = format!("{}.rooms[{}]", zone_path, j);
+            check_name(file, format!("{}.name", path), &room.name, report);
+            if !room_ids.insert(room.id.as_str()) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.id", path),
+                    format!("duplicate room id '{}'", room.id),
+                ));
+            }
+            for (direction, target) in &room.exits {
+                if world.room(target).is_none() {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.exits.{}", path, direction),
+                        format!("exit leads to unknown room '{}'", target),
+                    ));
+                }
+            }
+            for (k, spawn) in room.spawns.iter().enumerate() {
+                let spawn_path = format!("{}.spawns[{}]", path, k);
+                if npc_ids.is_some_and(|ids| !ids.contains(&spawn.npc)) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.npc", spawn_path),
+                        format!("NPC with id {} not found", spawn.npc),
+                    ));
+                }
+                if spawn.min_level == 0 || spawn.min_level > spawn.max_level {
+                    report.push(Issue::error(
+                        file,
+                        spawn_path,
+                        format!(
+                            "invalid level range {}..={}",
+                            spawn.min_level, spawn.max_level
+                        ),
+                    ));
+                }
+            }
+            for (k, npc) in room.npcs.iter().enumerate() {
+                if npc_ids.is_some_and(|ids| !ids.contains(npc)) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.npcs[{}]", path, k),
+                        format!("NPC with id {} not found", npc),
+                    ));
+                }
+            }
+            if let (Some(themes), Some(theme)) = (themes, &room.dungeon) {
+                if !themes.iter().any(|t| &t.id == theme) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.dungeon", path),
+                        format!("dungeon theme '{}' not found", theme),
+                    ));
+                }
+            }
+            if !room.spawns.is_empty() && room.max_enemies == 0 {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.max_enemies", path),
+                    "max_enemies is 0; the spawn table is never used",
+                ));
+            }
+            if let Some(items) = items {
+                for (k, item_id) in room.items.iter().enumerate() {
+                    if !items.iter().any(|item| item.id == *item_id) {
+                        report.push(Issue::error(
+                            file,
+                            format!("{}.items[{}]", path, k),
+                            format!("item with id {} not found", item_id),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    // Rooms no path of exits leads to from the start room
+    let mut reachable = HashSet::new();
+    let mut pending = vec![world.start.as_str()];
+    while let Some(id) = pending.pop() {
+        if let Some((_, room)) = world.room(id) {
+            if reachable.insert(room.id.as_str()) {
+                pending.extend(room.exits.values().map(|target| target.as_str()));
+            }
+        }
+    }
+    for (i, zone) in world.zones.iter().enumerate() {
+        for (j, room) in zone.rooms.iter().enumerate() {
+            if !reachable.contains(room.id.as_str()) {
+                report.push(Issue::warning(
+                    file,
+                    format!("zones[{}].rooms[{}]", i, j),
+                    format!("room '{}' cannot be reached from the start room", room.id),
+                ));
+            }
+        }
+    }
+}
+
+fn validate_classes(
+    file: &str,
+    wrapper: &ClassesWrapper,
+    items: Option<&[Item]>,
+    skills: Option<&[Skill]>,
+    report: &mut ValidationReport,
+) {
+    if wrapper.classes.is_empty() != wrapper.races.is_empty() {
+        report.push(Issue::error(
+            file,
+            if wrapper.classes.is_empty() {
+                "classes"
+            } else {
+                "races"
+            },
+            "characters need a class and a race; define both or neither",
+        ));
+    }
+    for (list, archetypes) in [("classes", &wrapper.classes), ("races", &wrapper.races)] {
+        check_duplicate_ids(file, list, archetypes.iter().map(|a| a.id), report);
+        for (i, archetype) in archetypes.iter().enumerate() {
+            validate_archetype(
+                file,
+                &format!("{}[{}]", list, i),
+                archetype,
+                items,
+                skills,
+                report,
+            );
+        }
+    }
+    validate_point_buy(file, &wrapper.point_buy, report);
+    for (i, class) in wrapper.classes.iter().enumerate() {
+        validate_stats(file, &format!("classes[{}]", i), &class.stats, report);
+        if class.skills.is_empty() {
+            report.push(Issue::warning(
+                file,
+                format!("classes[{}].skills", i),
+                "no skills; only races that teach one can attack",
+            ));
+        }
+    }
+}
+
+fn validate_skill_trees(
+    file: &str,
+    trees: &[SkillTree],
+    skills: Option<&[Skill]>,
+    classes: Option<&[Archetype]>,
+    report: &mut ValidationReport,
+) {
+    let mut tree_ids = HashSet::new();
+    let mut taught: HashMap<u32, String> = HashMap::new();
+    for (i, tree) in trees.iter().enumerate() {
+        let path = format!("trees[{}]", i);
+        if !tree_ids.insert(tree.id.as_str()) {
+            report.push(Issue::error(
+                file,
+                format!("{}.id", path),
+                format!("duplicate skill tree id '{}'", tree.id),
+            ));
+        }
+        check_name(file, format!("{}.name", path), &tree.name, report);
+        if let Some(classes) = classes {
+            for (j, class) in tree.classes.iter().enumerate() {
+                if !classes.iter().any(|c| c.id == *class) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.classes[{}]", path, j),
+                        format!("class with id {} not found", class),
+                    ));
+                }
+            }
+        }
+        for (j, node) in tree.nodes.iter().enumerate() {
+            let node_path = format!("{}.nodes[{}]", path, j);
+            if let Some(first) = taught.insert(node.skill, node_path.clone()) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.skill", node_path),
+                    format!(
+                        "skill {} is already taught at {}; learn could not tell them apart",
+                        node.skill, first
+                    ),
+                ));
+            }
+            if node.cost == 0 {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.cost", node_path),
+                    "costs no skill points, so every rank is free",
+                ));
+            }
+            let Some(skills) = skills else {
+                continue;
+            };
+            let ids = std::iter::once(("skill".to_string(), node.skill)).chain(
+                node.requires
+                    .iter()
+                    .enumerate()
+                    .map(|(k, id)| (format!("requires[{}]", k), *id)),
+            );
+            for (field, id) in ids {
+                if !skills.iter().any(|skill| skill.id == id) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.{}", node_path, field),
+                        format!("skill with id {} not found", id),
+                    ));
+                }
+            }
+            if node.requires.contains(&node.skill) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.requires", node_path),
+                    "a skill cannot require itself",
+                ));
+            }
+        }
+    }
+}
+
+fn validate_point_buy(file: &str, rules: &PointBuy, report: &mut ValidationReport) {
+    for name in ["hp", "attack", "defense", "agility"] {
+        let [min, max, per_point] =
+            [rules.min, rules.max, rules.per_point].map(|stats| stats.get(name).unwrap_or(0));
+        if min < 0 {
+            report.push(Issue::error(
+                file,
+                format!("point_buy.min.{}", name),
+                format!("{} must not be negative (found {})", name, min),
+            ));
+        }
+        if max < min {
+            report.push(Issue::error(
+                file,
+                format!("point_buy.max.{}", name),
+                format!("the maximum {} is below the minimum {}", max, min),
+            ));
+        }
+        if per_point <= 0 {
+            report.push(Issue::warning(
+                file,
+                format!("point_buy.per_point.{}", name),
+                format!("points spent on {} do nothing", name),
+            ));
+        }
+    }
+    if rules.min.hp == 0 {
+        report.push(Issue::error(
+            file,
+            "point_buy.min.hp",
+            "hp is 0; characters buying none would start dead",
+        ));
+    }
+}
+
+fn validate_archetype(
+    file: &str,
+    path: &str,
+    archetype: &Archetype,
+    items: Option<&[Item]>,
+    skills: Option<&[Skill]>,
+    report: &mut ValidationReport,
+) {
+    check_name(file, format!("{}.name", path), &archetype.name, report);
+    if let Some(skills) = skills {
+        for (j, skill_id) in archetype.skills.iter().enumerate() {
+            if !skills.iter().any(|skill| skill.id == *skill_id) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.skills[{}]", path, j),
+                    format!("skill with id {} not found", skill_id),
+                ));
+            }
+        }
+    }
+    let Some(items) = items else {
+        return;
+    };
+    for (j, item_id) in archetype.items.iter().enumerate() {
+        match items.iter().find(|item| item.id == *item_id) {
+            None => report.push(Issue::error(
+                file,
+                format!("{}.items[{}]", path, j),
+                format!("item with id {} not found", item_id),
+            )),
+            Some(item)
+                if item
+                    .tags
+                    .iter()
+                    .any(|tag| archetype.cannot_equip.contains(tag)) =>
+            {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.items[{}]", path, j),
+                    format!(
+                        "{} starts with {} but cannot equip it",
+                        archetype.name, item.name
+                    ),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+    for (j, tag) in archetype.cannot_equip.iter().enumerate() {
+        if !items.iter().any(|item| item.tags.contains(tag)) {
+            report.push(Issue::warning(
+                file,
+                format!("{}.cannot_equip[{}]", path, j),
+                format!("no item has the tag '{}'", tag),
+            ));
+        }
+    }
+}
+
+fn validate_factions(file: &str, factions: &[Faction], report: &mut ValidationReport) {
+    let mut ids = HashSet::new();
+    for (i, faction) in factions.iter().enumerate() {
+        let path = format!("factions[{}]", i);
+        if !ids.insert(faction.id.as_str()) {
+            report.push(Issue::error(
+                file,
+                format!("{}.id", path),
+                format!("duplicate faction id '{}'", faction.id),
+            ));
+        }
+        check_name(file, format!("{}.name", path), &faction.name, report);
+        if faction.reputation.abs() > MAX_REPUTATION {
+            report.push(Issue::error(
+                file,
+                format!("{}.reputation", path),
+                format!("must be between -{0} and {0}", MAX_REPUTATION),
+            ));
+        }
+        if faction.kill_penalty < 0 || faction.help_bonus < 0 {
+            report.push(Issue::warning(
+                file,
+                path.clone(),
+                "a negative kill_penalty or help_bonus turns the reputation change around",
+            ));
+        }
+        for (other, relation) in &faction.relations {
+            let relation_path = format!("{}.relations.{}", path, other);
+            if other == &faction.id {
+                report.push(Issue::error(
+                    file,
+                    relation_path,
+                    "a faction cannot have a relation with itself",
+                ));
+            } else if !factions.iter().any(|f| &f.id == other) {
+                report.push(Issue::error(
+                    file,
+                    relation_path,
+                    format!("faction '{}' not found", other),
+                ));
+            } else if relation.abs() > MAX_REPUTATION {
+                report.push(Issue::error(
+                    file,
+                    relation_path,
+                    format!("must be between -{0} and {0}", MAX_REPUTATION),
+                ));
+            }
+        }
+    }
+}
+
+fn validate_dungeons(
+    file: &str,
+    themes: &[DungeonTheme],
+    npc_ids: Option<&[u32]>,
+    items: Option<&[Item]>,
+    report: &mut ValidationReport,
+) {
+    let mut ids = HashSet::new();
+    for (i, theme) in themes.iter().enumerate() {
+        let path = format!("themes[{}]", i);
+        check_name(file, format!("{}.name", path), &theme.name, report);
+        if !ids.insert(theme.id.as_str()) {
+            report.push(Issue::error(
+                file,
+                format!("{}.id", path),
+                format!("duplicate theme id '{}'", theme.id),
+            ));
+        }
+        if theme.floors == 0 {
+            report.push(Issue::error(
+                file,
+                format!("{}.floors", path),
+                "a dungeon needs at least one floor",
+            ));
+        }
+        if theme.min_rooms < 2 || theme.min_rooms > theme.max_rooms {
+            report.push(Issue::error(
+                file,
+                format!("{}.min_rooms", path),
+                format!(
+                    "invalid room range {}..={}; a floor needs at least 2 rooms",
+                    theme.min_rooms, theme.max_rooms
+                ),
+            ));
+        }
+        if theme.boss_level == 0 {
+            report.push(Issue::error(
+                file,
+                format!("{}.boss_level", path),
+                "levels start at 1",
+            ));
+        }
+        if theme.enemies.is_empty() {
+            report.push(Issue::warning(
+                file,
+                format!("{}.enemies", path),
+                "no enemies; only the boss will fight back",
+            ));
+        }
+        if let Some(npc_ids) = npc_ids {
+            let npcs = theme
+                .enemies
+                .iter()
+                .enumerate()
+                .map(|(j, npc)| (format!("{}.enemies[{}]", path, j), *npc))
+                .chain(std::iter::once((format!("{}.boss", path), theme.boss)));
+            for (npc_path, npc) in npcs {
+                if !npc_ids.contains(&npc) {
+                    report.push(Issue::error(
+                        file,
+                        npc_path,
+                        format!("NPC with id {} not found", npc),
+                    ));
+                }
+            }
+        }
+        if let Some(items) = items {
+            for (j, item_id) in theme.treasure.iter().enumerate() {
+                if !items.iter().any(|item| item.id == *item_id) {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.treasure[{}]", path, j),
+                        format!("item with id {} not found", item_id),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn validate_quests(
+    file: &str,
+    quests: &[Quest],
+    npc_ids: Option<&[u32]>,
+    items: Option<&[Item]>,
+    world: Option<&World>,
+    report: &mut ValidationReport,
+) {
+    check_duplicate_ids(file, "quests", quests.iter().map(|q| q.id), report);
+    let item_exists = |id: u32| items.is_none_or(|items| items.iter().any(|i| i.id == id));
+    let npc_exists = |id: u32| npc_ids.is_none_or(|ids| ids.contains(&id));
+
+    for (i, quest) in quests.iter().enumerate() {
+        let path = format!("quests[{}]", i);
+        check_name(file, format!("{}.name", path), &quest.name, report);
+        if let Some(giver) = quest.giver.filter(|id| !npc_exists(*id)) {
+            report.push(Issue::error(
+                file,
+                format!("{}.giver", path),
+                format!("NPC with id {} not found", giver),
+            ));
+        }
+        for (j, prerequisite) in quest.prerequisites.iter().enumerate() {
+            let message = if *prerequisite == quest.id {
+                "a quest cannot require itself".to_string()
+            } else if !quests.iter().any(|q| q.id == *prerequisite) {
+                format!("quest with id {} not found", prerequisite)
+            } else {
+                continue;
+            };
+            report.push(Issue::error(
+                file,
+                format!("{}.prerequisites[{}]", path, j),
+                message,
+            ));
+        }
+        if quest.objectives.is_empty() {
+            report.push(Issue::warning(
+                file,
+                format!("{}.objectives", path),
+                "no objectives; the quest completes as soon as it starts",
+            ));
+        }
+        for (j, objective) in quest.objectives.iter().enumerate() {
+            let objective_path = format!("{}.objectives[{}]", path, j);
+            let problem = match objective {
+                Objective::Kill { count: 0, .. } | Objective::Collect { count: 0, .. } => {
+                    Some("count must be at least 1".to_string())
+                }
+                Objective::Kill { npc, .. } | Objective::Talk { npc } if !npc_exists(*npc) => {
+                    Some(format!("NPC with id {} not found", npc))
+                }
+                Objective::Collect { item, .. } if !item_exists(*item) => {
+                    Some(format!("item with id {} not found", item))
+                }
+                Objective::Reach { room }
+                    if world.is_some_and(|world| world.room(room).is_none()) =>
+                {
+                    Some(format!("room '{}' not found in the world", room))
+                }
+                _ => None,
+            };
+            if let Some(problem) = problem {
+                report.push(Issue::error(file, objective_path, problem));
+            }
+        }
+        for (j, item_id) in quest.rewards.items.iter().enumerate() {
+            if !item_exists(*item_id) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.rewards.items[{}]", path, j),
+                    format!("item with id {} not found", item_id),
+                ));
+            }
+        }
+    }
+}
+
+fn validate_dialogue(
+    file: &str,
+    dialogues: &[Dialogue],
+    npc_ids: Option<&[u32]>,
+    items: Option<&[Item]>,
+    quests: Option<&[Quest]>,
+    factions: Option<&[Faction]>,
+    report: &mut ValidationReport,
+) {
+    let item_exists = |id: u32| items.is_none_or(|items| items.iter().any(|i| i.id == id));
+    let quest_exists = |id: u32| quests.is_none_or(|quests| quests.iter().any(|q| q.id == id));
+    let mut npcs = HashSet::new();
+
+    for (i, dialogue) in dialogues.iter().enumerate() {
+        let path = format!("dialogues[{}]", i);
+        if npc_ids.is_some_and(|ids| !ids.contains(&dialogue.npc)) {
+            report.push(Issue::error(
+                file,
+                format!("{}.npc", path),
+                format!("NPC with id {} not found", dialogue.npc),
+            ));
+        }
+        if !npcs.insert(dialogue.npc) {
+            report.push(Issue::error(
+                file,
+                format!("{}.npc", path),
+                format!("NPC {} already has a dialogue", dialogue.npc),
+            ));
+        }
+        let mut node_ids = HashSet::new();
+        for (j, node) in dialogue.nodes.iter().enumerate() {
+            if !node_ids.insert(node.id.as_str()) {
+                report.push(Issue::error(
+                    file,
+                    format!("{}.nodes[{}].id", path, j),
+                    format!("duplicate node id '{}'", node.id),
+                ));
+            }
+        }
+        if dialogue.node(&dialogue.start).is_none() {
+            report.push(Issue::error(
+                file,
+                format!("{}.start", path),
+                format!("start node '{}' not found", dialogue.start),
+            ));
+        }
+
+        for (j, node) in dialogue.nodes.iter().enumerate() {
+            let node_path = format!("{}.nodes[{}]", path, j);
+            for (k, action) in node.actions.iter().enumerate() {
+                let problem = match action {
+                    Action::GiveItem { item } if !item_exists(*item) => {
+                        Some(format!("item with id {} not found", item))
+                    }
+                    Action::StartQuest { quest } if !quest_exists(*quest) => {
+                        Some(format!("quest with id {} not found", quest))
+                    }
+                    Action::OpenShop { stock } => stock
+                        .iter()
+                        .find(|stock| !item_exists(stock.item))
+                        .map(|stock| format!("item with id {} not found", stock.item)),
+                    _ => None,
+                };
+                if let Some(problem) = problem {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.actions[{}]", node_path, k),
+                        problem,
+                    ));
+                }
+            }
+            for (k, choice) in node.choices.iter().enumerate() {
+                let choice_path = format!("{}.choices[{}]", node_path, k);
+                if let Some(next) = choice
+                    .next
+                    .as_ref()
+                    .filter(|next| dialogue.node(next).is_none())
+                {
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.next", choice_path),
+                        format!("links to unknown node '{}'", next),
+                    ));
+                }
+                for (l, condition) in choice.conditions.iter().enumerate() {
+                    let problem = match condition {
+                        Condition::QuestNotStarted { quest }
+                        | Condition::QuestActive { quest }
+                        | Condition::QuestCompleted { quest }
+                            if !quest_exists(*quest) =>
+                        {
+                            format!("quest with id {} not found", quest)
+                        }
+                        Condition::HasItem { item, .. } if !item_exists(*item) => {
+                            format!("item with id {} not found", item)
+                        }
+                        Condition::Stat { stat, .. } if Stats::new().get(stat).is_none() => {
+                            format!("unknown stat '{}'", stat)
+                        }
+                        Condition::Reputation { faction, .. }
+                            if factions.is_some_and(|factions| {
+                                !factions.iter().any(|f| &f.id == faction)
+                            }) =>
+                        {
+                            format!("faction '{}' not found", faction)
+                        }
+                        _ => continue,
+                    };
+                    report.push(Issue::error(
+                        file,
+                        format!("{}.conditions[{}]", choice_path, l),
+                        problem,
+                    ));
+                }
+            }
+        }
+
+        // Nodes no chain of choices leads to from the start node
+        let mut reached = HashSet::new();
+        let mut queue = VecDeque::from([dialogue.start.as_str()]);
+        while let Some(id) = queue.pop_front() {
+            let Some(node) = dialogue.node(id) else {
+                continue;
+            };
+            if !reached.insert(id) {
+                continue;
+            }
+            queue.extend(node.choices.iter().filter_map(|c| c.next.as_deref()));
+        }
+        for (j, node) in dialogue.nodes.iter().enumerate() {
+            if !reached.contains(node.id.as_str()) {
+                report.push(Issue::warning(
+                    file,
+                    format!("{}.nodes[{}]", path, j),
+                    format!("node '{}' can never be reached", node.id),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+    use serde_json::{json, Value};
+
+    /// The smallest content that validates cleanly: a template, one NPC,
+    /// one skill and one item.
+    fn clean() -> [Value; 4] {
+        [
+            json!({ "entities": [
+                { "id": 1, "name": "Hero", "stats": { "hp": 50, "attack": 5, "defense": 2, "agility": 3 },
+                  "inventory": [], "equipment": [], "skills": [10] },
+                { "id": 2, "name": "Rat", "faction": "vermin",
+                  "stats": { "hp": 10, "attack": 2, "defense": 0, "agility": 2 },
+                  "inventory": [], "equipment": [], "skills": [10] }
+            ] }),
+            json!({ "items": [
+                { "id": 100, "name": "Potion", "description": "", "item_type": "Consumable",
+                  "stat_modifier": { "hp": 10, "attack": 0, "defense": 0, "agility": 0 } }
+            ] }),
+            json!({ "skills": [
+                { "id": 10, "name": "Bite", "description": "", "power": 3, "cost": 0,
+                  "skill_type": "Active", "skill_target": "SingleTarget", "skill_class": "Physical" }
+            ] }),
+            json!({ "factions": [{ "id": "vermin", "name": "Vermin" }] }),
+        ]
+    }
+
+    /// Validate `content` written to a temp dir named `name`.
+    fn validate(name: &str, [entities, items, skills, factions]: [Value; 4]) -> Vec<Issue> {
+        let dir = fixtures::temp_dir(&format!("validation-{}", name));
+        let path = |file: &str| dir.join(file).to_string_lossy().into_owned();
+        for (file, value) in [
+            ("entities.json", entities),
+            ("items.json", items),
+            ("skills.json", skills),
+            ("factions.json", factions),
+        ] {
+            fs::write(path(file), value.to_string()).unwrap();
+        }
+        let report = validate_assets(
+            &AssetFiles {
+                entities: &path("entities.json"),
+                items: &path("items.json"),
+                skills: &path("skills.json"),
+                world: &path("world.json"),
+                dungeons: &path("dungeons.json"),
+                quests: &path("quests.json"),
+                dialogue: &path("dialogue.json"),
+                factions: &path("factions.json"),
+                classes: &path("classes.json"),
+                skill_trees: &path("skill_trees.json"),
+            },
+            &path("packs"),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        report.issues
+    }
+
+    /// Assert that `issues` holds exactly one issue, at `path` with `severity`.
+    fn assert_single(issues: &[Issue], severity: Severity, path: &str) {
+        assert_eq!(issues.len(), 1, "{:?}", issues);
+        assert_eq!(issues[0].severity, severity);
+        assert_eq!(issues[0].path, path);
+    }
+
+    #[test]
+    fn clean_content_has_no_issues() {
+        let issues = validate("clean", clean());
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn duplicate_ids_are_errors() {
+        let mut content = clean();
+        let skill = content[2]["skills"][0].clone();
+        content[2]["skills"].as_array_mut().unwrap().push(skill);
+        let issues = validate("duplicate", content);
+        assert_single(&issues, Severity::Error, "skills[1].id");
+        assert!(issues[0].message.contains("duplicate id 10"));
+    }
+
+    #[test]
+    fn negative_stats_are_errors() {
+        let mut content = clean();
+        content[0]["entities"][1]["stats"]["defense"] = json!(-1);
+        let issues = validate("negative", content);
+        assert_single(&issues, Severity::Error, "entities[1].stats.defense");
+    }
+
+    #[test]
+    fn zero_power_rolling_skills_are_errors() {
+        let mut content = clean();
+        content[2]["skills"][0]["power"] = json!(0);
+        let issues = validate("zero-power", content);
+        assert_single(&issues, Severity::Error, "skills[0].power");
+    }
+
+    #[test]
+    fn content_without_npcs_is_an_error() {
+        let mut content = clean();
+        content[0]["entities"].as_array_mut().unwrap().pop();
+        let issues = validate("no-npcs", content);
+        assert_single(&issues, Severity::Error, "entities");
+        assert!(issues[0].message.contains("no NPC entities"));
+    }
+
+    #[test]
+    fn the_player_template_must_not_be_an_npc() {
+        let mut content = clean();
+        content[0]["entities"][0]["faction"] = json!("vermin");
+        let issues = validate("template", content);
+        assert_single(&issues, Severity::Error, "entities[0].faction");
+    }
+
+    #[test]
+    fn consumables_without_an_effect_are_warnings() {
+        let mut content = clean();
+        content[1]["items"][0]["stat_modifier"]["hp"] = json!(0);
+        let issues = validate("no-effect", content);
+        assert_single(&issues, Severity::Warning, "items[0].stat_modifier");
+    }
+
+    #[test]
+    fn unknown_references_are_errors() {
+        let mut content = clean();
+        content[0]["entities"][1]["skills"] = json!([99]);
+        content[0]["entities"][1]["faction"] = json!("pirates");
+        let issues = validate("references", content);
+        let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+        assert_eq!(paths, ["entities[1].faction", "entities[1].skills[0]"]);
+    }
+}
@@ -0,0 +1,411 @@
+// src/game/world.rs
+use crate::game::combat;
+use crate::game::command::resolve_index;
+use crate::game::console::Console;
+use crate::game::dungeon;
+use crate::game::error::{GameError, GameResult};
+use crate::game::faction;
+use crate::game::item::Item;
+use crate::game::log::say;
+use crate::game::persistence::GameState;
+use crate::game::quest::{self, QuestEvent};
+use crate::game::rng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Zones and rooms the player can walk through, from `world.json`.
+/// An empty world means the game is a single endless arena.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct World {
+    /// Room new characters start in and revived characters wake up in.
+    #[serde(default)]
+    pub start: String,
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub name: String,
+    pub rooms: Vec<Room>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Room {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Direction to room id, e.g. `"north": "forest_edge"`.
+    #[serde(default)]
+    pub exits: BTreeMap<String, String>,
+    /// NPCs that may be here when the player walks in, if their faction is
+    /// hostile to the player. No spawns makes the room safe.
+    #[serde(default)]
+    pub spawns: Vec<Spawn>,
+    /// Most enemies spawned at once.
+    #[serde(default = "default_max_enemies")]
+    pub max_enemies: u32,
+    /// Item ids lying on the floor the first time the room is visited.
+    #[serde(default)]
+    pub items: Vec<u32>,
+    /// Ids of NPCs who are always here: to talk to, or to fight once their
+    /// faction turns hostile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub npcs: Vec<u32>,
+    /// Id of the dungeon theme whose entrance is in this room.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dungeon: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spawn {
+    pub npc: u32,
+    #[serde(default = "first_level")]
+    pub min_level: u32,
+    #[serde(default = "first_level")]
+    pub max_level: u32,
+}
+
+fn default_max_enemies() -> u32 {
+    2
+}
+
+fn first_level() -> u32 {
+    1
+}
+
+/// Where a character is and what it has changed on the way.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Location {
+    pub room: String,
+    /// Rooms in the order they were first entered.
+    pub visited: Vec<String>,
+    /// Item ids still on the floor of each visited room.
+    pub floor: BTreeMap<String, Vec<u32>>,
+}
+
+impl World {
+    pub fn is_empty(&self) -> bool {
+        self.zones.is_empty()
+    }
+
+    pub fn rooms(&self) -> impl Iterator<Item = (&Zone, &Room)> {
+        self.zones
+            .iter()
+            .flat_map(|zone| zone.rooms.iter().map(move |room| (zone, room)))
+    }
+
+    pub fn room(&self, id: &str) -> Option<(&Zone, &Room)> {
+        self.rooms().find(|(_, room)| room.id == id)
+    }
+}
+
+impl Location {
+    /// Whether this is the location of an NPC, or of a character that has
+    /// not set foot in the world yet.
+    pub fn is_nowhere(&self) -> bool {
+        self.room.is_empty() && self.visited.is_empty()
+    }
+
+    pub fn has_visited(&self, room: &str) -> bool {
+        self.visited.iter().any(|visited| visited == room)
+    }
+}
+
+/// A room of the world map or of the dungeon floor the player is on.
+pub fn find_room<'a>(state: &'a GameState, id: &str) -> Option<(&'a Zone, &'a Room)> {
+    state
+        .dungeon()
+        .and_then(|run| run.room(id))
+        .or_else(|| state.world.room(id))
+}
+
+/// The zone and room the player is in.
+pub fn current_room(state: &GameState) -> GameResult<(&Zone, &Room)> {
+    if state.world.is_empty() {
+        return Err(GameError::InvalidState(
+            "There is no world to explore; the game is an arena.".into(),
+        ));
+    }
+    let room = &state.location()?.room;
+    find_room(state, room)
+        .ok_or_else(|| GameError::InvalidState(format!("You are lost: no room '{}'.", room)))
+}
+
+/// Move the player into room `id`. The first visit puts the room's items on
+/// the floor; every visit brings in fresh enemies from its spawn table.
+pub fn enter_room(state: &mut GameState, id: &str) -> GameResult<()> {
+    let room = find_room(state, id)
+        .map(|(_, room)| room.clone())
+        .ok_or_else(|| GameError::Content(format!("no room with id '{}'", id)))?;
+    let location = state.location_mut()?;
+    if !location.has_visited(id) {
+        location.visited.push(id.to_string());
+        location.floor.insert(id.to_string(), room.items.clone());
+    }
+    location.room = id.to_string();
+    state.get_player_mut()?.conversation = None;
+    quest::notify(state, QuestEvent::Entered(id))?;
+
+    state.enemies.clear();
+    state.enemy_index = 0;
+    combat::end_encounter(state);
+    // People who have turned against the player are waiting for them
+    for id in &room.npcs {
+        let npc = state.npc(*id)?;
+        if faction::is_hostile(state, &npc) {
+            say!("{} attacks on sight!", npc.name);
+            state.create_enemy(npc);
+        }
+    }
+    let mut spawns = Vec::new();
+    for spawn in &room.spawns {
+        if faction::is_hostile(state, &state.npc(spawn.npc)?) {
+            spawns.push(spawn);
+        }
+    }
+    if spawns.is_empty() || room.max_enemies == 0 {
+        return Ok(());
+    }
+    let rolled = match state
+        .dungeon()
+        .and_then(|run| dungeon::spawn_generator(run, id))
+    {
+        Some(mut gen) => roll_spawns(&spawns, room.max_enemies, &mut gen),
+        None => rng::with(|gen| roll_spawns(&spawns, room.max_enemies, gen)),
+    };
+    for (npc, level) in rolled {
+        let mut enemy = state.npc(npc)?;
+        enemy.scale_to_level(level);
+        state.create_enemy(enemy);
+    }
+    Ok(())
+}
+
+/// Pick between one and `max_enemies` of `spawns` with `gen`. Returns the
+/// NPC id and level of each.
+pub fn roll_spawns(spawns: &[&Spawn], max_enemies: u32, gen: &mut impl Rng) -> Vec<(u32, u32)> {
+    (0..gen.gen_range(1..max_enemies + 1))
+        .map(|_| {
+            let spawn = spawns[gen.gen_range(0..spawns.len())];
+            let level = gen.gen_range(spawn.min_level..spawn.max_level.max(spawn.min_level) + 1);
+            (spawn.npc, level)
+        })
+        .collect()
+}
+
+fn floor_items(state: &GameState) -> GameResult<Vec<Item>> {
+    let location = state.location()?;
+    Ok(location
+        .floor
+        .get(&location.room)
+        .into_iter()
+        .flatten()
+        .filter_map(|id| state.get_item_by_id(*id))
+        .collect())
+}
+
+fn names<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    names.collect::<Vec<_>>().join(", ")
+}
+
+/// Describe the current room: exits, items on the floor and enemies.
+pub fn look(state: &GameState) -> GameResult<()> {
+    let (zone, room) = current_room(state)?;
+    say!("{} ({})\n{}", room.name, zone.name, room.description);
+    if room.exits.is_empty() {
+        say!("There is no way out.");
+    } else {
+        say!("Exits: {}", names(room.exits.keys().map(|s| s.as_str())));
+    }
+    if let Some(theme) = room
+        .dungeon
+        .as_ref()
+        .and_then(|id| state.dungeons.iter().find(|theme| &theme.id == id))
+    {
+        say!(
+            "A way down into {} is here. Type 'descend' to enter.",
+            theme.name
+        );
+    }
+    let people: Vec<String> = room
+        .npcs
+        .iter()
+        .filter_map(|id| state.npc(*id).ok())
+        .filter(|npc| !faction::is_hostile(state, npc))
+        .map(|npc| npc.name)
+        .collect();
+    if !people.is_empty() {
+        say!("People here: {}", people.join(", "));
+    }
+    let items = floor_items(state)?;
+    if !items.is_empty() {
+        say!(
+            "On the floor: {}",
+            names(items.iter().map(|item| item.name.as_str()))
+        );
+    }
+    if state.enemies.is_empty() {
+        say!("Nothing hostile in sight.");
+    } else {
+        let enemies: Vec<String> = state
+            .enemies
+            .iter()
+            .map(|enemy| format!("{} (level {})", enemy.name, enemy.level))
+            .collect();
+        say!("Enemies: {}", enemies.join(", "));
+    }
+    Ok(())
+}
+
+/// The exit of the current room named `direction`, or the start of it.
+fn find_exit(room: &Room, direction: &str) -> GameResult<(String, String)> {
+    let lower = direction.trim().to_lowercase();
+    let exact = room
+        .exits
+        .iter()
+        .find(|(dir, _)| dir.to_lowercase() == lower);
+    let mut prefixed = room
+        .exits
+        .iter()
+        .filter(|(dir, _)| !lower.is_empty() && dir.to_lowercase().starts_with(&lower));
+    let exit = match (exact, prefixed.next(), prefixed.next()) {
+        (Some(exit), _, _) | (None, Some(exit), None) => exit,
+        (None, Some(_), Some(_)) => {
+            return Err(GameError::InvalidInput(format!(
+                "'{}' could be several exits. Exits: {}",
+                direction,
+                names(room.exits.keys().map(|s| s.as_str()))
+            )))
+        }
+        (None, None, _) => {
+            return Err(GameError::NotFound(format!(
+                "no exit '{}' here. Exits: {}",
+                direction,
+                names(room.exits.keys().map(|s| s.as_str()))
+            )))
+        }
+    };
+    Ok((exit.0.clone(), exit.1.clone()))
+}
+
+/// A random exit of the current room, for running away.
+pub fn random_exit(state: &GameState) -> GameResult<String> {
+    let (_, room) = current_room(state)?;
+    if room.exits.is_empty() {
+        return Err(GameError::InvalidState(
+            "There is no way out of here.".into(),
+        ));
+    }
+    let index = rng::gen_range(0..room.exits.len());
+    Ok(room.exits.keys().nth(index).cloned().unwrap_or_default())
+}
+
+/// Leave through an exit of the current room. An enemy in the room gets
+/// one last hit in first.
+pub fn go(state: &mut GameState, direction: &str) -> GameResult<()> {
+    let (direction, _) = find_exit(current_room(state)?.1, direction)?;
+    if parting_hit(state)? {
+        walk(state, &direction)?;
+    }
+    Ok(())
+}
+
+/// Let an enemy in the room hit the player, who is leaving. Returns whether
+/// the player survived.
+pub fn parting_hit(state: &mut GameState) -> GameResult<bool> {
+    if state.is_enemy_alive() {
+        let (player, enemy) = state.get_combatants()?;
+        let summons = combat::combat_round(enemy, player)?;
+        combat::place(state, summons, false)?;
+    }
+    Ok(state.is_player_alive())
+}
+
+/// Walk through an exit of the current room and look around.
+pub fn walk(state: &mut GameState, direction: &str) -> GameResult<()> {
+    let (direction, target) = find_exit(current_room(state)?.1, direction)?;
+    say!("You go {}.", direction);
+    enter_room(state, &target)?;
+    look(state)
+}
+
+/// The visited rooms of the current zone and where their exits lead.
+/// Rooms not visited yet show up as `???`.
+pub fn map(state: &GameState) -> GameResult<()> {
+    let (zone, current) = current_room(state)?;
+    let location = state.location()?;
+    let mut text = format!("Map of {}:", zone.name);
+    for room in &zone.rooms {
+        if !location.has_visited(&room.id) {
+            continue;
+        }
+        let marker = if room.id == current.id { "*" } else { " " };
+        text.push_str(&format!("\n{} {}", marker, room.name));
+        for (direction, target) in &room.exits {
+            let name = match find_room(state, target) {
+                Some((_, target)) if location.has_visited(&target.id) => target.name.as_str(),
+                _ => "???",
+            };
+            text.push_str(&format!("\n      {}: {}", direction, name));
+        }
+    }
+    say!("{}\n(* you are here)", text);
+    Ok(())
+}
+
+/// Pick an item up from the floor of the current room.
+pub fn take(
+    state: &mut GameState,
+    console: &mut Console,
+    item_ref: Option<&str>,
+) -> GameResult<()> {
+    current_room(state)?;
+    state.get_player()?;
+    let items = floor_items(state)?;
+    if items.is_empty() {
+        return Err(GameError::NotFound("there is nothing here to take".into()));
+    }
+    let item_ref = match item_ref {
+        Some(item_ref) => item_ref.to_string(),
+        None => {
+            console.require_interactive("item")?;
+            say!(
+                "On the floor: {}",
+                names(items.iter().map(|item| item.name.as_str()))
+            );
+            console.ask("Enter item to take: ")?
+        }
+    };
+    let item = items[resolve_index(&items, &item_ref, "item on the floor")?].clone();
+    let location = state.location_mut()?;
+    let floor = location.floor.entry(location.room.clone()).or_default();
+    if let Some(position) = floor.iter().position(|id| *id == item.id) {
+        floor.remove(position);
+    }
+    say!("You pick up {}.", item.name);
+    state.get_player_mut()?.add_item_to_inventory(item);
+    quest::notify(state, QuestEvent::PickedUp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+
+    #[test]
+    fn characters_keep_their_own_location() {
+        let mut state = fixtures::village();
+        assert_eq!(state.location().unwrap().room, "square");
+        walk(&mut state, "north").unwrap();
+
+        state.set_player(1).unwrap();
+        assert_eq!(state.location().unwrap().room, "square");
+        assert!(!state.location().unwrap().has_visited("woods"));
+
+        state.set_player(0).unwrap();
+        assert_eq!(state.location().unwrap().room, "woods");
+    }
+}
@@ -1,175 +1,700 @@
 mod cli;
 mod game;
+#[cfg(feature = "tui")]
+mod tui;
 
-use crate::game::persistence::GameState;
-use std::io::{self, Write};
+use crate::game::character::{self, PointBuy};
+use crate::game::command::{self, GAME_COMMANDS, MENU_COMMANDS};
+use crate::game::config::Config;
+use crate::game::console::Console;
+use crate::game::content::Content;
+use crate::game::death::{self, DeathOutcome};
+use crate::game::error::{GameError, GameResult};
+use crate::game::history::History;
+use crate::game::persistence::{GameState, SaveFormat};
+use crate::game::replay::{RecordedCommand, Replay};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
 
 // CONSTANTS
-const SAVE_FILE: &str = "savegame.json";
-const ENTITIES_FILE: &str = "assets/entities.json";
-const ITEMS_FILE: &str = "assets/items.json";
-const SKILLS_FILE: &str = "assets/skills.json";
+/// Default save file name; the extension follows the configured save format.
+const SAVE_FILE_STEM: &str = "savegame";
+const EXPORT_FILE: &str = "savegame.export.json";
+const CONFIG_FILE: &str = "config.json";
+const ASSETS_DIR: &str = "assets";
+const ENTITIES_FILE: &str = "entities.json";
+const ITEMS_FILE: &str = "items.json";
+const SKILLS_FILE: &str = "skills.json";
+const WORLD_FILE: &str = "world.json";
+const DUNGEONS_FILE: &str = "dungeons.json";
+const QUESTS_FILE: &str = "quests.json";
+const DIALOGUE_FILE: &str = "dialogue.json";
+const FACTIONS_FILE: &str = "factions.json";
+const CLASSES_FILE: &str = "classes.json";
+const SKILL_TREES_FILE: &str = "skill_trees.json";
+const PACKS_DIR: &str = "packs";
 
-fn help() {
-    println!("Commands:");
-    println!("    1. start: Start game.");
-    println!("    2. cc: Create a new character.");
-    println!("    3. lc: Load an existing character.");
-    println!("    4. sc: Show all characters.");
-    println!("    5. help: Display this help message.");
-    println!("    6. exit: Exit the game.");
+/// Files chosen with the global `--save`, `--assets`, `--config`, `--packs`
+/// and `--record` options.
+struct Paths {
+    save_file: String,
+    /// The default save in the other format, read until `save_file` exists,
+    /// so switching `save_format` keeps the game going.
+    other_save_file: Option<String>,
+    assets_dir: String,
+    config_file: String,
+    packs_dir: String,
+    record_file: Option<String>,
 }
 
-fn split_command_args(input: &str) -> (String, Vec<String>) {
-    let split_input = input.split_whitespace();
-    let command = split_input.clone().next().unwrap_or("").to_lowercase();
-    let args = split_input.skip(1).map(|s| s.to_string()).collect();
-    (command, args)
+impl Paths {
+    /// The save file to read: `save_file`, or the save in the other format
+    /// when there is only that one.
+    fn load_file(&self) -> &str {
+        match &self.other_save_file {
+            Some(other) if !Path::new(&self.save_file).exists() && Path::new(other).exists() => {
+                other
+            }
+            _ => &self.save_file,
+        }
+    }
+
+    fn asset(&self, file: &str) -> String {
+        Path::new(&self.assets_dir)
+            .join(file)
+            .to_string_lossy()
+            .into_owned()
+    }
 }
 
-fn start_game(game_state: &mut GameState, save_file: &str) {
+/// Runs the game loop until the player leaves or their character is buried.
+/// Returns how the character's last death, if any, was resolved.
+fn start_game(
+    game_state: &mut GameState,
+    console: &mut Console,
+    paths: &Paths,
+    config: &Config,
+    debug_mode: bool,
+) -> Option<DeathOutcome> {
+    let mut recording = paths
+        .record_file
+        .as_ref()
+        .map(|_| Replay::record(game_state, config));
+    let mut history = History::new(config);
+    let mut death = None;
+
     // Main game loop
-    loop {
+    while !console.should_stop() {
         // Display prompt
         let line_leader = ">>. ";
-        let input = ask_user_for_input(&format!("{} ", line_leader));
-        let (command, args) = split_command_args(&input);
-
-        // Check for exit conditions
-        if command.eq_ignore_ascii_case("exit") || command.eq_ignore_ascii_case("quit") {
-            println!("Exiting game. Goodbye!");
-            break;
-        }
+        let input = match console.read_command(&format!("{} ", line_leader)) {
+            Ok(Some(input)) => input,
+            Ok(None) => break,
+            Err(e) => {
+                console.report_error(&e);
+                break;
+            }
+        };
+        let parsed = match command::parse(&input, GAME_COMMANDS, debug_mode) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(e) => {
+                console.report_error(&e);
+                continue;
+            }
+        };
 
-        // Process the command via game logic
-        if let Err(e) = game::process_command(game_state, command.as_str(), args) {
-            println!("Error: {}", e);
+        match parsed.name() {
+            // Check for exit conditions
+            "exit" => {
+                println!("Exiting game. Goodbye!");
+                break;
+            }
+            // Playback can't read the content files as they were, so a
+            // recording would no longer match
+            "reload" if recording.is_some() => {
+                console.report_error(&GameError::InvalidState(
+                    "reload is not available while recording a session.".into(),
+                ));
+                continue;
+            }
+            "reload" => {
+                reload_game_data(game_state, paths);
+                continue;
+            }
+            "help" => {
+                let topic = parsed.args.first().map(|s| s.as_str());
+                match command::help(GAME_COMMANDS, topic, debug_mode) {
+                    Ok(text) => println!("{}", text),
+                    Err(e) => console.report_error(&e),
+                }
+                continue;
+            }
+            // Process the command via game logic
+            name => {
+                console.take_answers();
+                let args = parsed.args.clone();
+                match history.process_command(game_state, console, name, parsed.args) {
+                    Ok(outcome) => death = outcome.or(death),
+                    Err(e) => console.report_error(&e),
+                }
+                if let (Some(replay), Some(record_file)) = (&mut recording, &paths.record_file) {
+                    let command = RecordedCommand {
+                        command: name.to_string(),
+                        args,
+                        answers: console.take_answers(),
+                    };
+                    replay.push(command, game_state);
+                    if let Err(e) = replay.save(record_file) {
+                        eprintln!("Failed to save recording: {}", e);
+                    }
+                }
+            }
         }
 
         // Optionally, save the game state after processing the command
-        if let Err(e) = game_state.save_to_file(save_file) {
+        if let Err(e) = game_state.save_to_file(&paths.save_file, config.save_format) {
             eprintln!("Failed to save game state: {}", e);
         }
 
         // Check for game over conditions
-        if !game_state.is_player_alive() {
-            println!("You died!");
-            break;
+        if death == Some(DeathOutcome::Buried) {
+            return death;
+        }
+    }
+
+    // Leaving while dead, with the death still undoable
+    if !game_state.is_player_alive() {
+        match history.settle(game_state) {
+            Ok(outcome) => death = Some(outcome),
+            Err(e) => console.report_error(&e),
+        }
+        if let Err(e) = game_state.save_to_file(&paths.save_file, config.save_format) {
+            eprintln!("Failed to save game state: {}", e);
         }
     }
+    death
 }
 
-fn load_game_state(save_file: &str) -> GameState {
-    match GameState::load_from_file(save_file) {
+fn load_game_state(paths: &Paths) -> GameState {
+    match GameState::load_from_file(paths.load_file()) {
         Ok(state) => {
             println!("Loaded game state from file.");
             state
         }
         Err(e) => {
             eprintln!("Failed to load game state: {}", e);
-            let state = create_new_game_state();
-            state
+            create_new_game_state(paths)
         }
     }
 }
 
-fn create_new_game_state() -> GameState {
+/// Load the save file for a headless command, starting from the content
+/// files if there is no save yet.
+fn open_game_state(paths: &Paths) -> GameResult<GameState> {
+    if Path::new(paths.load_file()).exists() {
+        return GameState::load_from_file(paths.load_file());
+    }
     let mut state = GameState::new();
-    if let Err(e) = state.reload(ENTITIES_FILE, ITEMS_FILE, SKILLS_FILE) {
-        eprintln!("Failed to load game data: {}", e);
-    } else {
-        println!("Loaded game data.");
+    state.reload(load_content(paths)?)?;
+    Ok(state)
+}
+
+fn load_content(paths: &Paths) -> GameResult<Content> {
+    let mut content = Content::from_files(
+        &paths.asset(ENTITIES_FILE),
+        &paths.asset(ITEMS_FILE),
+        &paths.asset(SKILLS_FILE),
+    )?;
+    content.load_world(&paths.asset(WORLD_FILE))?;
+    content.load_dungeons(&paths.asset(DUNGEONS_FILE))?;
+    content.load_quests(&paths.asset(QUESTS_FILE))?;
+    content.load_dialogue(&paths.asset(DIALOGUE_FILE))?;
+    content.load_factions(&paths.asset(FACTIONS_FILE))?;
+    content.load_classes(&paths.asset(CLASSES_FILE))?;
+    content.load_skill_trees(&paths.asset(SKILL_TREES_FILE))?;
+    for issue in content.apply_packs(&paths.packs_dir) {
+        eprintln!("{}", issue);
+    }
+    Ok(content)
+}
+
+fn create_new_game_state(paths: &Paths) -> GameState {
+    let mut state = GameState::new();
+    match load_content(paths).and_then(|content| state.reload(content)) {
+        Ok(()) => println!("Loaded game data."),
+        Err(e) => eprintln!("Failed to load game data: {}", e),
     }
     state
 }
 
-fn ask_user_for_input(message: &str) -> String {
-    print!("{}", message);
-    io::stdout().flush().unwrap();
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+fn reload_game_data(game_state: &mut GameState, paths: &Paths) {
+    match load_content(paths).and_then(|content| game_state.reload(content)) {
+        Ok(()) => println!("Reloaded game data."),
+        Err(e) => eprintln!("Failed to reload game data: {}", e),
+    }
 }
 
-fn ask_user_create_player(game_state: &mut GameState) {
+fn ask_user_create_player(
+    game_state: &mut GameState,
+    console: &mut Console,
+    args: &[String],
+) -> GameResult<()> {
     // Ask user for player name
-    let name = ask_user_for_input("Enter character name: ");
-    let id = game_state.players.len() as u32 + 1;
-    let mut player = game::entity::Entity::new(id, &name);
-    let default_player = game_state.get_default_player();
+    let name = match args.first() {
+        Some(name) => name.to_string(),
+        None => {
+            console.require_interactive("character name")?;
+            console.ask("Enter character name: ")?
+        }
+    };
+    if game_state.classes.is_empty() || game_state.races.is_empty() {
+        return create_player(game_state, &name, None, None, None);
+    }
+
+    let mut prompted = false;
+    let class = match args.get(1) {
+        Some(class) => class.to_string(),
+        None => {
+            console.require_interactive("class")?;
+            prompted = true;
+            println!("Classes:");
+            for (i, class) in game_state.classes.iter().enumerate() {
+                println!("    {}. {} - {}", i + 1, class.name, class.description);
+            }
+            console.ask("Choose a class: ")?
+        }
+    };
+    let class = &game_state.classes[command::resolve_index(&game_state.classes, &class, "class")?];
+    let race = match args.get(2) {
+        Some(race) => race.to_string(),
+        None => {
+            console.require_interactive("race")?;
+            prompted = true;
+            println!("Races:");
+            for (i, race) in game_state.races.iter().enumerate() {
+                println!("    {}. {} - {}", i + 1, race.name, race.description);
+                println!("    {}", character::preview(game_state, race, class, None)?);
+            }
+            console.ask("Choose a race: ")?
+        }
+    };
+    let race = &game_state.races[command::resolve_index(&game_state.races, &race, "race")?];
+    let points = match args.get(3) {
+        Some(points) => Some(points.to_string()),
+        None if prompted => {
+            let answer = console.ask(&format!(
+                "Buy your own stats with {} points instead of the {}'s? (y/n): ",
+                game_state.point_buy.budget, class.name
+            ))?;
+            if answer.trim().eq_ignore_ascii_case("y") {
+                println!("{}", game_state.point_buy.describe());
+                Some(console.ask("Allocate points (e.g. hp=4,attack=6): ")?)
+            } else {
+                None
+            }
+        }
+        None => None,
+    };
+    let bought = points.as_deref().map(PointBuy::parse).transpose()?;
+    if prompted {
+        println!(
+            "{}",
+            character::preview(game_state, race, class, bought.as_ref())?
+        );
+        let answer = console.ask(&format!(
+            "Create {} the {} {}? (y/n): ",
+            name, race.name, class.name
+        ))?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            return Err(GameError::InvalidState(
+                "Character creation cancelled.".into(),
+            ));
+        }
+    }
+    let (class, race) = (class.id.to_string(), race.id.to_string());
+    create_player(
+        game_state,
+        &name,
+        Some(&class),
+        Some(&race),
+        points.as_deref(),
+    )
+}
 
-    player.stats = default_player.stats.clone();
-    player.skills = default_player.skills.clone();
-    player.inventory = default_player.inventory.clone();
-    player.equipment = default_player.equipment.clone();
+/// Add a character and select it. With classes and races defined it is
+/// built from the ones given, or the first of each, with its stats bought
+/// with `points` if given; otherwise it is a copy of the player template.
+fn create_player(
+    game_state: &mut GameState,
+    name: &str,
+    class: Option<&str>,
+    race: Option<&str>,
+    points: Option<&str>,
+) -> GameResult<()> {
+    if name.is_empty() {
+        return Err(GameError::InvalidInput(
+            "character name cannot be empty".into(),
+        ));
+    }
+    let id = game_state.next_player_id();
+    let player = if game_state.classes.is_empty() || game_state.races.is_empty() {
+        if points.is_some() {
+            return Err(GameError::InvalidInput(
+                "buying stats needs classes and races to be defined".into(),
+            ));
+        }
+        let mut player = game::entity::Entity::new(id, name);
+        let default_player = game_state.get_default_player()?;
+
+        player.stats = default_player.stats;
+        player.skills = default_player.skills.clone();
+        player.inventory = default_player.inventory.clone();
+        player.equipment = default_player.equipment.clone();
+        player
+    } else {
+        let class = match class {
+            Some(class) => command::resolve_index(&game_state.classes, class, "class")?,
+            None => 0,
+        };
+        let race = match race {
+            Some(race) => command::resolve_index(&game_state.races, race, "race")?,
+            None => 0,
+        };
+        character::build(
+            game_state,
+            id,
+            name,
+            game_state.races[race].id,
+            game_state.classes[class].id,
+            points.map(PointBuy::parse).transpose()?.as_ref(),
+        )?
+    };
 
     game_state.create_player(player);
-    game_state.set_player(game_state.players.len() - 1);
+    game_state.set_player(game_state.players.len() - 1)
 }
 
-fn ask_user_select_player(game_state: &mut GameState) {
-    let players = game_state.get_players_string();
-    println!("Select a character to load: ");
-    println!("{}", players);
-
-    let player_id = ask_user_for_input("Enter character ID: ");
-    // index - 1
-    let index = player_id.parse::<usize>().unwrap() - 1;
-    game_state.set_player(index);
+fn ask_user_select_player(
+    game_state: &mut GameState,
+    console: &mut Console,
+    player_id: Option<&str>,
+) -> GameResult<()> {
+    if game_state.players.is_empty() {
+        return Err(GameError::InvalidState(
+            "No characters to load. Create one with 'cc'.".into(),
+        ));
+    }
+    let player_id = match player_id {
+        Some(player_id) => player_id.to_string(),
+        None => {
+            console.require_interactive("character")?;
+            let players = game_state.get_players_string();
+            println!("Select a character to load: ");
+            println!("{}", players);
+            console.ask("Enter character ID: ")?
+        }
+    };
+    let index = command::resolve_index(&game_state.players, &player_id, "character")?;
+    game_state.set_player(index)
 }
 
-fn welcome_screen() {
-    println!("");
+/// Runs the main menu until the player exits or the input ends.
+/// Returns the process exit status: 0 if everything went well, 1 if a command
+/// failed and 2 if a character died.
+fn welcome_screen(console: &mut Console, paths: &Paths, debug_mode: bool) -> i32 {
+    println!();
     println!("+++++++++++++++++++++");
     println!("Welcome to Ultimate End");
     println!("+++++++++++++++++++++");
-    println!("");
+    println!();
 
     let mut exiting_game = false;
 
-    let save_file = SAVE_FILE;
-    let mut game_state = load_game_state(save_file);
+    let config = Config::load(&paths.config_file).unwrap_or_else(|e| {
+        eprintln!("Failed to load config, using defaults: {}", e);
+        Config::default()
+    });
+    let mut game_state = load_game_state(paths);
+    // Characters that died before death policies existed
+    if let Err(e) = death::settle_fallen(&mut game_state, config.death_policy) {
+        eprintln!("Failed to settle fallen characters: {}", e);
+    }
     let mut is_character_loaded = false;
+    let mut died = false;
 
-    while !exiting_game {
+    while !exiting_game && !console.should_stop() {
         let line_leader = ">. ";
-        let input = ask_user_for_input(&format!("{} ", line_leader));
-        let (command, _args) = split_command_args(&input);
+        let input = match console.read_command(&format!("{} ", line_leader)) {
+            Ok(Some(input)) => input,
+            Ok(None) => break,
+            Err(e) => {
+                console.report_error(&e);
+                break;
+            }
+        };
+        let parsed = match command::parse(&input, MENU_COMMANDS, debug_mode) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(e) => {
+                console.report_error(&e);
+                continue;
+            }
+        };
+        let arg = parsed.args.first().map(|s| s.as_str());
 
-        match command.as_str() {
-            "1" | "start" => {
+        match parsed.name() {
+            "start" => {
                 if is_character_loaded {
                     println!("Starting a new game.");
                     //let &mut run_game_state = &mut game_state;
-                    start_game(&mut game_state, save_file);
+                    let death = start_game(&mut game_state, console, paths, &config, debug_mode);
+                    died |= death.is_some();
+                    if death == Some(DeathOutcome::Buried) {
+                        is_character_loaded = false;
+                    }
                 } else {
-                    println!("No character loaded. Please load a character or create a new one.");
+                    console.report_error(&GameError::InvalidState(
+                        "No character loaded. Please load a character or create a new one.".into(),
+                    ));
                 }
             }
-            "2" | "cc" => {
+            "cc" => {
                 println!("Creating a new character.");
-                ask_user_create_player(&mut game_state);
-                is_character_loaded = true;
+                match ask_user_create_player(&mut game_state, console, &parsed.args) {
+                    Ok(()) => is_character_loaded = true,
+                    Err(e) => console.report_error(&e),
+                }
             }
-            "3" | "lc" => {
+            "lc" => {
                 println!("Loading an existing character.");
-                ask_user_select_player(&mut game_state);
-                is_character_loaded = true;
+                match ask_user_select_player(&mut game_state, console, arg) {
+                    Ok(()) => is_character_loaded = true,
+                    Err(e) => console.report_error(&e),
+                }
             }
-            "4" | "sc" => {
+            "sc" => {
                 println!("Showing all characters.");
                 println!("{}", game_state.get_players_string());
             }
-            "5" | "help" => {
-                help();
+            "graveyard" => {
+                if game_state.graveyard.is_empty() {
+                    println!("The graveyard is empty.");
+                } else {
+                    println!("{}", game_state.get_graveyard_string());
+                }
             }
-            "6" | "exit" => {
+            "help" => match command::help(MENU_COMMANDS, arg, debug_mode) {
+                Ok(text) => println!("{}", text),
+                Err(e) => console.report_error(&e),
+            },
+            "exit" => {
                 println!("Thanks for playing Ultimate End.");
                 exiting_game = true;
             }
-            _ => {
-                println!("Invalid command. Type 'help' for a list of commands.");
+            "export" => {
+                let export_file = arg.unwrap_or(EXPORT_FILE);
+                match game_state.save_to_file(export_file, SaveFormat::Json) {
+                    Ok(()) => println!("Exported game state to {}.", export_file),
+                    Err(e) => console.report_error(&e),
+                }
+            }
+            "reload" => {
+                reload_game_data(&mut game_state, paths);
             }
+            name => {
+                console.report_error(&GameError::InvalidState(format!(
+                    "'{}' can't be used here.",
+                    name
+                )));
+            }
+        }
+    }
+
+    if console.failed() && !console.is_interactive() {
+        1
+    } else if died {
+        2
+    } else {
+        0
+    }
+}
+
+fn validate_content(paths: &Paths) -> ! {
+    let report = game::validation::validate_assets(
+        &game::validation::AssetFiles {
+            entities: &paths.asset(ENTITIES_FILE),
+            items: &paths.asset(ITEMS_FILE),
+            skills: &paths.asset(SKILLS_FILE),
+            world: &paths.asset(WORLD_FILE),
+            dungeons: &paths.asset(DUNGEONS_FILE),
+            quests: &paths.asset(QUESTS_FILE),
+            dialogue: &paths.asset(DIALOGUE_FILE),
+            factions: &paths.asset(FACTIONS_FILE),
+            classes: &paths.asset(CLASSES_FILE),
+            skill_trees: &paths.asset(SKILL_TREES_FILE),
+        },
+        &paths.packs_dir,
+    );
+    println!("{}", report);
+    if report.has_errors() {
+        std::process::exit(1);
+    }
+    std::process::exit(0);
+}
+
+fn convert_content(input: &str, output: &str) -> ! {
+    match game::content::convert_file(input, output) {
+        Ok(()) => {
+            println!("Converted {} to {}.", input, output);
+            std::process::exit(0);
+        }
+        Err(e) => {
+            eprintln!("Failed to convert {}: {}", input, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn new_character(
+    paths: &Paths,
+    name: &str,
+    class: Option<&str>,
+    race: Option<&str>,
+    points: Option<&str>,
+) -> GameResult<()> {
+    let mut game_state = open_game_state(paths)?;
+    create_player(&mut game_state, name, class, race, points)?;
+    let config = Config::load(&paths.config_file)?;
+    game_state.save_to_file(&paths.save_file, config.save_format)?;
+    let player = game_state.get_player()?;
+    println!("Created {} (id {}).", player.name, player.id);
+    Ok(())
+}
+
+fn list_characters(paths: &Paths) -> GameResult<()> {
+    let game_state = open_game_state(paths)?;
+    if game_state.players.is_empty() {
+        println!("No characters yet.");
+    } else {
+        print!("{}", game_state.get_players_string());
+    }
+    Ok(())
+}
+
+fn show_graveyard(paths: &Paths) -> GameResult<()> {
+    let game_state = open_game_state(paths)?;
+    if game_state.graveyard.is_empty() {
+        println!("The graveyard is empty.");
+    } else {
+        print!("{}", game_state.get_graveyard_string());
+    }
+    Ok(())
+}
+
+fn show_character(paths: &Paths, character: &str) -> GameResult<()> {
+    let game_state = open_game_state(paths)?;
+    let index = command::resolve_index(&game_state.players, character, "character")?;
+    println!("{}", game_state.players[index].get_entity_string());
+    Ok(())
+}
+
+fn simulate(paths: &Paths, character: &str, fights: &str) -> GameResult<()> {
+    let fights: u32 = fights
+        .parse()
+        .map_err(|_| GameError::InvalidInput(format!("'{}' is not a number of fights", fights)))?;
+    let game_state = open_game_state(paths)?;
+    let index = command::resolve_index(&game_state.players, character, "character")?;
+    let player = &game_state.players[index];
+
+    let mut wins = 0;
+    let mut total_rounds = 0;
+    for fight in 1..=fights {
+        let enemy = game_state.random_npc()?;
+        let enemy_name = enemy.name.clone();
+        let outcome = game::combat::simulate_fight(player.clone(), enemy)?;
+        total_rounds += outcome.rounds;
+        if outcome.player_won {
+            wins += 1;
+            println!(
+                "Fight {}: {} beat {} in {} rounds.",
+                fight, player.name, enemy_name, outcome.rounds
+            );
+        } else {
+            println!(
+                "Fight {}: {} lost to {} in {} rounds.",
+                fight, player.name, enemy_name, outcome.rounds
+            );
+        }
+    }
+    if fights > 0 {
+        println!(
+            "{} won {} of {} fights, {:.1} rounds on average.",
+            player.name,
+            wins,
+            fights,
+            total_rounds as f32 / fights as f32
+        );
+    }
+    Ok(())
+}
+
+fn export_save(paths: &Paths, export_file: &str) -> GameResult<()> {
+    let game_state = GameState::load_from_file(paths.load_file())?;
+    game_state.save_to_file(export_file, SaveFormat::Json)?;
+    println!("Exported game state to {}.", export_file);
+    Ok(())
+}
+
+/// Play in the full-screen frontend. Exits with status 2 if the character
+/// died, like the line prompt.
+#[cfg(feature = "tui")]
+fn play_tui(paths: &Paths) -> GameResult<()> {
+    let mut game_state = open_game_state(paths)?;
+    let config = Config::load(&paths.config_file)?;
+    death::settle_fallen(&mut game_state, config.death_policy)?;
+    if tui::run(game_state, &paths.save_file, &config)? {
+        std::process::exit(2);
+    }
+    Ok(())
+}
+
+fn replay_session(file: &str, step: bool) -> GameResult<()> {
+    let replay = Replay::load(file)?;
+    println!(
+        "Replaying {} commands with seed {}.",
+        replay.commands.len(),
+        replay.seed
+    );
+    let state = replay.play(|i, recorded| {
+        let line = std::iter::once(recorded.command.as_str())
+            .chain(recorded.args.iter().map(|s| s.as_str()))
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("[{}] >>. {}", i + 1, line);
+        if step {
+            let mut pause = String::new();
+            io::stdin().read_line(&mut pause).ok();
+        }
+    });
+    if replay.matches_end(&state)? {
+        println!("Replay finished: the final state matches the recording.");
+        Ok(())
+    } else {
+        Err(GameError::InvalidState(
+            "Replay finished: the final state differs from the recording.".into(),
+        ))
+    }
+}
+
+/// Exit with status 1 if a headless command failed.
+fn finish(result: GameResult<()>) -> ! {
+    match result {
+        Ok(()) => std::process::exit(0),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
     }
 }
@@ -181,5 +706,81 @@ fn main() {
     if debug_mode {
         println!("Debug mode enabled.");
     }
-    welcome_screen();
+    let config_file = matches.value_of("config").unwrap_or(CONFIG_FILE);
+    let (save_file, other_save_file) = match matches.value_of("save") {
+        Some(file) => (file.to_string(), None),
+        // A broken config is reported once the game loads it
+        None => {
+            let format = Config::load(config_file).unwrap_or_default().save_format;
+            let other = match format {
+                SaveFormat::Json => SaveFormat::Binary,
+                SaveFormat::Binary => SaveFormat::Json,
+            };
+            (
+                format!("{}.{}", SAVE_FILE_STEM, format.extension()),
+                Some(format!("{}.{}", SAVE_FILE_STEM, other.extension())),
+            )
+        }
+    };
+    let paths = Paths {
+        save_file,
+        other_save_file,
+        assets_dir: matches.value_of("assets").unwrap_or(ASSETS_DIR).to_string(),
+        config_file: config_file.to_string(),
+        packs_dir: matches.value_of("packs").unwrap_or(PACKS_DIR).to_string(),
+        record_file: matches.value_of("record").map(str::to_string),
+    };
+    match matches.subcommand() {
+        #[cfg(feature = "tui")]
+        Some(("play", sub_matches)) if sub_matches.is_present("tui") => finish(play_tui(&paths)),
+        Some(("new-character", sub_matches)) => finish(new_character(
+            &paths,
+            sub_matches.value_of("name").unwrap_or_default(),
+            sub_matches.value_of("class"),
+            sub_matches.value_of("race"),
+            sub_matches.value_of("points"),
+        )),
+        Some(("list-characters", _)) => finish(list_characters(&paths)),
+        Some(("graveyard", _)) => finish(show_graveyard(&paths)),
+        Some(("show", sub_matches)) => finish(show_character(
+            &paths,
+            sub_matches.value_of("character").unwrap_or_default(),
+        )),
+        Some(("simulate", sub_matches)) => finish(simulate(
+            &paths,
+            sub_matches.value_of("character").unwrap_or_default(),
+            sub_matches.value_of("fights").unwrap_or_default(),
+        )),
+        Some(("export", sub_matches)) => finish(export_save(
+            &paths,
+            sub_matches.value_of("file").unwrap_or(EXPORT_FILE),
+        )),
+        Some(("replay", sub_matches)) => finish(replay_session(
+            sub_matches.value_of("file").unwrap_or_default(),
+            sub_matches.is_present("step"),
+        )),
+        Some(("validate", _)) => validate_content(&paths),
+        Some(("convert", sub_matches)) => convert_content(
+            sub_matches.value_of("input").unwrap_or_default(),
+            sub_matches.value_of("output").unwrap_or_default(),
+        ),
+        // `play` and no subcommand both start the game
+        _ => {}
+    }
+
+    let mut console = if let Some(script) = matches.value_of("script") {
+        match File::open(script) {
+            Ok(file) => Console::script(Box::new(BufReader::new(file)), script),
+            Err(e) => {
+                eprintln!("Failed to open script {}: {}", script, e);
+                std::process::exit(1);
+            }
+        }
+    } else if matches.is_present("batch") {
+        Console::script(Box::new(BufReader::new(io::stdin())), "stdin")
+    } else {
+        Console::stdin()
+    };
+    let status = welcome_screen(&mut console, &paths, debug_mode);
+    std::process::exit(status);
 }
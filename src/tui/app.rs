@@ -0,0 +1,293 @@
+// src/tui/app.rs
+use crate::game::config::Config;
+use crate::game::console::Console;
+use crate::game::death::DeathOutcome;
+use crate::game::error::GameResult;
+use crate::game::history::History;
+use crate::game::item::ItemType;
+use crate::game::log;
+use crate::game::persistence::{GameState, SaveFormat};
+use ratatui::crossterm::event::KeyCode;
+use std::io;
+
+/// Lines of combat log kept in memory.
+const LOG_LIMIT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Screen {
+    Characters,
+    Combat,
+    Inventory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pane {
+    Inventory,
+    Equipment,
+}
+
+/// Everything the TUI shows, driven by key presses.
+///
+/// Actions go through `game::process_command` like typed commands do, so the
+/// TUI and the line prompt share the same rules. Keeping input handling here
+/// and drawing in `ui` lets both run against ratatui's `TestBackend`.
+pub struct App {
+    pub state: GameState,
+    console: Console,
+    history: History,
+    save_file: Option<String>,
+    save_format: SaveFormat,
+    pub screen: Screen,
+    pub log: Vec<String>,
+    /// Lines scrolled up from the bottom of the log.
+    pub log_scroll: usize,
+    pub character_selection: usize,
+    pub skill_selection: usize,
+    pub pane: Pane,
+    pub inventory_selection: usize,
+    pub equipment_selection: usize,
+    pub died: bool,
+    pub quit: bool,
+}
+
+impl App {
+    /// `save_file` is written after every action; pass `None` to play
+    /// without saving.
+    pub fn new(state: GameState, save_file: Option<&str>, config: &Config) -> Self {
+        let mut app = Self {
+            state,
+            // Every action passes its arguments, so the engine never prompts
+            console: Console::script(Box::new(io::empty()), "tui"),
+            history: History::new(config),
+            save_file: save_file.map(str::to_string),
+            save_format: config.save_format,
+            screen: Screen::Characters,
+            log: Vec::new(),
+            log_scroll: 0,
+            character_selection: 0,
+            skill_selection: 0,
+            pane: Pane::Inventory,
+            inventory_selection: 0,
+            equipment_selection: 0,
+            died: false,
+            quit: false,
+        };
+        if app.state.players.is_empty() {
+            app.push_log("No characters yet. Create one with `new-character <name>`.");
+        }
+        app
+    }
+
+    fn push_log(&mut self, line: &str) {
+        self.log.push(line.to_string());
+        if self.log.len() > LOG_LIMIT {
+            self.log.drain(..self.log.len() - LOG_LIMIT);
+        }
+        self.log_scroll = 0;
+    }
+
+    /// Run a game command, sending its messages to the log.
+    fn run_command(&mut self, command: &str, args: Vec<String>) {
+        let (result, lines) = log::capture(|| {
+            self.history
+                .process_command(&mut self.state, &mut self.console, command, args)
+        });
+        for line in lines {
+            self.push_log(&line);
+        }
+        if let Err(e) = self.save() {
+            self.push_log(&format!("Failed to save game state: {}", e));
+        }
+        match result {
+            Ok(Some(DeathOutcome::Buried)) => {
+                self.push_log("Press any key to leave.");
+                self.died = true;
+            }
+            Ok(_) => {}
+            Err(e) => self.push_log(&format!("Error: {}", e)),
+        }
+    }
+
+    fn save(&self) -> GameResult<()> {
+        match &self.save_file {
+            Some(path) => self.state.save_to_file(path, self.save_format),
+            None => Ok(()),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) {
+        if self.died {
+            self.quit = true;
+            return;
+        }
+        match self.screen {
+            Screen::Characters => self.characters_key(key),
+            Screen::Combat => self.combat_key(key),
+            Screen::Inventory => self.inventory_key(key),
+        }
+    }
+
+    fn characters_key(&mut self, key: KeyCode) {
+        let count = self.state.players.len();
+        match key {
+            KeyCode::Up => self.character_selection = step(self.character_selection, count, -1),
+            KeyCode::Down => self.character_selection = step(self.character_selection, count, 1),
+            KeyCode::Enter if count > 0 => match self.state.set_player(self.character_selection) {
+                Ok(()) => {
+                    let name = self.state.players[self.character_selection].name.clone();
+                    self.push_log(&format!("Playing as {}.", name));
+                    self.screen = Screen::Combat;
+                }
+                Err(e) => self.push_log(&format!("Error: {}", e)),
+            },
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+    }
+
+    fn combat_key(&mut self, key: KeyCode) {
+        let skill_count = self
+            .state
+            .get_player()
+            .map(|player| player.skills.len())
+            .unwrap_or(0);
+        match key {
+            KeyCode::Up => self.skill_selection = step(self.skill_selection, skill_count, -1),
+            KeyCode::Down => self.skill_selection = step(self.skill_selection, skill_count, 1),
+            KeyCode::Left | KeyCode::Right => {
+                let count = self.state.enemies.len();
+                let delta = if key == KeyCode::Left { -1 } else { 1 };
+                let index = step(self.state.enemy_index, count, delta);
+                if self.state.set_enemy(index).is_ok() {
+                    self.log_scroll = 0;
+                }
+            }
+            KeyCode::Enter if skill_count > 0 => {
+                let skill = (self.skill_selection.min(skill_count - 1) + 1).to_string();
+                self.run_command("attack", vec![skill]);
+            }
+            KeyCode::Char('r') => self.run_command("run", Vec::new()),
+            KeyCode::Char('u') => self.run_command("undo", Vec::new()),
+            KeyCode::Char('i') | KeyCode::Tab => self.screen = Screen::Inventory,
+            KeyCode::PageUp => self.log_scroll = (self.log_scroll + 5).min(self.log.len()),
+            KeyCode::PageDown => self.log_scroll = self.log_scroll.saturating_sub(5),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+    }
+
+    fn inventory_key(&mut self, key: KeyCode) {
+        let (inventory_count, equipment_count) = self
+            .state
+            .get_player()
+            .map(|player| (player.inventory.len(), player.equipment.len()))
+            .unwrap_or((0, 0));
+        match key {
+            KeyCode::Left | KeyCode::Right | KeyCode::Tab => {
+                self.pane = match self.pane {
+                    Pane::Inventory => Pane::Equipment,
+                    Pane::Equipment => Pane::Inventory,
+                }
+            }
+            KeyCode::Up | KeyCode::Down => {
+                let delta = if key == KeyCode::Up { -1 } else { 1 };
+                match self.pane {
+                    Pane::Inventory => {
+                        self.inventory_selection =
+                            step(self.inventory_selection, inventory_count, delta)
+                    }
+                    Pane::Equipment => {
+                        self.equipment_selection =
+                            step(self.equipment_selection, equipment_count, delta)
+                    }
+                }
+            }
+            KeyCode::Enter => match self.pane {
+                Pane::Inventory if inventory_count > 0 => {
+                    let index = self.inventory_selection.min(inventory_count - 1);
+                    let consumable = self.state.get_player().is_ok_and(|player| {
+                        player.inventory[index].item_type == ItemType::Consumable
+                    });
+                    let command = if consumable { "use_item" } else { "equip" };
+                    self.run_command(command, vec![(index + 1).to_string()]);
+                }
+                Pane::Equipment if equipment_count > 0 => {
+                    let index = self.equipment_selection.min(equipment_count - 1);
+                    self.run_command("unequip", vec![(index + 1).to_string()]);
+                }
+                _ => {}
+            },
+            KeyCode::Char('i') | KeyCode::Esc => self.screen = Screen::Combat,
+            KeyCode::Char('q') => self.quit = true,
+            _ => {}
+        }
+    }
+}
+
+/// Move a list selection by `delta`, wrapping around.
+fn step(selection: usize, len: usize, delta: i32) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let selection = selection.min(len - 1) as i64 + delta as i64;
+    selection.rem_euclid(len as i64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+
+    fn app() -> App {
+        App::new(fixtures::arena(), None, &Config::default())
+    }
+
+    #[test]
+    fn enter_picks_a_character_and_starts_combat() {
+        let mut app = app();
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.screen, Screen::Combat);
+        assert_eq!(app.log.last().unwrap(), "Playing as Mira.");
+    }
+
+    #[test]
+    fn selections_wrap_around() {
+        let mut app = app();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.skill_selection, 0);
+        app.handle_key(KeyCode::Left);
+        assert_eq!(app.state.enemy_index, app.state.enemies.len() - 1);
+        app.handle_key(KeyCode::Right);
+        assert_eq!(app.state.enemy_index, 0);
+    }
+
+    #[test]
+    fn enter_in_combat_attacks_with_the_selected_skill() {
+        let mut app = app();
+        app.handle_key(KeyCode::Enter);
+        let lines = app.log.len();
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.log[lines], "Mira attacks Goblin for 5 damage!");
+    }
+
+    #[test]
+    fn the_inventory_opens_and_closes() {
+        let mut app = app();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.screen, Screen::Inventory);
+        app.handle_key(KeyCode::Tab);
+        assert_eq!(app.pane, Pane::Equipment);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.screen, Screen::Combat);
+    }
+
+    #[test]
+    fn any_key_leaves_after_death() {
+        let mut app = app();
+        app.died = true;
+        app.handle_key(KeyCode::Char('x'));
+        assert!(app.quit);
+    }
+}
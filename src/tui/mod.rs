@@ -0,0 +1,64 @@
+// src/tui/mod.rs
+pub mod app;
+pub mod ui;
+
+use crate::game::config::Config;
+use crate::game::error::GameResult;
+use crate::game::persistence::GameState;
+use app::App;
+use ratatui::backend::Backend;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::Terminal;
+use std::io;
+
+/// Run the full-screen frontend until the player quits.
+/// Returns true if the character died for good.
+pub fn run(state: GameState, save_file: &str, config: &Config) -> GameResult<bool> {
+    let mut terminal = ratatui::init();
+    let mut app = App::new(state, Some(save_file), config);
+    let result = run_app(&mut terminal, &mut app, event::read);
+    ratatui::restore();
+    result.map(|()| app.died)
+}
+
+/// The event loop, separate from terminal setup so it works on any backend.
+/// `next_event` blocks until the next input event arrives.
+pub fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    mut next_event: impl FnMut() -> io::Result<Event>,
+) -> GameResult<()> {
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if let Event::Key(key) = next_event()? {
+            if key.kind == KeyEventKind::Press {
+                app.handle_key(key.code);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::fixtures;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::{KeyCode, KeyEvent};
+
+    #[test]
+    fn the_loop_runs_on_scripted_events() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let mut app = App::new(fixtures::arena(), None, &Config::default());
+        let mut keys = [KeyCode::Enter, KeyCode::Char('q')]
+            .into_iter()
+            .map(|code| Event::Key(KeyEvent::from(code)));
+        run_app(&mut terminal, &mut app, || {
+            keys.next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "no more keys"))
+        })
+        .unwrap();
+        assert!(app.quit);
+        assert_eq!(app.screen, app::Screen::Combat);
+    }
+}
@@ -0,0 +1,338 @@
+// src/tui/ui.rs
+use crate::game::cooldown;
+use crate::game::entity::Entity;
+use crate::game::item::Item;
+use crate::tui::app::{App, Pane, Screen};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [body, keys] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    match app.screen {
+        Screen::Characters => draw_characters(frame, app, body),
+        Screen::Combat => draw_combat(frame, app, body),
+        Screen::Inventory => draw_inventory(frame, app, body),
+    }
+    let help = if app.died {
+        "any key: leave"
+    } else {
+        match app.screen {
+            Screen::Characters => "↑↓ select  enter: play  q: quit",
+            Screen::Combat => {
+                "↑↓ skill  enter: attack  ←→ enemy  r: run  u: undo  i: inventory  pgup/pgdn: log  q: quit"
+            }
+            Screen::Inventory => "←→ switch list  ↑↓ select  enter: equip/use/unequip  esc: back",
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(help).style(Style::new().fg(Color::DarkGray)),
+        keys,
+    );
+}
+
+fn selectable_list<'a>(title: &'a str, items: Vec<ListItem<'a>>, focused: bool) -> List<'a> {
+    let border = if focused {
+        Style::new().fg(Color::Yellow)
+    } else {
+        Style::new()
+    };
+    List::new(items)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(border),
+        )
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ")
+}
+
+/// Render `list` with `selection` highlighted, kept in range.
+fn render_selection(frame: &mut Frame, list: List, len: usize, selection: usize, area: Rect) {
+    let mut list_state = ListState::default();
+    if len > 0 {
+        list_state.select(Some(selection.min(len - 1)));
+    }
+    frame.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_characters(frame: &mut Frame, app: &App, area: Rect) {
+    let [list, log] = Layout::vertical([Constraint::Min(3), Constraint::Length(5)]).areas(area);
+    let items = app
+        .state
+        .players
+        .iter()
+        .map(|player| {
+            ListItem::new(format!(
+                "{}  HP {}  ATK {}  DEF {}  AGI {}",
+                player.name,
+                player.stats.hp,
+                player.stats.attack,
+                player.stats.defense,
+                player.stats.agility
+            ))
+        })
+        .collect();
+    render_selection(
+        frame,
+        selectable_list("Characters", items, true),
+        app.state.players.len(),
+        app.character_selection,
+        list,
+    );
+    draw_log(frame, app, log);
+}
+
+fn draw_combat(frame: &mut Frame, app: &App, area: Rect) {
+    let [stats, log, skills] = Layout::vertical([
+        Constraint::Length(6),
+        Constraint::Min(3),
+        Constraint::Length(8),
+    ])
+    .areas(area);
+    let [player_area, enemy_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(stats);
+
+    match app.state.get_player() {
+        Ok(player) => {
+            let title = match app.state.world.room(&player.location.room) {
+                Some((_, room)) => format!("{} - Player", room.name),
+                None => "Player".to_string(),
+            };
+            draw_entity(frame, app, player, title, player_area)
+        }
+        Err(e) => draw_message(frame, "Player", &e.to_string(), player_area),
+    }
+    match app.state.get_enemy() {
+        Ok(enemy) => {
+            let title = format!(
+                "Enemy {}/{}",
+                app.state.enemy_index + 1,
+                app.state.enemies.len()
+            );
+            draw_entity(frame, app, enemy, title, enemy_area)
+        }
+        Err(e) => draw_message(frame, "Enemy", &e.to_string(), enemy_area),
+    }
+    draw_log(frame, app, log);
+
+    let player = app.state.get_player().ok();
+    let player_skills = player
+        .map(|player| player.skills.as_slice())
+        .unwrap_or_default();
+    let items = player_skills
+        .iter()
+        .map(|skill| {
+            let status = player.map_or_else(String::new, |player| {
+                format!(", {}", cooldown::status(player, skill))
+            });
+            ListItem::new(format!("{}  (power {}{})", skill.name, skill.power, status))
+        })
+        .collect();
+    render_selection(
+        frame,
+        selectable_list("Skills", items, true),
+        player_skills.len(),
+        app.skill_selection,
+        skills,
+    );
+}
+
+fn draw_entity(frame: &mut Frame, app: &App, entity: &Entity, title: String, area: Rect) {
+    let block = Block::default()
+        .title(format!("{}: {}", title, entity.name))
+        .borders(Borders::ALL);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [hp, stats] = Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(inner);
+
+    let full_hp = app.state.full_hp(entity).max(entity.stats.hp).max(1);
+    let ratio = (entity.stats.hp.max(0) as f64 / full_hp as f64).clamp(0.0, 1.0);
+    let color = if ratio > 0.5 {
+        Color::Green
+    } else if ratio > 0.25 {
+        Color::Yellow
+    } else {
+        Color::Red
+    };
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::new().fg(color))
+            .ratio(ratio)
+            .label(format!("HP {}/{}", entity.stats.hp, full_hp)),
+        hp,
+    );
+    let lines = vec![
+        Line::from(format!(
+            "Attack {}  Defense {}  Agility {}",
+            entity.stats.attack, entity.stats.defense, entity.stats.agility
+        )),
+        Line::from(format!(
+            "Equipped: {}",
+            entity
+                .equipment
+                .iter()
+                .map(|item| item.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    ];
+    frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), stats);
+}
+
+fn draw_message(frame: &mut Frame, title: &str, message: &str, area: Rect) {
+    frame.render_widget(
+        Paragraph::new(message).block(Block::default().title(title).borders(Borders::ALL)),
+        area,
+    );
+}
+
+fn draw_log(frame: &mut Frame, app: &App, area: Rect) {
+    let height = area.height.saturating_sub(2) as usize;
+    let end = app.log.len().saturating_sub(app.log_scroll);
+    let start = end.saturating_sub(height);
+    let lines: Vec<Line> = app.log[start..end]
+        .iter()
+        .map(|line| Line::from(line.as_str()))
+        .collect();
+    let title = if app.log_scroll > 0 {
+        format!("Log (scrolled up {})", app.log_scroll)
+    } else {
+        "Log".to_string()
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL)),
+        area,
+    );
+}
+
+fn item_line(item: &Item) -> ListItem<'_> {
+    let modifier = item.stat_modifier;
+    let bonuses: Vec<String> = [
+        ("HP", modifier.hp),
+        ("ATK", modifier.attack),
+        ("DEF", modifier.defense),
+        ("AGI", modifier.agility),
+    ]
+    .iter()
+    .filter(|(_, value)| *value != 0)
+    .map(|(stat, value)| format!("{} {:+}", stat, value))
+    .collect();
+    ListItem::new(format!(
+        "{} ({:?}) {}",
+        item.name,
+        item.item_type,
+        bonuses.join(" ")
+    ))
+}
+
+fn draw_inventory(frame: &mut Frame, app: &App, area: Rect) {
+    let player = match app.state.get_player() {
+        Ok(player) => player,
+        Err(e) => return draw_message(frame, "Inventory", &e.to_string(), area),
+    };
+    let [lists, details, log] = Layout::vertical([
+        Constraint::Min(5),
+        Constraint::Length(4),
+        Constraint::Length(5),
+    ])
+    .areas(area);
+    let [inventory_area, equipment_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(lists);
+
+    let inventory = player.inventory.iter().map(item_line).collect();
+    render_selection(
+        frame,
+        selectable_list("Inventory", inventory, app.pane == Pane::Inventory),
+        player.inventory.len(),
+        app.inventory_selection,
+        inventory_area,
+    );
+    let equipment = player.equipment.iter().map(item_line).collect();
+    render_selection(
+        frame,
+        selectable_list("Equipment", equipment, app.pane == Pane::Equipment),
+        player.equipment.len(),
+        app.equipment_selection,
+        equipment_area,
+    );
+
+    let (list, selection) = match app.pane {
+        Pane::Inventory => (&player.inventory, app.inventory_selection),
+        Pane::Equipment => (&player.equipment, app.equipment_selection),
+    };
+    let description = list
+        .get(selection.min(list.len().saturating_sub(1)))
+        .map(|item| item.description.as_str())
+        .unwrap_or("");
+    frame.render_widget(
+        Paragraph::new(description)
+            .wrap(Wrap { trim: true })
+            .block(Block::default().title("Details").borders(Borders::ALL)),
+        details,
+    );
+    draw_log(frame, app, log);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::config::Config;
+    use crate::game::fixtures;
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyCode;
+    use ratatui::Terminal;
+
+    /// Draw `app` on an 80x24 test terminal and return its rows.
+    fn render(app: &App) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(buffer.area.width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    fn shows(rows: &[String], text: &str) -> bool {
+        rows.iter().any(|row| row.contains(text))
+    }
+
+    #[test]
+    fn the_character_screen_lists_characters() {
+        let app = App::new(fixtures::arena(), None, &Config::default());
+        let rows = render(&app);
+        assert!(shows(&rows, "Characters"));
+        assert!(shows(&rows, "> Mira  HP 60"));
+        assert!(shows(&rows, "enter: play"));
+    }
+
+    #[test]
+    fn the_combat_screen_shows_both_fighters_and_skills() {
+        let mut app = App::new(fixtures::arena(), None, &Config::default());
+        app.handle_key(KeyCode::Enter);
+        let rows = render(&app);
+        assert!(shows(&rows, "Player: Mira"));
+        assert!(shows(&rows, "Enemy 1/"));
+        assert!(shows(&rows, "Goblin"));
+        assert!(shows(&rows, "HP 60/60"));
+        assert!(shows(&rows, "> Strike  (power 4"));
+        assert!(shows(&rows, "Playing as Mira."));
+    }
+
+    #[test]
+    fn the_inventory_screen_shows_both_lists() {
+        let mut app = App::new(fixtures::arena(), None, &Config::default());
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('i'));
+        let rows = render(&app);
+        assert!(shows(&rows, "Inventory"));
+        assert!(shows(&rows, "Equipment"));
+        assert!(shows(&rows, "Details"));
+    }
+}